@@ -1,6 +1,6 @@
-use crate::core::{Address, Block, ContractId, Ratio};
+use crate::core::{hash::Hash, Address, Block, ContractId, Hasher, Ratio};
 use crate::mpn::MpnConfig;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Clone)]
 pub struct BlockchainConfig {
@@ -19,4 +19,11 @@ pub struct BlockchainConfig {
     pub max_validator_commission: Ratio,
     pub teleport_log4_tree_size: u8,
     pub teleport_contract_id: ContractId,
+    /// Accept contracts with dummy verifier keys (Only on dev networks)
+    pub dummy_proofs: bool,
+    /// Block height -> header hash of blocks that can never be reverted
+    pub checkpoints: HashMap<u64, <Hasher as Hash>::Output>,
+    /// Maximum number of blocks an extension may roll back, and number of the
+    /// latest local deltas kept for each contract (Unlimited if not set)
+    pub max_reorg_depth: Option<u64>,
 }
@@ -29,6 +29,8 @@ pub enum BlockchainError {
     InvalidParentHash,
     #[error("merkle root invalid")]
     InvalidMerkleRoot,
+    #[error("state root invalid")]
+    InvalidStateRoot,
     #[error("transaction nonce invalid")]
     InvalidTransactionNonce,
     #[error("block timestamp is in past")]
@@ -115,10 +117,20 @@ pub enum BlockchainError {
     StateNotGiven,
     #[error("state invalid")]
     InvalidState,
+    #[error("contract has no missing delta at height {0}")]
+    DeltaNotMissing(u64),
     #[error("validator proof not given")]
     ValidatorProofNotGiven,
     #[error("epoch randomness can only be changed with vrf-proofs on 0th attempt")]
     RandomnessChangeNotPermitted,
     #[error("contract is not a token")]
     ContractNotToken,
+    #[error("block does not match the checkpoint")]
+    CheckpointMismatch,
+    #[error("reorgs deeper than {0} blocks are not allowed")]
+    ReorgTooDeep(u64),
+    #[error("stakes of the light client checkpoint are not proven")]
+    InvalidLightCheckpoint,
+    #[error("dummy proofs are not allowed on this network")]
+    DummyProofsNotAllowed,
 }
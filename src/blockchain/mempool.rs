@@ -1,11 +1,16 @@
 use super::{Blockchain, BlockchainError, TransactionMetadata, TransactionStats};
 use crate::core::{
-    Address, Amount, ContractId, GeneralAddress, GeneralTransaction, MpnDeposit, MpnWithdraw,
-    NonceGroup, TransactionAndDelta, TransactionKind,
+    Address, Amount, ContractDeposit, ContractId, ContractWithdraw, GeneralAddress,
+    GeneralTransaction, MpnDeposit, MpnWithdraw, NonceGroup, TransactionAndDelta, TransactionKind,
 };
 use crate::db::KvStore;
-use crate::zk::MpnTransaction;
+use crate::utils::Clock;
+use crate::zk::{MpnTransaction, ZkScalar};
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+/// MPN deposits/withdraws are remembered for an hour after being added
+const MPN_PAYMENT_REMEMBER_TIME: u32 = 3600;
 
 trait Nonced {
     fn nonce(&self) -> u32;
@@ -117,18 +122,33 @@ impl SingleMempool {
 
 #[derive(Clone, Debug)]
 pub struct Mempool {
+    clock: Arc<dyn Clock>,
     min_balance_per_tx: Amount,
+    max_txs_per_sender: usize,
     local_addrs: HashSet<GeneralAddress>,
     banned: HashMap<GeneralAddress, u32>,
     txs: HashMap<NonceGroup, SingleMempool>,
     min_fees: HashMap<TransactionKind, Amount>,
     rejected: HashMap<GeneralTransaction, TransactionStats>,
+    // MPN deposits/withdraws lose their MPN-side data when put in a block,
+    // so they are remembered for a while in order to be re-injected in case
+    // their blocks get rolled back. Deposits are found by their nonce group
+    // and nonce, withdraw payments don't carry their nonce group, so they
+    // are found by their calldata which commits to it.
+    mpn_deposits: HashMap<(NonceGroup, u32), (MpnDeposit, u32)>,
+    mpn_withdraws: HashMap<ZkScalar, (MpnWithdraw, u32)>,
 }
 
 impl Mempool {
-    pub fn new(min_balance_per_tx: Amount) -> Self {
+    pub fn new(
+        clock: Arc<dyn Clock>,
+        min_balance_per_tx: Amount,
+        max_txs_per_sender: usize,
+    ) -> Self {
         Self {
+            clock,
             min_balance_per_tx,
+            max_txs_per_sender,
             txs: Default::default(),
             min_fees: [
                 (TransactionKind::TransactionAndDelta, Amount(0)),
@@ -139,6 +159,8 @@ impl Mempool {
             .into_iter()
             .collect(),
             rejected: Default::default(),
+            mpn_deposits: Default::default(),
+            mpn_withdraws: Default::default(),
             banned: Default::default(),
             local_addrs: Default::default(),
         }
@@ -167,7 +189,8 @@ impl Mempool {
             })
             .collect()
     }
-    pub fn is_banned(&mut self, addr: GeneralAddress, now: u32) -> bool {
+    pub fn is_banned(&mut self, addr: GeneralAddress) -> bool {
+        let now = self.clock.now();
         if let Some(until) = self.banned.get(&addr) {
             if now < *until {
                 return true;
@@ -180,10 +203,10 @@ impl Mempool {
     pub fn refresh<K: KvStore, B: Blockchain<K>>(
         &mut self,
         blockchain: &B,
-        local_ts: u32,
         _max_time_alive: Option<u32>,
         _max_time_remember: Option<u32>,
     ) -> Result<(), BlockchainError> {
+        let local_ts = self.clock.now();
         let mpn_contract_id = blockchain.config().mpn_config.mpn_contract_id;
         let mut banned_ngs = vec![];
         for (ng, mempool) in self.txs.iter_mut() {
@@ -205,8 +228,26 @@ impl Mempool {
         for banned_ng in banned_ngs {
             self.txs.remove(&banned_ng);
         }
+        self.mpn_deposits
+            .retain(|_, (_, added)| local_ts.saturating_sub(*added) < MPN_PAYMENT_REMEMBER_TIME);
+        self.mpn_withdraws
+            .retain(|_, (_, added)| local_ts.saturating_sub(*added) < MPN_PAYMENT_REMEMBER_TIME);
         Ok(())
     }
+    /// Finds the full MPN deposit of a deposit payment seen in a block
+    pub fn find_mpn_deposit(&self, payment: &ContractDeposit) -> Option<MpnDeposit> {
+        self.mpn_deposits
+            .get(&(NonceGroup::MpnDeposit(payment.src.clone()), payment.nonce))
+            .filter(|(dep, _)| &dep.payment == payment)
+            .map(|(dep, _)| dep.clone())
+    }
+    /// Finds the full MPN withdraw of a withdraw payment seen in a block
+    pub fn find_mpn_withdraw(&self, payment: &ContractWithdraw) -> Option<MpnWithdraw> {
+        self.mpn_withdraws
+            .get(&payment.calldata)
+            .filter(|(wdr, _)| &wdr.payment == payment)
+            .map(|(wdr, _)| wdr.clone())
+    }
     pub fn chain_address_limit(&self, _addr: Address) -> usize {
         100
     }
@@ -215,14 +256,14 @@ impl Mempool {
         blockchain: &B,
         tx: GeneralTransaction,
         is_local: bool,
-        now: u32,
         meta: Option<TransactionMetadata>,
     ) -> Result<(), BlockchainError> {
+        let now = self.clock.now();
         if is_local {
             self.local_addrs.insert(tx.nonce_group().address());
         }
 
-        if !is_local && self.is_banned(tx.sender(), now) {
+        if !is_local && self.is_banned(tx.sender()) {
             return Ok(());
         }
 
@@ -315,12 +356,12 @@ impl Mempool {
             }
         };
 
-        // Allow 1tx in mempool per Ziesha
-        // Min: 1 Max: 1000
+        // Allow 1tx in mempool per `min_balance_per_tx`
+        // Min: 1 Max: `max_txs_per_sender`
         let limit = std::cmp::max(
             std::cmp::min(
                 Into::<u64>::into(ziesha_balance) / self.min_balance_per_tx.0,
-                1000,
+                self.max_txs_per_sender as u64,
             ),
             1,
         ) as usize;
@@ -332,6 +373,21 @@ impl Mempool {
 
         if is_local || all.len() < limit {
             all.insert(tx.clone(), TransactionStats::new(is_local, now, meta), now);
+            match tx {
+                GeneralTransaction::MpnDeposit(dep) => {
+                    self.mpn_deposits.insert(
+                        (
+                            NonceGroup::MpnDeposit(dep.payment.src.clone()),
+                            dep.payment.nonce,
+                        ),
+                        (dep, now),
+                    );
+                }
+                GeneralTransaction::MpnWithdraw(wdr) => {
+                    self.mpn_withdraws.insert(wdr.payment.calldata, (wdr, now));
+                }
+                _ => {}
+            }
         }
         Ok(())
     }
@@ -413,6 +469,19 @@ impl Mempool {
     pub fn len(&self) -> usize {
         self.txs.values().map(|c| c.len()).sum()
     }
+    /// Removes all transactions, returns the number of removed transactions
+    pub fn clear(&mut self) -> usize {
+        let removed = self.len();
+        self.txs.clear();
+        removed
+    }
+    pub fn len_by_kind(&self) -> HashMap<TransactionKind, usize> {
+        let mut lens: HashMap<TransactionKind, usize> = Default::default();
+        for (ng, mempool) in self.txs.iter() {
+            *lens.entry(ng.kind()).or_default() += mempool.len();
+        }
+        lens
+    }
 }
 
 #[cfg(test)]
@@ -421,6 +490,7 @@ mod tests {
     use crate::blockchain::KvStoreChain;
     use crate::core::Money;
     use crate::db::RamKvStore;
+    use crate::utils::{MockClock, SystemClock};
     use crate::wallet::TxBuilder;
 
     fn dummy_tx(wallet: &TxBuilder, nonce: u32) -> GeneralTransaction {
@@ -443,9 +513,9 @@ mod tests {
         let abc = TxBuilder::new(&Vec::from("ABC"));
 
         for i in 0..5 {
-            let mut mempool = Mempool::new(Amount(1));
+            let mut mempool = Mempool::new(Arc::new(SystemClock), Amount(1), 1000);
             mempool
-                .add_tx(&chain, dummy_tx(&abc, i), false, 0, None)
+                .add_tx(&chain, dummy_tx(&abc, i), false, None)
                 .unwrap();
 
             let snapshot = mempool.all().collect::<Vec<_>>();
@@ -463,44 +533,134 @@ mod tests {
         .unwrap();
         let abc = TxBuilder::new(&Vec::from("ABC"));
         let other = TxBuilder::new(&Vec::from("DELEGATOR"));
-        let mut mempool = Mempool::new(Amount(1));
+        let mut mempool = Mempool::new(Arc::new(SystemClock), Amount(1), 1000);
 
         mempool
-            .add_tx(&chain, dummy_tx(&abc, 1), false, 0, None)
+            .add_tx(&chain, dummy_tx(&abc, 1), false, None)
             .unwrap();
         assert_eq!(mempool.all().collect::<Vec<_>>().len(), 1);
         mempool
-            .add_tx(&chain, dummy_tx(&abc, 2), false, 0, None)
+            .add_tx(&chain, dummy_tx(&abc, 2), false, None)
             .unwrap();
         assert_eq!(mempool.all().collect::<Vec<_>>().len(), 2);
         mempool
-            .add_tx(&chain, dummy_tx(&abc, 4), false, 0, None)
+            .add_tx(&chain, dummy_tx(&abc, 4), false, None)
             .unwrap();
         assert_eq!(mempool.all().collect::<Vec<_>>().len(), 2);
         mempool
-            .add_tx(&chain, dummy_tx(&abc, 3), false, 0, None)
+            .add_tx(&chain, dummy_tx(&abc, 3), false, None)
             .unwrap();
         assert_eq!(mempool.all().collect::<Vec<_>>().len(), 3);
         mempool
-            .add_tx(&chain, dummy_tx(&abc, 4), false, 0, None)
+            .add_tx(&chain, dummy_tx(&abc, 4), false, None)
             .unwrap();
         assert_eq!(mempool.all().collect::<Vec<_>>().len(), 4);
 
         mempool
-            .add_tx(&chain, dummy_tx(&other, 10), false, 0, None)
+            .add_tx(&chain, dummy_tx(&other, 10), false, None)
             .unwrap();
         assert_eq!(mempool.all().collect::<Vec<_>>().len(), 4);
         mempool
-            .add_tx(&chain, dummy_tx(&other, 1), false, 0, None)
+            .add_tx(&chain, dummy_tx(&other, 1), false, None)
             .unwrap();
         assert_eq!(mempool.all().collect::<Vec<_>>().len(), 5);
         mempool
-            .add_tx(&chain, dummy_tx(&other, 3), false, 0, None)
+            .add_tx(&chain, dummy_tx(&other, 3), false, None)
             .unwrap();
         assert_eq!(mempool.all().collect::<Vec<_>>().len(), 5);
         mempool
-            .add_tx(&chain, dummy_tx(&other, 2), false, 0, None)
+            .add_tx(&chain, dummy_tx(&other, 2), false, None)
             .unwrap();
         assert_eq!(mempool.all().collect::<Vec<_>>().len(), 6);
     }
+
+    #[test]
+    fn test_mempool_ban_expiry() {
+        let chain = KvStoreChain::new(
+            RamKvStore::new(),
+            crate::config::blockchain::get_test_blockchain_config(),
+        )
+        .unwrap();
+        let abc = TxBuilder::new(&Vec::from("ABC"));
+        let clock = MockClock::new(1000);
+        let mut mempool = Mempool::new(Arc::new(clock.clone()), Amount(1), 1000);
+
+        mempool
+            .add_tx(&chain, dummy_tx(&abc, 1), false, None)
+            .unwrap();
+        assert_eq!(mempool.len(), 1);
+
+        // Sender is banned after 10 minutes without any execution
+        clock.advance(601);
+        mempool.refresh(&chain, None, None).unwrap();
+        assert_eq!(mempool.len(), 0);
+        mempool
+            .add_tx(&chain, dummy_tx(&abc, 1), false, None)
+            .unwrap();
+        assert_eq!(mempool.len(), 0);
+
+        // Ban is lifted after 20 minutes
+        clock.advance(1200);
+        mempool
+            .add_tx(&chain, dummy_tx(&abc, 1), false, None)
+            .unwrap();
+        assert_eq!(mempool.len(), 1);
+    }
+
+    #[test]
+    fn test_mempool_remembers_mpn_payments() {
+        let chain = KvStoreChain::new(
+            RamKvStore::new(),
+            crate::config::blockchain::get_test_blockchain_config(),
+        )
+        .unwrap();
+        let abc = TxBuilder::new(&Vec::from("ABC"));
+        let clock = MockClock::new(1000);
+        let mut mempool = Mempool::new(Arc::new(clock.clone()), Amount(1), 1000);
+
+        let deposit = abc.deposit_mpn(
+            "".into(),
+            chain.config().mpn_config.mpn_contract_id,
+            abc.get_mpn_address(),
+            1,
+            Money::ziesha(10),
+            Money::ziesha(0),
+        );
+        mempool
+            .add_tx(&chain, deposit.clone().into(), false, None)
+            .unwrap();
+        assert_eq!(
+            mempool
+                .find_mpn_deposit(&deposit.payment)
+                .map(|d| d.mpn_address),
+            Some(deposit.mpn_address.clone())
+        );
+        let mut other_nonce = deposit.payment.clone();
+        other_nonce.nonce += 1;
+        assert!(mempool.find_mpn_deposit(&other_nonce).is_none());
+
+        let withdraw = abc.withdraw_mpn(
+            "".into(),
+            chain.config().mpn_config.mpn_contract_id,
+            1,
+            Money::ziesha(5),
+            Money::ziesha(0),
+            abc.get_address(),
+        );
+        mempool
+            .add_tx(&chain, withdraw.clone().into(), false, None)
+            .unwrap();
+        assert_eq!(
+            mempool
+                .find_mpn_withdraw(&withdraw.payment)
+                .map(|w| w.mpn_withdraw_nonce),
+            Some(1)
+        );
+
+        // Payments are forgotten after an hour
+        clock.advance(3600);
+        mempool.refresh(&chain, None, None).unwrap();
+        assert!(mempool.find_mpn_deposit(&deposit.payment).is_none());
+        assert!(mempool.find_mpn_withdraw(&withdraw.payment).is_none());
+    }
 }
@@ -13,8 +13,8 @@ use crate::core::{
     TransactionAndDelta, TransactionData, Undelegation, UndelegationId, ValidatorProof, Vrf,
     ZkHasher as CoreZkHasher,
 };
-use crate::crypto::VerifiableRandomFunction;
-use crate::db::{keys, KvStore, RamMirrorKvStore, WriteOp};
+use crate::crypto::{sparse_merkle, VerifiableRandomFunction};
+use crate::db::{keys, KvStore, RamMirrorKvStore, StringKey, WriteOp};
 
 use crate::wallet::TxBuilder;
 use crate::zk;
@@ -55,6 +55,19 @@ impl TransactionStats {
     }
 }
 
+/// Proof of a value of a state key against the `state_root` of a header
+pub type StateProof = sparse_merkle::SparseMerkleProof<Hasher>;
+
+/// Update of a contract whose delta was not given in its block. The local
+/// state of the contract stays behind until the delta is fetched from peers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissingDelta {
+    /// Block of the update, whose rollback also undoes the delta once applied
+    pub block: u64,
+    /// State of the contract right after the update
+    pub next_state: zk::ZkCompressedState,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct ZkCompressedStateChange {
     prev_state: zk::ZkCompressedState,
@@ -108,6 +121,9 @@ pub trait Blockchain<K: KvStore> {
 
     fn db_checksum(&self) -> Result<String, BlockchainError>;
 
+    fn get_state_root(&self) -> Result<<Hasher as Hash>::Output, BlockchainError>;
+    fn get_state_proof(&self, key: StringKey) -> Result<StateProof, BlockchainError>;
+
     fn get_token(&self, token_id: ContractId) -> Result<Option<Token>, BlockchainError>;
 
     fn get_balance(&self, addr: Address, token_id: ContractId) -> Result<Amount, BlockchainError>;
@@ -150,6 +166,16 @@ pub trait Blockchain<K: KvStore> {
         &self,
         contract_id: ContractId,
     ) -> Result<ContractAccount, BlockchainError>;
+    /// Local heights of the contracts whose local states are behind, since
+    /// the deltas of some of their updates are missing
+    fn get_outdated_heights(&self) -> Result<HashMap<ContractId, u64>, BlockchainError>;
+    /// Applies the missing deltas of a contract, starting from its local
+    /// height, and marks the contract as available once it's up to date
+    fn update_contract_state(
+        &mut self,
+        contract_id: ContractId,
+        deltas: &[zk::ZkDeltaPairs],
+    ) -> Result<(), BlockchainError>;
     fn read_state(
         &self,
         contract_id: ContractId,
@@ -157,8 +183,10 @@ pub trait Blockchain<K: KvStore> {
     ) -> Result<zk::ZkScalar, BlockchainError>;
     fn next_reward(&self) -> Result<Amount, BlockchainError>;
     fn will_extend(&self, from: u64, headers: &[Header]) -> Result<bool, BlockchainError>;
-    fn extend(&mut self, from: u64, blocks: &[Block]) -> Result<(), BlockchainError>;
-    fn rollback(&mut self) -> Result<(), BlockchainError>;
+    /// Returns the blocks that got rolled back in order to extend the chain
+    fn extend(&mut self, from: u64, blocks: &[Block]) -> Result<Vec<Block>, BlockchainError>;
+    /// Returns the removed block
+    fn rollback(&mut self) -> Result<Block, BlockchainError>;
     fn draft_block(
         &self,
         timestamp: u32,
@@ -240,7 +268,71 @@ impl<K: KvStore> KvStoreChain<K> {
     }
 
     fn apply_block(&mut self, block: &Block) -> Result<(), BlockchainError> {
-        ops::apply_block(self, block)
+        ops::apply_block(self, block, false)
+    }
+
+    fn get_outdated_contracts(&self) -> Result<Vec<ContractId>, BlockchainError> {
+        let mut contracts = Vec::new();
+        for (k, _) in self
+            .database
+            .pairs(keys::MissingDeltaDbKey::any_prefix().into())?
+            .into_iter()
+        {
+            let contract_id = keys::MissingDeltaDbKey::try_from(k)?.contract_id;
+            if contracts.last() != Some(&contract_id) {
+                contracts.push(contract_id);
+            }
+        }
+        Ok(contracts)
+    }
+
+    fn is_outdated(&self, contract_id: ContractId) -> Result<bool, BlockchainError> {
+        Ok(self
+            .database
+            .pairs(keys::MissingDeltaDbKey::prefix(&contract_id).into())?
+            .into_iter()
+            .next()
+            .is_some())
+    }
+
+    fn update_state_tree(
+        &mut self,
+        changes: &[WriteOp],
+    ) -> Result<<Hasher as Hash>::Output, BlockchainError> {
+        ops::update_state_tree(self, changes)
+    }
+
+    fn get_state_node(
+        &self,
+        depth: usize,
+        path: &<Hasher as Hash>::Output,
+    ) -> Result<<Hasher as Hash>::Output, BlockchainError> {
+        Ok(match self.database.get(keys::state_node(depth, path))? {
+            Some(b) => b.try_into()?,
+            None => Default::default(),
+        })
+    }
+
+    fn validate_headers(&self, from: u64, headers: &[Header]) -> Result<(), BlockchainError> {
+        ops::validate_headers(self, from, headers)
+    }
+
+    fn check_checkpoint(&self, header: &Header) -> Result<(), BlockchainError> {
+        if let Some(hash) = self.config.checkpoints.get(&header.number) {
+            if *hash != header.hash() {
+                return Err(BlockchainError::CheckpointMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    fn check_reorg_depth(&self, from: u64) -> Result<(), BlockchainError> {
+        if let Some(max_depth) = self.config.max_reorg_depth {
+            if self.get_height()?.saturating_sub(from) > max_depth {
+                return Err(BlockchainError::ReorgTooDeep(max_depth));
+            }
+        }
+        Ok(())
     }
 }
 
@@ -250,6 +342,21 @@ impl<K: KvStore> Blockchain<K> for KvStoreChain<K> {
             self.database.pairs("".into())?.checksum::<Hasher>()?,
         ))
     }
+    fn get_state_root(&self) -> Result<<Hasher as Hash>::Output, BlockchainError> {
+        self.get_state_node(0, &Default::default())
+    }
+    fn get_state_proof(&self, key: StringKey) -> Result<StateProof, BlockchainError> {
+        let path = Hasher::hash(key.0.as_bytes());
+        let mut siblings = Vec::new();
+        for depth in (1..=sparse_merkle::SPARSE_MERKLE_DEPTH).rev() {
+            let sibling =
+                self.get_state_node(depth, &sparse_merkle::sibling_path::<Hasher>(&path, depth))?;
+            if sibling != <Hasher as Hash>::Output::default() {
+                siblings.push((depth as u16, sibling));
+            }
+        }
+        Ok(StateProof { siblings })
+    }
     fn get_header(&self, index: u64) -> Result<Header, BlockchainError> {
         if index >= self.get_height()? {
             return Err(BlockchainError::BlockNotFound);
@@ -274,7 +381,7 @@ impl<K: KvStore> Blockchain<K> for KvStoreChain<K> {
         })
     }
 
-    fn rollback(&mut self) -> Result<(), BlockchainError> {
+    fn rollback(&mut self) -> Result<Block, BlockchainError> {
         ops::rollback(self)
     }
 
@@ -304,6 +411,25 @@ impl<K: KvStore> Blockchain<K> for KvStoreChain<K> {
             .ok_or(BlockchainError::ContractNotFound)??)
     }
 
+    fn get_outdated_heights(&self) -> Result<HashMap<ContractId, u64>, BlockchainError> {
+        let mut heights = HashMap::new();
+        for contract_id in self.get_outdated_contracts()? {
+            heights.insert(
+                contract_id,
+                zk::KvStoreStateManager::<CoreZkHasher>::height_of(&self.database, contract_id)?,
+            );
+        }
+        Ok(heights)
+    }
+
+    fn update_contract_state(
+        &mut self,
+        contract_id: ContractId,
+        deltas: &[zk::ZkDeltaPairs],
+    ) -> Result<(), BlockchainError> {
+        ops::update_contract_state(self, contract_id, deltas)
+    }
+
     fn get_contract_balance(
         &self,
         contract_id: ContractId,
@@ -415,23 +541,10 @@ impl<K: KvStore> Blockchain<K> for KvStoreChain<K> {
         } else if from > self.get_height()? {
             return Err(BlockchainError::ExtendFromFuture);
         }
+        self.check_reorg_depth(from)?;
 
         let mut last_header = self.get_header(from - 1)?;
 
-        let sum_powers = headers
-            .iter()
-            .map(|h| {
-                h.proof_of_stake
-                    .proof
-                    .as_ref()
-                    .map(|p| p.power())
-                    .unwrap_or(1.)
-            })
-            .sum::<f64>();
-        if self.get_power_at(from)? + sum_powers <= self.get_power()? {
-            return Ok(false);
-        }
-
         for h in headers.iter() {
             let (last_epoch, last_slot) = self.epoch_slot(last_header.proof_of_stake.timestamp);
             let (h_epoch, h_slot) = self.epoch_slot(h.proof_of_stake.timestamp);
@@ -448,13 +561,52 @@ impl<K: KvStore> Blockchain<K> for KvStoreChain<K> {
                 return Err(BlockchainError::InvalidParentHash);
             }
 
+            self.check_checkpoint(h)?;
+
             last_header = h.clone();
         }
 
+        // Validating the headers may need a fork of the chain, so it is only
+        // done for the headers which would win
+        let sum_powers = headers
+            .iter()
+            .map(|h| {
+                h.proof_of_stake
+                    .proof
+                    .as_ref()
+                    .map(|p| p.power())
+                    .unwrap_or(1.)
+            })
+            .sum::<f64>();
+        if self.get_power_at(from)? + sum_powers <= self.get_power()? {
+            return Ok(false);
+        }
+
+        if self.config.check_validator {
+            self.validate_headers(from, headers)?;
+        }
+
         Ok(true)
     }
-    fn extend(&mut self, from: u64, blocks: &[Block]) -> Result<(), BlockchainError> {
-        let (ops, _) = self.isolated(|chain| {
+    fn extend(&mut self, from: u64, blocks: &[Block]) -> Result<Vec<Block>, BlockchainError> {
+        // Checkpointed blocks can't be rolled back, unless they are replaced
+        // with the very same blocks
+        let curr_height = self.get_height()?;
+        let new_height = from + blocks.len() as u64;
+        if self
+            .config
+            .checkpoints
+            .keys()
+            .any(|h| *h >= from && *h < curr_height && *h >= new_height)
+        {
+            return Err(BlockchainError::CheckpointMismatch);
+        }
+        for block in blocks.iter() {
+            self.check_checkpoint(&block.header)?;
+        }
+        self.check_reorg_depth(from)?;
+
+        let (ops, rolled_back) = self.isolated(|chain| {
             let curr_height = chain.get_height()?;
 
             if from == 0 {
@@ -463,19 +615,20 @@ impl<K: KvStore> Blockchain<K> for KvStoreChain<K> {
                 return Err(BlockchainError::ExtendFromFuture);
             }
 
+            let mut rolled_back = Vec::new();
             while chain.get_height()? > from {
-                chain.rollback()?;
+                rolled_back.insert(0, chain.rollback()?);
             }
 
             for block in blocks.iter() {
                 chain.apply_block(block)?;
             }
 
-            Ok(())
+            Ok(rolled_back)
         })?;
 
         self.database.update(&ops)?;
-        Ok(())
+        Ok(rolled_back)
     }
     fn get_height(&self) -> Result<u64, BlockchainError> {
         Ok(match self.database.get(keys::height())? {
@@ -732,6 +885,10 @@ impl<K: KvStore> Blockchain<K> for KvStoreChain<K> {
         Ok(result)
     }
     fn check_tx(&self, tx: &Transaction) -> Result<(), BlockchainError> {
+        // Blocks may lack contract deltas, but new transactions should not
+        if !tx.has_state() {
+            return Err(BlockchainError::StateNotGiven);
+        }
         let mut chain = self.fork_on_ram();
         chain.apply_tx(&tx, false)?;
 
@@ -1,8 +1,11 @@
 use super::*;
 
+/// Applies the block on the chain. The state root of the header is not
+/// checked for `draft` blocks, since it is not known yet.
 pub fn apply_block<K: KvStore>(
     chain: &mut KvStoreChain<K>,
     block: &Block,
+    draft: bool,
 ) -> Result<(), BlockchainError> {
     let (ops, _) = chain.isolated(|chain| {
         let curr_height = chain.get_height()?;
@@ -53,15 +56,9 @@ pub fn apply_block<K: KvStore>(
 
         if !is_genesis {
             if chain.config.check_validator {
+                // Validator proof is already checked by will_extend
                 if let Some(proof) = block.header.proof_of_stake.proof.clone() {
                     curr_pow += proof.power();
-                    if !chain.is_validator(
-                        block.header.proof_of_stake.timestamp,
-                        block.header.proof_of_stake.validator.clone(),
-                        proof,
-                    )? {
-                        return Err(BlockchainError::UnelectedValidator);
-                    }
                 } else {
                     return Err(BlockchainError::ValidatorProofNotGiven);
                 }
@@ -164,6 +161,19 @@ pub fn apply_block<K: KvStore>(
             }
         }
 
+        let state_changes = chain
+            .database
+            .to_ops()
+            .into_iter()
+            .filter(|op| match op {
+                WriteOp::Put(k, _) | WriteOp::Remove(k) => keys::is_state_key(k),
+            })
+            .collect::<Vec<_>>();
+        let state_root = chain.update_state_tree(&state_changes)?;
+        if !is_genesis && !draft && state_root != block.header.state_root {
+            return Err(BlockchainError::InvalidStateRoot);
+        }
+
         chain.database.update(&[
             WriteOp::Put(keys::power_at(curr_height + 1), curr_pow.into()),
             WriteOp::Put(keys::height(), (curr_height + 1).into()),
@@ -11,6 +11,9 @@ pub fn create_contract<K: KvStore>(
     if !contract.state_model.is_valid::<CoreZkHasher>() {
         return Err(BlockchainError::InvalidStateModel);
     }
+    if !chain.config().dummy_proofs && contract.has_dummy_verifier_keys() {
+        return Err(BlockchainError::DummyProofsNotAllowed);
+    }
     if let Some(token) = &contract.token {
         if !token.token.validate() {
             return Err(BlockchainError::TokenBadNameSymbol);
@@ -139,6 +142,16 @@ mod tests {
                 "CON-0x0001020304050607080900010203040506070809000102030405060708090001".into(),
                 contract.into(),
             ),
+            WriteOp::Put(
+                "S-0x0001020304050607080900010203040506070809000102030405060708090001-DLT-0000000000000001"
+                    .into(),
+                (&zk::ZkDeltaPairs::default()).into(),
+            ),
+            WriteOp::Put(
+                "S-0x0001020304050607080900010203040506070809000102030405060708090001-DRT-0000000000000001"
+                    .into(),
+                initial_state.clone().into(),
+            ),
             WriteOp::Put(
                 "S-0x0001020304050607080900010203040506070809000102030405060708090001-HGT".into(),
                 1u64.into(),
@@ -151,4 +164,50 @@ mod tests {
 
         assert_eq!(ops, expected_ops);
     }
+
+    #[test]
+    fn test_create_contract_dummy_keys() {
+        let abc = TxBuilder::new(&Vec::from("ABC"));
+        let contract_id: ContractId =
+            "0x0001020304050607080900010203040506070809000102030405060708090001"
+                .parse()
+                .unwrap();
+        let state_model = zk::ZkStateModel::Scalar;
+        let contract = zk::ZkContract {
+            token: None,
+            initial_state: zk::ZkCompressedState::empty::<crate::core::ZkHasher>(
+                state_model.clone(),
+            ),
+            state_model,
+            deposit_functions: vec![],
+            withdraw_functions: vec![],
+            functions: vec![zk::ZkSingleInputVerifierKey {
+                verifier_key: zk::ZkVerifierKey::Dummy,
+            }],
+        };
+
+        for dummy_proofs in [true, false] {
+            let mut chain = KvStoreChain::new(
+                RamKvStore::new(),
+                crate::config::blockchain::get_test_blockchain_config(),
+            )
+            .unwrap();
+            chain.config.dummy_proofs = dummy_proofs;
+            let res = chain.isolated(|chain| {
+                Ok(create_contract(
+                    chain,
+                    abc.get_address(),
+                    contract_id,
+                    &contract,
+                    &Some(Default::default()),
+                    Money::ziesha(0),
+                )?)
+            });
+            if dummy_proofs {
+                assert!(res.is_ok());
+            } else {
+                assert!(matches!(res, Err(BlockchainError::DummyProofsNotAllowed)));
+            }
+        }
+    }
 }
@@ -55,6 +55,27 @@ pub fn index_mpn_accounts<K: KvStore>(
     Ok(())
 }
 
+/// Records the update of a contract at `height` whose delta cannot be applied
+/// locally, either because it is not given or because the contract is already
+/// behind. The contract stays outdated until the deltas are fetched.
+pub fn mark_missing_delta<K: KvStore>(
+    chain: &mut KvStoreChain<K>,
+    contract_id: ContractId,
+    height: u64,
+    next_state: zk::ZkCompressedState,
+) -> Result<(), BlockchainError> {
+    let block = chain.get_height()?;
+    chain.database.update(&[WriteOp::Put(
+        keys::MissingDeltaDbKey {
+            contract_id,
+            height,
+        }
+        .into(),
+        MissingDelta { block, next_state }.into(),
+    )])?;
+    Ok(())
+}
+
 pub fn apply_tx<K: KvStore>(
     chain: &mut KvStoreChain<K>,
     tx: &Transaction,
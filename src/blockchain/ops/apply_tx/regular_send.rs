@@ -29,6 +29,7 @@ fn put_in_teleport_tree<K: KvStore>(
         ),
         height + 1,
     )?;
+    prune_contract_deltas(chain, cont_id, height + 1)?;
     Ok(())
 }
 
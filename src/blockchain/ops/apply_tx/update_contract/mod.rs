@@ -12,6 +12,12 @@ pub fn update_contract<K: KvStore>(
     updates: &[ContractUpdate],
     delta: &Option<zk::ZkDeltaPairs>,
 ) -> Result<(), BlockchainError> {
+    // MPN works are built on top of the local MPN state, so its deltas may
+    // never be missing
+    if delta.is_none() && *contract_id == chain.config().mpn_config.mpn_contract_id {
+        return Err(BlockchainError::StateNotGiven);
+    }
+
     let contract = chain.get_contract(*contract_id)?;
     let mut executor_fees = Vec::new();
 
@@ -96,6 +102,9 @@ pub fn update_contract<K: KvStore>(
             }
         };
 
+        if !chain.config().dummy_proofs && circuit.is_dummy() {
+            return Err(BlockchainError::DummyProofsNotAllowed);
+        }
         let mut cont_account = chain.get_contract_account(*contract_id)?;
         if !zk::check_proof(
             &circuit,
@@ -126,17 +135,28 @@ pub fn update_contract<K: KvStore>(
 
     let cont_account = chain.get_contract_account(*contract_id)?;
 
-    let delta = delta.clone().ok_or(BlockchainError::StateNotGiven)?;
+    let delta = match delta {
+        Some(delta) if !chain.is_outdated(*contract_id)? => delta,
+        _ => {
+            return mark_missing_delta(
+                chain,
+                *contract_id,
+                cont_account.height,
+                cont_account.compressed_state,
+            );
+        }
+    };
     if *contract_id == chain.config().mpn_config.mpn_contract_id {
-        index_mpn_accounts(chain, &delta)?;
+        index_mpn_accounts(chain, delta)?;
     }
 
     zk::KvStoreStateManager::<CoreZkHasher>::update_contract(
         &mut chain.database,
         *contract_id,
-        &delta,
+        delta,
         cont_account.height,
     )?;
+    prune_contract_deltas(chain, *contract_id, cont_account.height)?;
     if zk::KvStoreStateManager::<CoreZkHasher>::root(&mut chain.database, *contract_id)?
         != cont_account.compressed_state
     {
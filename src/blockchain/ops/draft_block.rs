@@ -28,6 +28,7 @@ pub fn draft_block<K: KvStore>(
             parent_hash: last_header.hash(),
             number: height as u64,
             block_root: Default::default(),
+            state_root: Default::default(),
             proof_of_stake: ProofOfStake {
                 timestamp,
                 validator: wallet.get_address(),
@@ -39,11 +40,14 @@ pub fn draft_block<K: KvStore>(
     blk.header.block_root = blk.merkle_tree().root();
 
     match chain.isolated(|chain| {
-        chain.apply_block(&blk)?; // Check if everything is ok
-        Ok(())
+        apply_block(chain, &blk, true)?; // Check if everything is ok
+        chain.get_state_root()
     }) {
         Err(BlockchainError::InsufficientMpnUpdates) => Ok(None),
         Err(e) => Err(e),
-        Ok(_) => Ok(Some(blk)),
+        Ok((_, state_root)) => {
+            blk.header.state_root = state_root;
+            Ok(Some(blk))
+        }
     }
 }
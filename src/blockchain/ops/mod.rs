@@ -15,3 +15,9 @@ mod rollback;
 pub use rollback::*;
 mod pay_validator_and_delegators;
 pub use pay_validator_and_delegators::*;
+mod validate_headers;
+pub use validate_headers::*;
+mod update_state_tree;
+pub use update_state_tree::*;
+mod update_contract_state;
+pub use update_contract_state::*;
@@ -1,13 +1,15 @@
 use super::*;
 
-pub fn rollback<K: KvStore>(chain: &mut KvStoreChain<K>) -> Result<(), BlockchainError> {
-    let (ops, _) = chain.isolated(|chain| {
+pub fn rollback<K: KvStore>(chain: &mut KvStoreChain<K>) -> Result<Block, BlockchainError> {
+    let (ops, block) = chain.isolated(|chain| {
         let height = chain.get_height()?;
 
         if height == 0 {
             return Err(BlockchainError::NoBlocksToRollback);
         }
 
+        let block = chain.get_block(height - 1)?;
+
         let rollback: Vec<WriteOp> = match chain.database.get(keys::rollback(height - 1))? {
             Some(b) => b.try_into()?,
             None => {
@@ -20,8 +22,8 @@ pub fn rollback<K: KvStore>(chain: &mut KvStoreChain<K>) -> Result<(), Blockchai
             .database
             .update(&[WriteOp::Remove(keys::rollback(height - 1))])?;
 
-        Ok(())
+        Ok(block)
     })?;
     chain.database.update(&ops)?;
-    Ok(())
+    Ok(block)
 }
@@ -0,0 +1,89 @@
+use super::*;
+
+/// Applies the missing deltas of an outdated contract, in order, starting
+/// from its local height. Each delta is checked against the state recorded
+/// when its update was applied, and nothing is applied if any of them fails.
+/// The undo ops of each delta are prepended to the rollback of the block
+/// containing the update, so that rolling back the block also rolls the local
+/// state back.
+pub fn update_contract_state<K: KvStore>(
+    chain: &mut KvStoreChain<K>,
+    contract_id: ContractId,
+    deltas: &[zk::ZkDeltaPairs],
+) -> Result<(), BlockchainError> {
+    let (ops, _) = chain.isolated(|chain| {
+        for delta in deltas {
+            let (ops, _) = chain.isolated(|chain| {
+                let height = zk::KvStoreStateManager::<CoreZkHasher>::height_of(
+                    &chain.database,
+                    contract_id,
+                )? + 1;
+                let missing_key: StringKey = keys::MissingDeltaDbKey {
+                    contract_id,
+                    height,
+                }
+                .into();
+                let missing: MissingDelta = chain
+                    .database
+                    .get(missing_key.clone())?
+                    .ok_or(BlockchainError::DeltaNotMissing(height))?
+                    .try_into()?;
+
+                if contract_id == chain.config().mpn_config.mpn_contract_id {
+                    index_mpn_accounts(chain, delta)?;
+                }
+                zk::KvStoreStateManager::<CoreZkHasher>::update_contract(
+                    &mut chain.database,
+                    contract_id,
+                    delta,
+                    height,
+                )?;
+                if zk::KvStoreStateManager::<CoreZkHasher>::root(&chain.database, contract_id)?
+                    != missing.next_state
+                {
+                    return Err(BlockchainError::InvalidState);
+                }
+
+                chain.database.update(&[WriteOp::Remove(missing_key)])?;
+                prune_contract_deltas(chain, contract_id, height)?;
+
+                let mut rollback = chain.database.rollback()?;
+                let block_rollback: Vec<WriteOp> = chain
+                    .database
+                    .get(keys::rollback(missing.block))?
+                    .ok_or(BlockchainError::Inconsistency)?
+                    .try_into()?;
+                rollback.extend(block_rollback);
+                chain
+                    .database
+                    .update(&[WriteOp::Put(keys::rollback(missing.block), rollback.into())])?;
+                Ok(())
+            })?;
+            chain.database.update(&ops)?;
+        }
+        Ok(())
+    })?;
+    chain.database.update(&ops)?;
+    Ok(())
+}
+
+/// Only the latest `max_reorg_depth` local deltas of a contract are kept, so
+/// the oldest one is forgotten whenever the contract reaches a new `height`.
+/// Forgetting is a part of the update, so rolling it back brings the delta
+/// back too.
+pub fn prune_contract_deltas<K: KvStore>(
+    chain: &mut KvStoreChain<K>,
+    contract_id: ContractId,
+    height: u64,
+) -> Result<(), BlockchainError> {
+    if let Some(depth) = chain.config.max_reorg_depth {
+        if height > depth {
+            zk::KvStoreStateManager::<CoreZkHasher>::forget_delta(
+                &mut chain.database,
+                contract_id,
+                height - depth,
+            )?;
+        }
+    }
+    Ok(())
+}
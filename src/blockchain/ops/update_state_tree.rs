@@ -0,0 +1,46 @@
+use super::*;
+
+/// Applies the changes of the state keys on the state tree and returns the
+/// new state root
+pub fn update_state_tree<K: KvStore>(
+    chain: &mut KvStoreChain<K>,
+    changes: &[WriteOp],
+) -> Result<<Hasher as Hash>::Output, BlockchainError> {
+    for change in changes.iter() {
+        let (key, value) = match change {
+            WriteOp::Put(k, v) => (k, Some(v)),
+            WriteOp::Remove(k) => (k, None),
+        };
+        let path = Hasher::hash(key.0.as_bytes());
+        let mut curr = value
+            .map(|v| sparse_merkle::leaf_hash::<Hasher>(&path, &v.0))
+            .unwrap_or_default();
+        let mut ops = Vec::new();
+        for depth in (1..=sparse_merkle::SPARSE_MERKLE_DEPTH).rev() {
+            ops.push(state_node_op(depth, &path, curr));
+            let sibling = chain
+                .get_state_node(depth, &sparse_merkle::sibling_path::<Hasher>(&path, depth))?;
+            curr = if sparse_merkle::path_bit(&path, depth - 1) {
+                sparse_merkle::merge_hash::<Hasher>(&sibling, &curr)
+            } else {
+                sparse_merkle::merge_hash::<Hasher>(&curr, &sibling)
+            };
+        }
+        ops.push(state_node_op(0, &path, curr));
+        chain.database.update(&ops)?;
+    }
+    chain.get_state_root()
+}
+
+fn state_node_op(
+    depth: usize,
+    path: &<Hasher as Hash>::Output,
+    hash: <Hasher as Hash>::Output,
+) -> WriteOp {
+    let key = keys::state_node(depth, path);
+    if hash == <Hasher as Hash>::Output::default() {
+        WriteOp::Remove(key)
+    } else {
+        WriteOp::Put(key, hash.into())
+    }
+}
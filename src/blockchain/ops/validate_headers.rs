@@ -0,0 +1,86 @@
+use super::*;
+
+/// Checks the validator-proofs of a chain of headers which is going to be
+/// built on top of block `from - 1`, without needing the block bodies.
+pub fn validate_headers<K: KvStore>(
+    chain: &KvStoreChain<K>,
+    from: u64,
+    headers: &[Header],
+) -> Result<(), BlockchainError> {
+    if from < chain.get_height()? {
+        let mut fork = chain.fork_on_ram();
+        while fork.get_height()? > from {
+            fork.rollback()?;
+        }
+        validate_headers_on_tip(&fork, headers)
+    } else {
+        validate_headers_on_tip(chain, headers)
+    }
+}
+
+fn validate_headers_on_tip<K: KvStore>(
+    chain: &KvStoreChain<K>,
+    headers: &[Header],
+) -> Result<(), BlockchainError> {
+    let mut last_header = chain.get_tip()?;
+    let mut randomness = chain.epoch_randomness()?;
+
+    for (i, h) in headers.iter().enumerate() {
+        let proof = h
+            .proof_of_stake
+            .proof
+            .clone()
+            .ok_or(BlockchainError::ValidatorProofNotGiven)?;
+        let last_epoch = chain.epoch_slot(last_header.proof_of_stake.timestamp).0;
+        let (epoch, slot) = chain.epoch_slot(h.proof_of_stake.timestamp);
+        let is_epoch_switch = epoch > last_epoch;
+
+        if i == 0 {
+            // The first header is built on our tip, so it can be checked completely
+            if !chain.is_validator(
+                h.proof_of_stake.timestamp,
+                h.proof_of_stake.validator.clone(),
+                proof.clone(),
+            )? {
+                return Err(BlockchainError::UnelectedValidator);
+            }
+        } else {
+            if is_epoch_switch && proof.attempt != 0 {
+                return Err(BlockchainError::RandomnessChangeNotPermitted);
+            }
+
+            // Stakes may change by the bodies of the previous blocks, so only
+            // the VRF itself is checked here. The staker must already be known
+            // though, otherwise the VRF could not be checked at all.
+            let staker_info = chain
+                .get_staker(h.proof_of_stake.validator.clone())?
+                .ok_or(BlockchainError::ValidatorNotRegistered)?;
+            let preimage = format!(
+                "{}-{}-{}-{}",
+                hex::encode(randomness),
+                epoch,
+                slot,
+                proof.attempt
+            );
+            if !Vrf::verify(
+                &staker_info.vrf_pub_key,
+                preimage.as_bytes(),
+                &proof.vrf_output,
+                &proof.vrf_proof,
+            ) {
+                return Err(BlockchainError::UnelectedValidator);
+            }
+        }
+
+        if is_epoch_switch {
+            // New randomness = H(H(tip) | VRF_out)
+            let mut preimage: Vec<u8> = last_header.hash().to_vec();
+            preimage.extend(Into::<Vec<u8>>::into(proof.vrf_output.clone()));
+            randomness = Hasher::hash(&preimage);
+        }
+
+        last_header = h.clone();
+    }
+
+    Ok(())
+}
@@ -47,3 +47,181 @@ fn test_contract_create_patch() {
 
     rollback_till_empty(&mut chain).unwrap();
 }
+
+#[test]
+fn test_contract_missing_delta() {
+    let miner = TxBuilder::new(&Vec::from("VALIDATOR"));
+    let alice = TxBuilder::new(&Vec::from("ABC"));
+    // Only the latest delta of each contract is kept
+    let mut conf = blockchain::get_test_blockchain_config();
+    conf.max_reorg_depth = Some(1);
+    let mut chain = KvStoreChain::new(db::RamKvStore::new(), conf.clone()).unwrap();
+    let mut outdated_chain = KvStoreChain::new(db::RamKvStore::new(), conf).unwrap();
+    let has_delta = |chain: &KvStoreChain<db::RamKvStore>, contract_id, height| {
+        zk::KvStoreStateManager::<CoreZkHasher>::delta_of(&chain.database, contract_id, height)
+            .is_ok()
+    };
+
+    let state_model = zk::ZkStateModel::List {
+        item_type: Box::new(zk::ZkStateModel::Scalar),
+        log4_size: 5,
+    };
+    let mut state = zk::ZkState {
+        rollbacks: vec![],
+        data: Default::default(),
+    };
+
+    let create_tx = alice.create_contract(
+        "".into(),
+        zk::ZkContract {
+            token: None,
+            state_model: state_model.clone(),
+            initial_state: state_model.compress::<CoreZkHasher>(&state.data).unwrap(),
+            deposit_functions: Vec::new(),
+            withdraw_functions: Vec::new(),
+            functions: vec![zk::ZkSingleInputVerifierKey {
+                verifier_key: zk::ZkVerifierKey::Dummy,
+            }],
+        },
+        state.data.clone(),
+        Money::ziesha(0),
+        Money::ziesha(0),
+        1,
+    );
+    let contract_id = ContractId::new(&create_tx.tx);
+
+    let update_tx =
+        |contract_id: ContractId, delta: &zk::ZkDeltaPairs, state: &zk::ZkState, nonce| {
+            alice.update_contract(
+                "".into(),
+                contract_id,
+                vec![ContractUpdate {
+                    circuit_id: 0,
+                    data: ContractUpdateData::FunctionCall {
+                        fee: Money::ziesha(0),
+                    },
+                    next_state: state_model.compress::<CoreZkHasher>(&state.data).unwrap(),
+                    prover: alice.get_address(),
+                    reward: Amount(0),
+                    proof: zk::ZkProof::Dummy(true),
+                }],
+                delta.clone(),
+                Money::ziesha(0),
+                nonce,
+            )
+        };
+    let strip_deltas = |block: &Block| {
+        let mut stripped = block.clone();
+        for tx in stripped.body.iter_mut() {
+            if let TransactionData::UpdateContract { delta, .. } = &mut tx.data {
+                *delta = None;
+            }
+        }
+        stripped
+    };
+
+    let draft = chain
+        .draft_block(100, &[create_tx], &miner, true)
+        .unwrap()
+        .unwrap();
+    chain.apply_block(&draft).unwrap();
+    outdated_chain.apply_block(&draft).unwrap();
+
+    let mut deltas = Vec::new();
+    for (i, nonce) in [2, 3].into_iter().enumerate() {
+        let delta = zk::ZkDeltaPairs(
+            [(
+                zk::ZkDataLocator(vec![i as u64]),
+                Some(zk::ZkScalar::from(123 + i as u64)),
+            )]
+            .into_iter()
+            .collect(),
+        );
+        state.apply_delta(&delta);
+        let draft = chain
+            .draft_block(
+                100 * nonce,
+                &[update_tx(contract_id, &delta, &state, nonce)],
+                &miner,
+                true,
+            )
+            .unwrap()
+            .unwrap();
+        chain.apply_block(&draft).unwrap();
+        let stripped = strip_deltas(&draft);
+        assert!(!stripped.body.iter().all(|tx| tx.has_state()));
+        outdated_chain.apply_block(&stripped).unwrap();
+        assert_eq!(chain.get_tip().unwrap(), outdated_chain.get_tip().unwrap());
+        deltas.push(delta);
+    }
+    assert!(chain.get_outdated_heights().unwrap().is_empty());
+    assert_eq!(
+        outdated_chain.get_outdated_heights().unwrap(),
+        [(contract_id, 1)].into_iter().collect()
+    );
+    assert!(has_delta(&chain, contract_id, 3));
+    assert!(!has_delta(&chain, contract_id, 2));
+
+    // Deltas not matching the state of the contract are rejected
+    let mut wrong_deltas = deltas.clone();
+    wrong_deltas[1]
+        .0
+        .insert(zk::ZkDataLocator(vec![4]), Some(zk::ZkScalar::from(321)));
+    assert!(matches!(
+        outdated_chain.update_contract_state(contract_id, &wrong_deltas),
+        Err(BlockchainError::InvalidState)
+    ));
+    assert_eq!(
+        outdated_chain.get_outdated_heights().unwrap(),
+        [(contract_id, 1)].into_iter().collect()
+    );
+
+    outdated_chain
+        .update_contract_state(contract_id, &deltas)
+        .unwrap();
+    assert!(outdated_chain.get_outdated_heights().unwrap().is_empty());
+    assert_eq!(
+        zk::KvStoreStateManager::<CoreZkHasher>::root(&outdated_chain.database, contract_id)
+            .unwrap(),
+        outdated_chain
+            .get_contract_account(contract_id)
+            .unwrap()
+            .compressed_state
+    );
+    assert!(matches!(
+        outdated_chain.update_contract_state(contract_id, &deltas[..1]),
+        Err(BlockchainError::DeltaNotMissing(4))
+    ));
+    assert!(has_delta(&outdated_chain, contract_id, 3));
+    assert!(!has_delta(&outdated_chain, contract_id, 2));
+
+    // Deltas of the MPN contract may not be missing
+    let mpn_contract_id = chain.config().mpn_config.mpn_contract_id;
+    let mut mpn_tx = update_tx(mpn_contract_id, &deltas[0], &state, 4).tx;
+    if let TransactionData::UpdateContract { delta, .. } = &mut mpn_tx.data {
+        *delta = None;
+    }
+    assert!(matches!(
+        chain.fork_on_ram().apply_tx(&mpn_tx, false),
+        Err(BlockchainError::StateNotGiven)
+    ));
+
+    // Rolling back undoes the fetched deltas too, and the contract is not
+    // marked as outdated again
+    chain.rollback().unwrap();
+    assert!(has_delta(&chain, contract_id, 2));
+    for height in [2, 1] {
+        outdated_chain.rollback().unwrap();
+        assert!(outdated_chain.get_outdated_heights().unwrap().is_empty());
+        let account = outdated_chain.get_contract_account(contract_id).unwrap();
+        assert_eq!(account.height, height);
+        assert_eq!(
+            zk::KvStoreStateManager::<CoreZkHasher>::root(&outdated_chain.database, contract_id)
+                .unwrap(),
+            account.compressed_state
+        );
+    }
+
+    rollback_till_empty(&mut chain).unwrap();
+    rollback_till_empty(&mut outdated_chain).unwrap();
+}
@@ -222,6 +222,149 @@ fn test_parent_hash_correctness_check() {
     rollback_till_empty(&mut chain).unwrap();
 }
 
+#[test]
+fn test_header_validator_proofs_check() {
+    let miner = TxBuilder::new(&Vec::from("VALIDATOR"));
+    let mut conf = blockchain::get_test_blockchain_config();
+    conf.check_validator = true;
+    let mut chain = KvStoreChain::new(db::RamKvStore::new(), conf).unwrap();
+
+    let blk1 = (1..100)
+        .find_map(|i| chain.draft_block(i * 5, &[], &miner, true).unwrap())
+        .unwrap();
+    assert!(chain.will_extend(1, &[blk1.header.clone()]).unwrap());
+
+    let mut no_proof = blk1.header.clone();
+    no_proof.proof_of_stake.proof = None;
+    assert!(matches!(
+        chain.will_extend(1, &[no_proof]),
+        Err(BlockchainError::ValidatorProofNotGiven)
+    ));
+
+    let mut wrong_validator = blk1.header.clone();
+    wrong_validator.proof_of_stake.validator =
+        TxBuilder::new(&Vec::from("VALIDATOR2")).get_address();
+    assert!(matches!(
+        chain.will_extend(1, &[wrong_validator]),
+        Err(BlockchainError::UnelectedValidator)
+    ));
+
+    let mut wrong_attempt = blk1.header.clone();
+    if let Some(proof) = wrong_attempt.proof_of_stake.proof.as_mut() {
+        proof.attempt += 1;
+    }
+    assert!(chain.will_extend(1, &[wrong_attempt]).is_err());
+
+    // Later headers from unknown stakers are rejected too
+    let mut fork = chain.fork_on_ram();
+    fork.extend(1, &[blk1.clone()]).unwrap();
+    let blk2 = (1..100)
+        .find_map(|i| fork.draft_block(500 + i * 5, &[], &miner, true).unwrap())
+        .unwrap();
+    assert!(chain
+        .will_extend(1, &[blk1.header.clone(), blk2.header.clone()])
+        .unwrap());
+    let mut unknown_staker = blk2.header.clone();
+    unknown_staker.proof_of_stake.validator = TxBuilder::new(&Vec::from("UNKNOWN")).get_address();
+    assert!(matches!(
+        chain.will_extend(1, &[blk1.header.clone(), unknown_staker]),
+        Err(BlockchainError::ValidatorNotRegistered)
+    ));
+    // Forks which can't win are not validated at all
+    chain.extend(1, &[blk1.clone()]).unwrap();
+    let mut weak = blk1.header.clone();
+    weak.proof_of_stake.validator = TxBuilder::new(&Vec::from("UNKNOWN")).get_address();
+    assert!(!chain.will_extend(1, &[weak]).unwrap());
+}
+
+#[test]
+fn test_checkpoints_are_enforced() {
+    let miner = TxBuilder::new(&Vec::from("VALIDATOR"));
+    let chain = KvStoreChain::new(
+        db::RamKvStore::new(),
+        blockchain::get_test_blockchain_config(),
+    )
+    .unwrap();
+    let mut fork1 = chain.fork_on_ram();
+    let blk1 = fork1.draft_block(100, &[], &miner, true).unwrap().unwrap();
+    fork1.extend(1, &[blk1.clone()]).unwrap();
+    let blk2 = fork1.draft_block(200, &[], &miner, true).unwrap().unwrap();
+    let blk1_alt = chain.draft_block(150, &[], &miner, true).unwrap().unwrap();
+
+    let mut conf = blockchain::get_test_blockchain_config();
+    conf.checkpoints.insert(1, blk1.header.hash());
+    let mut chain = KvStoreChain::new(db::RamKvStore::new(), conf).unwrap();
+
+    assert!(matches!(
+        chain.will_extend(1, &[blk1_alt.header.clone()]),
+        Err(BlockchainError::CheckpointMismatch)
+    ));
+    assert!(matches!(
+        chain.extend(1, &[blk1_alt]),
+        Err(BlockchainError::CheckpointMismatch)
+    ));
+
+    chain.extend(1, &[blk1.clone(), blk2.clone()]).unwrap();
+    assert_eq!(chain.get_height().unwrap(), 3);
+
+    // Checkpointed block can't be rolled back
+    assert!(matches!(
+        chain.extend(1, &[]),
+        Err(BlockchainError::CheckpointMismatch)
+    ));
+    chain.extend(1, &[blk1]).unwrap();
+    assert_eq!(chain.get_height().unwrap(), 2);
+}
+
+#[test]
+fn test_max_reorg_depth_is_enforced() {
+    let miner = TxBuilder::new(&Vec::from("VALIDATOR"));
+    let mut conf = blockchain::get_test_blockchain_config();
+    conf.max_reorg_depth = Some(1);
+    let mut chain = KvStoreChain::new(db::RamKvStore::new(), conf).unwrap();
+    let blk1_alt = chain.draft_block(150, &[], &miner, true).unwrap().unwrap();
+    let blk1 = chain.draft_block(100, &[], &miner, true).unwrap().unwrap();
+    chain.extend(1, &[blk1]).unwrap();
+    let blk2 = chain.draft_block(200, &[], &miner, true).unwrap().unwrap();
+    chain.extend(2, &[blk2]).unwrap();
+    assert_eq!(chain.get_height().unwrap(), 3);
+
+    // Rolling back two blocks is deeper than allowed
+    assert!(matches!(
+        chain.will_extend(1, &[blk1_alt.header.clone()]),
+        Err(BlockchainError::ReorgTooDeep(1))
+    ));
+    assert!(matches!(
+        chain.extend(1, &[blk1_alt]),
+        Err(BlockchainError::ReorgTooDeep(1))
+    ));
+    assert_eq!(chain.get_height().unwrap(), 3);
+    chain.extend(2, &[]).unwrap();
+    assert_eq!(chain.get_height().unwrap(), 2);
+}
+
+#[test]
+fn test_extend_returns_rolled_back_blocks() {
+    let miner = TxBuilder::new(&Vec::from("VALIDATOR"));
+    let mut chain = KvStoreChain::new(
+        db::RamKvStore::new(),
+        blockchain::get_test_blockchain_config(),
+    )
+    .unwrap();
+    let blk1 = chain.draft_block(100, &[], &miner, true).unwrap().unwrap();
+    assert!(chain.extend(1, &[blk1.clone()]).unwrap().is_empty());
+    let blk2 = chain.draft_block(200, &[], &miner, true).unwrap().unwrap();
+    assert!(chain.extend(2, &[blk2.clone()]).unwrap().is_empty());
+
+    let mut fork = chain.fork_on_ram();
+    fork.extend(1, &[]).unwrap();
+    let alt1 = fork.draft_block(150, &[], &miner, true).unwrap().unwrap();
+
+    assert_eq!(chain.extend(1, &[alt1.clone()]).unwrap(), vec![blk1, blk2]);
+    assert_eq!(chain.get_height().unwrap(), 2);
+    assert_eq!(chain.get_block(1).unwrap(), alt1);
+}
+
 #[test]
 fn test_merkle_root_check() {
     let alice = TxBuilder::new(&Vec::from("ABC"));
@@ -312,6 +455,47 @@ fn test_merkle_root_check() {
     rollback_till_empty(&mut chain).unwrap();
 }
 
+#[test]
+fn test_state_root_check() {
+    let alice = TxBuilder::new(&Vec::from("ABC"));
+    let miner = TxBuilder::new(&Vec::from("VALIDATOR"));
+    let mut chain = KvStoreChain::new(
+        db::RamKvStore::new(),
+        blockchain::get_test_blockchain_config(),
+    )
+    .unwrap();
+    let genesis_root = chain.get_state_root().unwrap();
+    let blk1 = chain
+        .draft_block(
+            100,
+            &[alice.create_transaction(
+                "".into(),
+                miner.get_address(),
+                Money::ziesha(100),
+                Money::ziesha(0),
+                1,
+            )],
+            &miner,
+            true,
+        )
+        .unwrap()
+        .unwrap();
+
+    let mut blk_wrong = blk1.clone();
+    blk_wrong.header.state_root = Default::default();
+    assert!(matches!(
+        chain.fork_on_ram().apply_block(&blk_wrong),
+        Err(BlockchainError::InvalidStateRoot)
+    ));
+
+    chain.extend(1, &[blk1.clone()]).unwrap();
+    assert_eq!(chain.get_state_root().unwrap(), blk1.header.state_root);
+    assert_ne!(blk1.header.state_root, genesis_root);
+
+    chain.rollback().unwrap();
+    assert_eq!(chain.get_state_root().unwrap(), genesis_root);
+}
+
 #[test]
 fn test_txs_cant_be_duplicated() {
     let miner = TxBuilder::new(&Vec::from("VALIDATOR"));
@@ -1033,7 +1217,7 @@ fn test_chain_should_rollback_applied_block() {
         .checksum::<Hasher>()
         .unwrap();
 
-    chain.rollback().unwrap();
+    assert_eq!(chain.rollback().unwrap(), draft);
 
     let rollbacked_checksum = chain
         .database
@@ -0,0 +1,39 @@
+use bazuka::config::chain_spec::ChainSpec;
+use std::path::{Path, PathBuf};
+
+fn is_json(path: &Path) -> bool {
+    path.extension().map(|ext| ext == "json").unwrap_or(false)
+}
+
+/// Reads a chain spec, as JSON if the file has a `.json` extension and as
+/// YAML otherwise, and checks its values.
+pub fn load_chain_spec(path: &Path) -> Result<ChainSpec, String> {
+    let data =
+        std::fs::read_to_string(path).map_err(|e| format!("Cannot read the chain spec: {}", e))?;
+    let spec: ChainSpec = if is_json(path) {
+        serde_json::from_str(&data).map_err(|e| e.to_string())
+    } else {
+        serde_yaml::from_str(&data).map_err(|e| e.to_string())
+    }
+    .map_err(|e| format!("Invalid chain spec: {}", e))?;
+    spec.validate()
+        .map_err(|e| format!("Invalid chain spec: {}", e))?;
+    Ok(spec)
+}
+
+pub fn spec_export(output: Option<PathBuf>) {
+    let spec = bazuka::config::blockchain::get_chain_spec();
+    match output {
+        Some(path) => {
+            let data = if is_json(&path) {
+                serde_json::to_string_pretty(&spec).unwrap()
+            } else {
+                serde_yaml::to_string(&spec).unwrap()
+            };
+            std::fs::write(&path, data).expect("Cannot write the chain spec!");
+        }
+        None => {
+            println!("{}", serde_yaml::to_string(&spec).unwrap());
+        }
+    }
+}
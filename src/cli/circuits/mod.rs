@@ -0,0 +1,3 @@
+pub mod setup;
+
+pub use setup::*;
@@ -0,0 +1,66 @@
+use crate::cli::chain::load_chain_spec;
+
+use bazuka::config::blockchain::{
+    get_chain_spec, SMALL_MPN_LOG4_BATCH_SIZE, SMALL_MPN_LOG4_TOKENS_TREE_SIZE,
+    SMALL_MPN_LOG4_TREE_SIZE,
+};
+use bazuka::mpn::setup::{MpnCircuitKind, MpnCircuitParams, MpnCircuitSetup};
+use colored::Colorize;
+use rand::rngs::OsRng;
+use std::path::PathBuf;
+
+/// Runs the Groth16 setup of the MPN circuits of the built-in network (Or of
+/// the given chain spec, or of `--small-mpn` dev chains) and writes the keys
+pub fn circuits_setup(output: PathBuf, small_mpn: bool, chain_spec: Option<PathBuf>) {
+    let mpn = match chain_spec.map(|path| load_chain_spec(&path)) {
+        Some(Ok(spec)) => spec.mpn,
+        Some(Err(e)) => {
+            println!("{} {}", "Error:".bright_red(), e);
+            return;
+        }
+        None => get_chain_spec().mpn,
+    };
+    std::fs::create_dir_all(&output).expect("Cannot create the output directory!");
+    for kind in MpnCircuitKind::ALL {
+        let circuit = if small_mpn {
+            MpnCircuitParams {
+                kind,
+                log4_tree_size: SMALL_MPN_LOG4_TREE_SIZE,
+                log4_token_tree_size: SMALL_MPN_LOG4_TOKENS_TREE_SIZE,
+                log4_batch_size: SMALL_MPN_LOG4_BATCH_SIZE,
+            }
+        } else {
+            MpnCircuitParams {
+                kind,
+                log4_tree_size: mpn.log4_tree_size,
+                log4_token_tree_size: mpn.log4_token_tree_size,
+                log4_batch_size: match kind {
+                    MpnCircuitKind::Deposit => mpn.log4_deposit_batch_size,
+                    MpnCircuitKind::Withdraw => mpn.log4_withdraw_batch_size,
+                    MpnCircuitKind::Update => mpn.log4_update_batch_size,
+                },
+            }
+        };
+        println!(
+            "{} {} circuit (log4 sizes: tree {}, token tree {}, batch {})...",
+            "Setting up:".bright_yellow(),
+            kind.name(),
+            circuit.log4_tree_size,
+            circuit.log4_token_tree_size,
+            circuit.log4_batch_size
+        );
+        let setup = MpnCircuitSetup::generate(circuit, &mut OsRng).expect("Circuit setup failed!");
+        setup.save(&output).expect("Cannot write the keys!");
+        println!(
+            "{} {}",
+            "Constraints:".bright_yellow(),
+            setup.num_constraints
+        );
+        println!(
+            "{} {}",
+            "Verifying key:".bright_yellow(),
+            hex::encode(bincode::serialize(&setup.verifying_key()).unwrap())
+        );
+    }
+    println!("Keys written to {}", output.display());
+}
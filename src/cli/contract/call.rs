@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use super::read_json;
+use crate::cli::BazukaConfig;
+use bazuka::client::{explorer::ExplorerDeltaPairs, BazukaClient, Limit, NodeError};
+use bazuka::common::*;
+use bazuka::core::{ContractId, ContractUpdate, Decimal, Money, NonceGroup};
+use bazuka::wallet::WalletCollection;
+use bazuka::zk;
+use serde::{Deserialize, Serialize};
+use tokio::try_join;
+
+/// Updates given to `contract call --update`, along with the state delta
+/// they make. The proofs commit to the prover and reward of each update, so
+/// they are given as they were proven.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ContractCall {
+    pub updates: Vec<ContractUpdate>,
+    pub delta: ExplorerDeltaPairs,
+}
+
+pub async fn call(
+    memo: Option<String>,
+    contract_id: ContractId,
+    update: PathBuf,
+    fee: Decimal,
+    conf: BazukaConfig,
+    mut wallet: WalletCollection,
+    wallet_path: &PathBuf,
+) {
+    let call: ContractCall = read_json(&update);
+    if call.updates.is_empty() {
+        panic!("No contract updates given!");
+    }
+    let delta = zk::ZkDeltaPairs::try_from(&call.delta).expect("Invalid state delta!");
+    let tx_builder = wallet.user(0).tx_builder();
+    let (req_loop, client) = BazukaClient::connect(
+        tx_builder.get_priv_key(),
+        conf.random_node(),
+        conf.network.clone(),
+        Some(Limit::default().time(2 * SECOND)),
+    );
+    try_join!(
+        async move {
+            let curr_nonce = client.get_account(tx_builder.get_address()).await?.nonce;
+
+            let new_nonce = wallet
+                .user(0)
+                .new_nonce(NonceGroup::TransactionAndDelta(tx_builder.get_address()))
+                .unwrap_or(curr_nonce + 1);
+            let tx = tx_builder.update_contract(
+                memo.unwrap_or_default(),
+                contract_id,
+                call.updates,
+                delta,
+                Money {
+                    amount: fee.to_amount(bazuka::config::UNIT_ZEROS),
+                    token_id: ContractId::Ziesha,
+                },
+                new_nonce,
+            );
+            if let Some(err) = client.transact(tx.clone().into()).await?.error {
+                println!("Error: {}", err);
+            } else {
+                wallet.user(0).add_tx(tx.clone().into());
+                wallet.save(wallet_path).unwrap();
+                println!("Sent");
+            }
+            Ok::<(), NodeError>(())
+        },
+        req_loop
+    )
+    .unwrap();
+}
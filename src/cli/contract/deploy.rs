@@ -0,0 +1,215 @@
+use std::path::{Path, PathBuf};
+
+use super::read_json;
+use crate::cli::BazukaConfig;
+use bazuka::client::{explorer::ExplorerDataPairs, BazukaClient, Limit, NodeError};
+use bazuka::common::*;
+use bazuka::core::{ContractId, Decimal, Money, NonceGroup, ZkHasher};
+use bazuka::wallet::WalletCollection;
+use bazuka::zk;
+use serde::{Deserialize, Serialize};
+use tokio::try_join;
+
+/// Contract given to `contract deploy --spec`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ContractSpec {
+    pub state_model: zk::ZkStateModel,
+    /// Initial data of the state, empty when not given
+    #[serde(default)]
+    pub initial_state: Option<ExplorerDataPairs>,
+    #[serde(default)]
+    pub token: Option<zk::ZkTokenContract>,
+    #[serde(default)]
+    pub deposit_functions: Vec<zk::ZkMultiInputVerifierKey>,
+    #[serde(default)]
+    pub withdraw_functions: Vec<zk::ZkMultiInputVerifierKey>,
+    #[serde(default)]
+    pub functions: Vec<zk::ZkSingleInputVerifierKey>,
+}
+
+impl ContractSpec {
+    /// Checks the spec and builds the contract along with its initial state
+    pub fn build(self) -> (zk::ZkContract, zk::ZkDataPairs) {
+        if !self.state_model.is_valid::<ZkHasher>() {
+            panic!("Invalid state model!");
+        }
+        let keys = self
+            .deposit_functions
+            .iter()
+            .chain(self.withdraw_functions.iter())
+            .map(|f| &f.verifier_key)
+            .chain(self.functions.iter().map(|f| &f.verifier_key))
+            .chain(
+                self.token
+                    .iter()
+                    .flat_map(|t| t.mint_functions.iter().map(|f| &f.verifier_key)),
+            );
+        for (i, key) in keys.enumerate() {
+            if !key.is_valid() {
+                panic!("Verifying key #{} is invalid!", i);
+            }
+        }
+        if let Some(token) = &self.token {
+            if !token.token.validate() {
+                panic!("Invalid token name or symbol!");
+            }
+        }
+        let state = match &self.initial_state {
+            Some(pairs) => zk::ZkDataPairs::try_from(pairs).expect("Invalid initial state!"),
+            None => Default::default(),
+        };
+        let initial_state = self
+            .state_model
+            .compress::<ZkHasher>(&state)
+            .expect("Initial state does not fit the state model!");
+        (
+            zk::ZkContract {
+                initial_state,
+                state_model: self.state_model,
+                deposit_functions: self.deposit_functions,
+                withdraw_functions: self.withdraw_functions,
+                functions: self.functions,
+                token: self.token,
+            },
+            state,
+        )
+    }
+}
+
+pub fn load_contract_spec(path: &Path) -> ContractSpec {
+    read_json(path)
+}
+
+pub async fn deploy(
+    memo: Option<String>,
+    spec: PathBuf,
+    deposit: Decimal,
+    fee: Decimal,
+    conf: BazukaConfig,
+    mut wallet: WalletCollection,
+    wallet_path: &PathBuf,
+) {
+    let (contract, state) = load_contract_spec(&spec).build();
+    let tx_builder = wallet.user(0).tx_builder();
+    let (req_loop, client) = BazukaClient::connect(
+        tx_builder.get_priv_key(),
+        conf.random_node(),
+        conf.network.clone(),
+        Some(Limit::default().time(2 * SECOND)),
+    );
+    try_join!(
+        async move {
+            let curr_nonce = client.get_account(tx_builder.get_address()).await?.nonce;
+
+            let new_nonce = wallet
+                .user(0)
+                .new_nonce(NonceGroup::TransactionAndDelta(tx_builder.get_address()))
+                .unwrap_or(curr_nonce + 1);
+            let tx = tx_builder.create_contract(
+                memo.unwrap_or_default(),
+                contract,
+                state,
+                Money {
+                    amount: deposit.to_amount(bazuka::config::UNIT_ZEROS),
+                    token_id: ContractId::Ziesha,
+                },
+                Money {
+                    amount: fee.to_amount(bazuka::config::UNIT_ZEROS),
+                    token_id: ContractId::Ziesha,
+                },
+                new_nonce,
+            );
+            let contract_id = ContractId::new(&tx.tx);
+            if let Some(err) = client.transact(tx.clone().into()).await?.error {
+                println!("Error: {}", err);
+            } else {
+                wallet.user(0).add_tx(tx.clone().into());
+                wallet.save(wallet_path).unwrap();
+                println!("Sent");
+                println!("Contract-Id: {}", contract_id);
+            }
+            Ok::<(), NodeError>(())
+        },
+        req_loop
+    )
+    .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Spec of a contract with a list of 4 scalars and a function whose
+    /// verifying key has `num_inputs` public inputs
+    fn spec(num_inputs: usize) -> serde_json::Value {
+        let fp = json!(vec![0u64; 6]);
+        let g1 = json!([fp, fp, false]);
+        let g2 = json!([[fp, fp], [fp, fp], false]);
+        json!({
+            "state_model": {"List": {"log4_size": 1, "item_type": "Scalar"}},
+            "functions": [{"verifier_key": {"Groth16": {
+                "alpha_g1": g1,
+                "beta_g1": g1,
+                "beta_g2": g2,
+                "gamma_g2": g2,
+                "delta_g1": g1,
+                "delta_g2": g2,
+                "ic": vec![g1.clone(); num_inputs + 1],
+            }}}],
+        })
+    }
+
+    fn build(spec: serde_json::Value) -> (zk::ZkContract, zk::ZkDataPairs) {
+        serde_json::from_value::<ContractSpec>(spec)
+            .unwrap()
+            .build()
+    }
+
+    #[test]
+    fn test_contract_spec_build() {
+        let mut spec = spec(5);
+        spec["initial_state"] = json!({"data": {"3": zk::ZkScalar::from(123).to_string()}});
+        let (contract, state) = build(spec);
+        assert_eq!(
+            state.0.get(&zk::ZkDataLocator(vec![3])),
+            Some(&zk::ZkScalar::from(123))
+        );
+        assert_eq!(
+            contract.initial_state,
+            contract.state_model.compress::<ZkHasher>(&state).unwrap()
+        );
+        assert_eq!(contract.functions.len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Verifying key #0 is invalid!")]
+    fn test_contract_spec_invalid_key() {
+        build(spec(4));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid state model!")]
+    fn test_contract_spec_invalid_state_model() {
+        let mut spec = spec(5);
+        spec["state_model"] = json!({"Struct": {"field_types": vec!["Scalar"; 100]}});
+        build(spec);
+    }
+
+    #[test]
+    #[should_panic(expected = "Initial state does not fit the state model!")]
+    fn test_contract_spec_initial_state_out_of_model() {
+        let mut spec = spec(5);
+        spec["initial_state"] = json!({"data": {"9": zk::ZkScalar::from(1).to_string()}});
+        build(spec);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid initial state!")]
+    fn test_contract_spec_invalid_initial_state() {
+        let mut spec = spec(5);
+        spec["initial_state"] = json!({"data": {"3": "not-a-scalar"}});
+        build(spec);
+    }
+}
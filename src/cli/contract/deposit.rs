@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use super::write_json;
+use crate::cli::BazukaConfig;
+use bazuka::client::{BazukaClient, Limit, NodeError};
+use bazuka::common::*;
+use bazuka::core::{ContractId, Decimal, Money};
+use bazuka::wallet::WalletCollection;
+use bazuka::zk::ZkScalar;
+use tokio::try_join;
+
+/// Signs a deposit to a contract. Deposits are not kept in the mempool, the
+/// signed deposit is written out for the executor of the contract, and its
+/// nonce is kept in the wallet so that the next deposits don't reuse it.
+#[allow(clippy::too_many_arguments)]
+pub async fn deposit(
+    memo: Option<String>,
+    contract_id: ContractId,
+    circuit_id: u32,
+    calldata: Option<ZkScalar>,
+    nonce: Option<u32>,
+    amount: Decimal,
+    token_id: Option<ContractId>,
+    fee: Decimal,
+    output: Option<PathBuf>,
+    conf: BazukaConfig,
+    mut wallet: WalletCollection,
+    wallet_path: &PathBuf,
+) {
+    let tx_builder = wallet.user(0).tx_builder();
+    let (req_loop, client) = BazukaClient::connect(
+        tx_builder.get_priv_key(),
+        conf.random_node(),
+        conf.network.clone(),
+        Some(Limit::default().time(2 * SECOND)),
+    );
+    let tkn = token_id.unwrap_or(ContractId::Ziesha);
+    try_join!(
+        async move {
+            let tkn_decimals = client
+                .get_token(tkn)
+                .await?
+                .token
+                .expect("Token not found!")
+                .decimals;
+            let nonce = match nonce {
+                Some(nonce) => nonce,
+                None => {
+                    let curr_nonce = client
+                        .get_deposit_nonce(tx_builder.get_address(), contract_id)
+                        .await?
+                        .nonce;
+                    wallet
+                        .user(0)
+                        .new_deposit_nonce(contract_id)
+                        .unwrap_or(curr_nonce + 1)
+                }
+            };
+            let deposit = tx_builder.deposit_contract(
+                memo.unwrap_or_default(),
+                contract_id,
+                circuit_id,
+                calldata.unwrap_or_default(),
+                nonce,
+                Money {
+                    amount: amount.to_amount(tkn_decimals),
+                    token_id: tkn,
+                },
+                Money {
+                    amount: fee.to_amount(bazuka::config::UNIT_ZEROS),
+                    token_id: ContractId::Ziesha,
+                },
+            );
+            wallet.user(0).add_deposit(&deposit);
+            wallet.save(wallet_path).unwrap();
+            write_json(&deposit, output);
+            Ok::<(), NodeError>(())
+        },
+        req_loop
+    )
+    .unwrap();
+}
@@ -0,0 +1,32 @@
+pub mod call;
+pub mod deploy;
+pub mod deposit;
+pub mod state;
+pub mod withdraw;
+
+pub use call::*;
+pub use deploy::*;
+pub use deposit::*;
+pub use state::*;
+pub use withdraw::*;
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::{Path, PathBuf};
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> T {
+    let data = std::fs::read_to_string(path).expect("Cannot read the file!");
+    serde_json::from_str(&data).expect("Invalid JSON file!")
+}
+
+/// Writes the value as JSON to the given file, or prints it when not given
+fn write_json<T: Serialize>(value: &T, output: Option<PathBuf>) {
+    let data = serde_json::to_string_pretty(value).unwrap();
+    match output {
+        Some(path) => {
+            std::fs::write(&path, data).expect("Cannot write the file!");
+        }
+        None => {
+            println!("{}", data);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use super::write_json;
+use crate::cli::BazukaConfig;
+use bazuka::client::{explorer::ExplorerDataPairs, BazukaClient, Limit, NodeError};
+use bazuka::common::*;
+use bazuka::config::blockchain::get_blockchain_config;
+use bazuka::core::ContractId;
+use bazuka::wallet::WalletCollection;
+use bazuka::zk;
+use serde::Serialize;
+use tokio::try_join;
+
+#[derive(Serialize)]
+struct ContractStateOutput {
+    height: u64,
+    state_model: zk::ZkStateModel,
+    state: ExplorerDataPairs,
+}
+
+/// Downloads the full state of a contract and writes it out. The state is
+/// checked against the compressed state of the contract account, which is
+/// proven against the headers synced in light mode from the node.
+pub async fn state(
+    contract_id: ContractId,
+    output: Option<PathBuf>,
+    conf: BazukaConfig,
+    mut wallet: WalletCollection,
+) {
+    let tx_builder = wallet.user(0).tx_builder();
+    let node = conf.random_node();
+    let (req_loop, client) = BazukaClient::connect(
+        tx_builder.get_priv_key(),
+        node,
+        conf.network.clone(),
+        Some(Limit::default().time(30 * SECOND)),
+    );
+    let client = client
+        .light(&get_blockchain_config(), vec![node])
+        .expect("Cannot start the light client!");
+    try_join!(
+        async move {
+            let state = client.download_contract_state(contract_id).await?;
+            write_json(
+                &ContractStateOutput {
+                    height: state.height,
+                    state_model: state.state_model,
+                    state: (&state.data.data).into(),
+                },
+                output,
+            );
+            Ok::<(), NodeError>(())
+        },
+        req_loop
+    )
+    .unwrap();
+}
@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use super::write_json;
+use crate::cli::BazukaConfig;
+use bazuka::client::{BazukaClient, Limit, NodeError};
+use bazuka::common::*;
+use bazuka::core::{Address, ContractId, Decimal, Money};
+use bazuka::wallet::WalletCollection;
+use bazuka::zk::ZkScalar;
+use tokio::try_join;
+
+/// Builds a withdrawal from a contract, to be included in an update by the
+/// executor of the contract. The contract is responsible for authorizing it
+/// through the calldata.
+#[allow(clippy::too_many_arguments)]
+pub async fn withdraw(
+    memo: Option<String>,
+    contract_id: ContractId,
+    circuit_id: u32,
+    calldata: Option<ZkScalar>,
+    amount: Decimal,
+    token_id: Option<ContractId>,
+    fee: Decimal,
+    to: Option<Address>,
+    output: Option<PathBuf>,
+    conf: BazukaConfig,
+    mut wallet: WalletCollection,
+) {
+    let tx_builder = wallet.user(0).tx_builder();
+    let (req_loop, client) = BazukaClient::connect(
+        tx_builder.get_priv_key(),
+        conf.random_node(),
+        conf.network.clone(),
+        Some(Limit::default().time(2 * SECOND)),
+    );
+    let tkn = token_id.unwrap_or(ContractId::Ziesha);
+    try_join!(
+        async move {
+            let tkn_decimals = client
+                .get_token(tkn)
+                .await?
+                .token
+                .expect("Token not found!")
+                .decimals;
+            let withdraw = tx_builder.withdraw_contract(
+                memo.unwrap_or_default(),
+                contract_id,
+                circuit_id,
+                calldata.unwrap_or_default(),
+                Money {
+                    amount: amount.to_amount(tkn_decimals),
+                    token_id: tkn,
+                },
+                Money {
+                    amount: fee.to_amount(bazuka::config::UNIT_ZEROS),
+                    token_id: ContractId::Ziesha,
+                },
+                to.unwrap_or_else(|| tx_builder.get_address()),
+            );
+            write_json(&withdraw, output);
+            Ok::<(), NodeError>(())
+        },
+        req_loop
+    )
+    .unwrap();
+}
@@ -0,0 +1,197 @@
+use super::{launch_node, BazukaConfig};
+
+use bazuka::{
+    blockchain::KvStoreChain,
+    client::{messages::SocialProfiles, NodeError, PeerAddress},
+    config::chain_spec::ChainSpec,
+    core::{hash::Hash, Hasher},
+    db::RamKvStore,
+    node::NodeOptions,
+    wallet::WalletCollection,
+};
+use bip39::Mnemonic;
+use colored::Colorize;
+use std::net::SocketAddr;
+
+/// Wallet of the i-th devnet node. Mnemonics are derived from the index so
+/// that the funded accounts are the same in every run.
+fn devnet_wallet(index: usize) -> WalletCollection {
+    let entropy = Hasher::hash(format!("devnet-{}", index).as_bytes());
+    let mnemonic = Mnemonic::from_entropy(&entropy[..16]).unwrap();
+    WalletCollection::create(&mut rand_mnemonic::thread_rng(), Some(mnemonic))
+}
+
+/// Address of the i-th devnet node. Peers are told apart by their IPs, so
+/// every node gets its own loopback IP.
+fn devnet_address(index: usize, base_port: u16) -> PeerAddress {
+    PeerAddress(SocketAddr::from((
+        [127, 0, 0, index as u8 + 1],
+        base_port + index as u16,
+    )))
+}
+
+/// Chain spec where the first `validators` nodes are staked in the genesis
+/// block, and every node's user wallet is funded
+fn devnet_spec(
+    wallets: &mut [WalletCollection],
+    validators: usize,
+    dummy_proofs: bool,
+) -> ChainSpec {
+    let validator_builders = wallets
+        .iter_mut()
+        .take(validators)
+        .map(|w| w.validator().tx_builder())
+        .collect::<Vec<_>>();
+    let user_builders = wallets
+        .iter_mut()
+        .map(|w| w.user(0).tx_builder())
+        .collect::<Vec<_>>();
+    let mut spec =
+        bazuka::config::blockchain::get_devnet_chain_spec(&validator_builders, &user_builders);
+    spec.mpn.dummy_proofs = dummy_proofs;
+    spec
+}
+
+/// Runs a node for each wallet, bootstrapped with all the other nodes
+async fn run_devnet(
+    spec: &ChainSpec,
+    addrs: &[PeerAddress],
+    wallets: Vec<WalletCollection>,
+) -> Result<(), NodeError> {
+    let mut blockchain_conf = spec
+        .blockchain_config()
+        .expect("Invalid devnet chain spec!");
+    blockchain_conf.max_reorg_depth = Some(NodeOptions::default().max_reorg_depth);
+    let node_futs = wallets.into_iter().enumerate().map(|(i, wallet)| {
+        let conf = BazukaConfig {
+            listen: addrs[i].0,
+            external: addrs[i],
+            bootstrap: addrs
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, a)| *a)
+                .collect(),
+            db: Default::default(),
+            network: spec.network.clone(),
+            mpn_workers: vec![],
+            mpn_worker_registry: None,
+            checkpoints: Default::default(),
+            admin: None,
+            node: Default::default(),
+            firewall: Default::default(),
+        };
+        launch_node(
+            KvStoreChain::new(RamKvStore::new(), blockchain_conf.clone()).unwrap(),
+            conf,
+            wallet,
+            SocialProfiles::default(),
+            Some(addrs[i]),
+            spec.network.clone(),
+            None,
+            Some(addrs[i].ip()),
+        )
+    });
+    futures::future::try_join_all(node_futs).await?;
+    Ok(())
+}
+
+/// Runs `nodes` in-memory nodes on local addresses, where the first
+/// `validators` of them are staked in the genesis block, and every node's user
+/// wallet is funded.
+pub async fn devnet(nodes: usize, validators: usize, base_port: u16, dummy_proofs: bool) {
+    assert!(
+        nodes > 0 && nodes < 255,
+        "A devnet needs between 1 and 254 nodes!"
+    );
+    assert!(
+        validators > 0 && validators <= nodes,
+        "Number of validators should be between 1 and the number of nodes!"
+    );
+
+    let mut wallets = (0..nodes).map(devnet_wallet).collect::<Vec<_>>();
+    let spec = devnet_spec(&mut wallets, validators, dummy_proofs);
+    let addrs = (0..nodes)
+        .map(|i| devnet_address(i, base_port))
+        .collect::<Vec<_>>();
+
+    println!("{} {}", "Network:".bright_yellow(), spec.network);
+    for (i, wallet) in wallets.iter_mut().enumerate() {
+        println!();
+        println!("{} {}", "Node:".bright_green(), i);
+        println!("{} http://{}", "Endpoint:".bright_yellow(), addrs[i]);
+        if i < validators {
+            println!(
+                "{} {}",
+                "Validator:".bright_yellow(),
+                wallet.validator().tx_builder().get_address()
+            );
+        }
+        println!(
+            "{} {}",
+            "User:".bright_yellow(),
+            wallet.user(0).tx_builder().get_address()
+        );
+        println!(
+            "{} {}",
+            "Mnemonic phrase:".bright_yellow(),
+            wallet.mnemonic()
+        );
+    }
+
+    run_devnet(&spec, &addrs, wallets).await.unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bazuka::client::{BazukaClient, Limit};
+    use bazuka::common::*;
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_devnet_nodes_see_each_other() {
+        let nodes = 3;
+        let mut wallets = (0..nodes).map(devnet_wallet).collect::<Vec<_>>();
+        let spec = devnet_spec(&mut wallets, 1, false);
+        let addrs = (0..nodes)
+            .map(|i| devnet_address(i, 38800))
+            .collect::<Vec<_>>();
+
+        let check = async {
+            loop {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                let mut all_connected = true;
+                for (i, addr) in addrs.iter().enumerate() {
+                    let (req_loop, client) = BazukaClient::connect(
+                        devnet_wallet(i).user(0).tx_builder().get_priv_key(),
+                        *addr,
+                        spec.network.clone(),
+                        Some(Limit::default().time(SECOND)),
+                    );
+                    let peers = tokio::select! {
+                        resp = client.peers() => resp.map(|r| r.peers).unwrap_or_default(),
+                        _ = req_loop => Vec::new(),
+                    };
+                    let expected = addrs
+                        .iter()
+                        .filter(|a| *a != addr)
+                        .cloned()
+                        .collect::<HashSet<_>>();
+                    all_connected &= peers.into_iter().collect::<HashSet<_>>() == expected;
+                }
+                if all_connected {
+                    break;
+                }
+            }
+        };
+
+        tokio::select! {
+            res = run_devnet(&spec, &addrs, wallets) => panic!("Devnet stopped: {:?}", res),
+            res = tokio::time::timeout(Duration::from_secs(60), check) => {
+                res.expect("Devnet nodes did not discover each other!");
+            }
+        }
+    }
+}
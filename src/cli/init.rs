@@ -1,4 +1,4 @@
-use super::{BazukaConfig, DEFAULT_PORT};
+use super::{BazukaConfig, BazukaConfigAdmin, DEFAULT_PORT};
 use bazuka::{client::PeerAddress, wallet::WalletCollection};
 use bip39::Mnemonic;
 use colored::Colorize;
@@ -44,7 +44,15 @@ pub async fn init(
                 external: external
                     .unwrap_or_else(|| PeerAddress(SocketAddr::from((public_ip, DEFAULT_PORT)))),
                 db: db.unwrap_or_else(|| home::home_dir().unwrap().join(Path::new(".bazuka"))),
+                network: super::default_network(),
                 mpn_workers: vec![],
+                mpn_worker_registry: super::default_mpn_worker_registry(),
+                checkpoints: Default::default(),
+                admin: Some(BazukaConfigAdmin::generate()),
+                #[cfg(feature = "node")]
+                node: Default::default(),
+                #[cfg(feature = "node")]
+                firewall: Default::default(),
             })
             .unwrap(),
         )
@@ -1,10 +1,14 @@
 #[cfg(feature = "node")]
 use {
     bazuka::blockchain::Blockchain,
-    bazuka::client::{messages::SocialProfiles, Limit, NodeRequest},
-    bazuka::common::*,
+    bazuka::client::{
+        messages::SocialProfiles, BazukaClient, Limit, NodeRequest, ADMIN_TOKEN_HEADER,
+    },
     bazuka::db::KvStore,
-    bazuka::node::{node_create, Firewall},
+    bazuka::mpn::prover::MpnProver,
+    bazuka::node::{node_create, Firewall, FirewallOptions, MpnWorkerRegistry, NodeOptions},
+    bazuka::utils::SystemClock,
+    hyper::client::HttpConnector,
     hyper::server::conn::AddrStream,
     hyper::service::{make_service_fn, service_fn},
     hyper::{Body, Client, Request, Response, Server, StatusCode},
@@ -15,12 +19,13 @@ use {
 #[cfg(feature = "client")]
 use {
     bazuka::client::{NodeError, PeerAddress},
-    bazuka::config,
     bazuka::core::{Address, ContractId, Decimal, GeneralAddress},
     bazuka::mpn::MpnWorker,
     bazuka::wallet::WalletCollection,
+    bazuka::zk::ZkScalar,
     serde::{Deserialize, Serialize},
-    std::net::SocketAddr,
+    std::collections::HashMap,
+    std::net::{IpAddr, SocketAddr},
     std::path::{Path, PathBuf},
     structopt::StructOpt,
     tokio::try_join,
@@ -33,19 +38,25 @@ use {
 };
 
 pub mod chain;
+pub mod circuits;
+pub mod contract;
 pub mod init;
 pub mod wallet;
 pub use init::*;
 
+#[cfg(feature = "node")]
+pub mod devnet;
+#[cfg(feature = "client")]
+pub mod mpn_worker;
 #[cfg(feature = "node")]
 pub mod node;
 
 #[cfg(feature = "client")]
 const DEFAULT_PORT: u16 = 8765;
+#[cfg(feature = "client")]
+const DEFAULT_ADMIN_PORT: u16 = 8766;
 const BAZUKA_NOT_INITILIZED: &str = "Bazuka is not initialized";
 
-const CURRENT_NETWORK: &str = "deruny-5";
-
 #[cfg(feature = "client")]
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BazukaConfigMpnWorker {
@@ -65,6 +76,32 @@ impl TryInto<MpnWorker> for BazukaConfigMpnWorker {
     }
 }
 
+#[cfg(feature = "client")]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BazukaConfigAdmin {
+    listen: SocketAddr,
+    token: String,
+}
+
+#[cfg(feature = "client")]
+impl BazukaConfigAdmin {
+    fn generate() -> Self {
+        Self {
+            listen: SocketAddr::from(([127, 0, 0, 1], DEFAULT_ADMIN_PORT)),
+            token: hex::encode(rand::random::<[u8; 32]>()),
+        }
+    }
+    #[cfg(feature = "node")]
+    fn validate(&self) -> Result<(), NodeError> {
+        if self.token.trim().is_empty() {
+            return Err(NodeError::InvalidNodeOptions(
+                "admin token should not be empty".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
 #[cfg(feature = "client")]
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BazukaConfig {
@@ -72,11 +109,86 @@ pub struct BazukaConfig {
     external: PeerAddress,
     bootstrap: Vec<PeerAddress>,
     db: PathBuf,
+    /// Name of the network the node and the CLI clients talk on
+    #[serde(
+        default = "default_network",
+        skip_serializing_if = "is_default_network"
+    )]
+    network: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     mpn_workers: Vec<BazukaConfigMpnWorker>,
+    /// File keeping the registered MPN workers and their statistics, they
+    /// are only kept in memory when not set
+    #[serde(
+        default = "default_mpn_worker_registry",
+        skip_serializing_if = "is_default_mpn_worker_registry"
+    )]
+    mpn_worker_registry: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    checkpoints: HashMap<u64, String>,
+    #[serde(default)]
+    admin: Option<BazukaConfigAdmin>,
+    /// Only the node options given by the user are kept in the config, the
+    /// rest are filled with their defaults when the node is started
+    #[cfg(feature = "node")]
+    #[serde(default, skip_serializing_if = "serde_yaml::Mapping::is_empty")]
+    node: serde_yaml::Mapping,
+    #[cfg(feature = "node")]
+    #[serde(default, skip_serializing_if = "serde_yaml::Mapping::is_empty")]
+    firewall: serde_yaml::Mapping,
+}
+
+#[cfg(feature = "client")]
+fn default_network() -> String {
+    bazuka::config::NETWORK.into()
+}
+
+#[cfg(feature = "client")]
+fn is_default_network(network: &str) -> bool {
+    network == bazuka::config::NETWORK
+}
+
+#[cfg(feature = "client")]
+fn default_mpn_worker_registry() -> Option<PathBuf> {
+    Some(
+        home::home_dir()
+            .unwrap()
+            .join(Path::new(".bazuka-mpn-workers")),
+    )
+}
+
+#[cfg(feature = "client")]
+fn is_default_mpn_worker_registry(path: &Option<PathBuf>) -> bool {
+    *path == default_mpn_worker_registry()
 }
 
 #[cfg(feature = "client")]
 impl BazukaConfig {
+    #[cfg(feature = "node")]
+    fn node_options(&self) -> Result<NodeOptions, NodeError> {
+        serde_yaml::from_value(serde_yaml::Value::Mapping(self.node.clone()))
+            .map_err(|e| NodeError::InvalidNodeOptions(e.to_string()))
+    }
+    #[cfg(feature = "node")]
+    fn firewall_options(&self) -> Result<FirewallOptions, NodeError> {
+        serde_yaml::from_value(serde_yaml::Value::Mapping(self.firewall.clone()))
+            .map_err(|e| NodeError::InvalidNodeOptions(e.to_string()))
+    }
+    /// Configs written before the admin listener existed get an admin section
+    /// with a fresh token, so that the admin operations keep working on them
+    fn with_admin(mut self, conf_path: &Path) -> Self {
+        if self.admin.is_none() {
+            let admin = BazukaConfigAdmin::generate();
+            println!(
+                "{} {}",
+                "Admin API enabled on:".bright_yellow(),
+                admin.listen
+            );
+            self.admin = Some(admin);
+            std::fs::write(conf_path, serde_yaml::to_string(&self).unwrap()).unwrap();
+        }
+        self
+    }
     fn random_node(&self) -> PeerAddress {
         PeerAddress(SocketAddr::from(([0, 0, 0, 0], DEFAULT_PORT)))
         /*self.bootstrap
@@ -198,11 +310,57 @@ enum NodeCliOptions {
         dev: bool,
         #[structopt(long)]
         small_mpn: bool,
+        /// Also write the logs as rotated JSON files
+        #[structopt(long)]
+        log_file: Option<PathBuf>,
+        /// Run the network described by a chain spec file, instead of the built-in one
+        #[structopt(long, conflicts_with = "dev")]
+        chain_spec: Option<PathBuf>,
+        /// Use the MPN circuits of the keys written by `circuits setup` in the dev chain
+        #[structopt(long, requires = "dev", conflicts_with = "small_mpn")]
+        mpn_keys: Option<PathBuf>,
+        /// Accept dummy MPN proofs in the dev chain (Needs the dev-proofs feature)
+        #[structopt(long, requires = "dev")]
+        dummy_proofs: bool,
+        /// Prove the MPN works of the node with the built-in prover
+        #[structopt(long, conflicts_with = "client_only")]
+        prove: bool,
+        /// Directory of the MPN proving parameters
+        #[structopt(long)]
+        mpn_params: Option<PathBuf>,
+        /// Threads of the rayon pool the proofs are computed on, one per cpu when zero
+        #[structopt(long, default_value = "0")]
+        prover_threads: usize,
     },
     /// Get status of a node
     Status {},
     /// Add a new mpn worker
     AddMpnWorker { address: Address },
+    /// Administrate a running node
+    Admin(NodeAdminOptions),
+}
+
+#[derive(StructOpt)]
+#[cfg(feature = "node")]
+pub enum NodeAdminOptions {
+    /// Shutdown the node
+    Shutdown {},
+    /// Add an mpn worker to the running node
+    AddMpnWorker { address: Address },
+    /// Remove an mpn worker from the running node
+    RemoveMpnWorker { address: Address },
+    /// Ban a peer
+    Ban {
+        ip: IpAddr,
+        #[structopt(long, default_value = "3600")]
+        secs: u32,
+    },
+    /// Unban a peer
+    Unban { ip: IpAddr },
+    /// Change the verbosity of the logs
+    LogLevel { level: String },
+    /// Remove all transactions of the mempool
+    FlushMempool {},
 }
 
 #[derive(StructOpt)]
@@ -215,6 +373,122 @@ enum ChainCliOptions {
     DbQuery { prefix: String },
     /// Check health of the blockchain
     HealthCheck {},
+    /// Chain spec subcommand
+    Spec(ChainSpecCliOptions),
+}
+
+#[derive(StructOpt)]
+#[cfg(feature = "client")]
+enum ChainSpecCliOptions {
+    /// Write the spec of the built-in network (YAML, or JSON if the file ends with .json)
+    Export {
+        #[structopt(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(StructOpt)]
+#[allow(clippy::large_enum_variant)]
+#[cfg(feature = "client")]
+enum ContractCliOptions {
+    /// Create a contract from a JSON spec of its state model, initial state and verifying keys
+    Deploy {
+        #[structopt(long)]
+        memo: Option<String>,
+        #[structopt(long)]
+        spec: PathBuf,
+        /// Ziesha deposited to the contract on creation
+        #[structopt(long, default_value = "0")]
+        deposit: Decimal,
+        #[structopt(long, default_value = "0")]
+        fee: Decimal,
+    },
+    /// Sign a deposit to a contract, to be included by its executor
+    Deposit {
+        #[structopt(long)]
+        memo: Option<String>,
+        #[structopt(long)]
+        contract_id: ContractId,
+        #[structopt(long, default_value = "0")]
+        circuit_id: u32,
+        #[structopt(long)]
+        calldata: Option<ZkScalar>,
+        /// Deposit nonce of the contract, one more than the previous deposit
+        /// of the wallet when not given
+        #[structopt(long)]
+        nonce: Option<u32>,
+        #[structopt(long)]
+        amount: Decimal,
+        #[structopt(long)]
+        token_id: Option<ContractId>,
+        /// Fee of the executor
+        #[structopt(long, default_value = "0")]
+        fee: Decimal,
+        /// Write the deposit to a file instead of printing it
+        #[structopt(long)]
+        output: Option<PathBuf>,
+    },
+    /// Build a withdrawal from a contract, to be included by its executor
+    Withdraw {
+        #[structopt(long)]
+        memo: Option<String>,
+        #[structopt(long)]
+        contract_id: ContractId,
+        #[structopt(long, default_value = "0")]
+        circuit_id: u32,
+        #[structopt(long)]
+        calldata: Option<ZkScalar>,
+        #[structopt(long)]
+        amount: Decimal,
+        #[structopt(long)]
+        token_id: Option<ContractId>,
+        /// Fee of the executor, paid by the contract
+        #[structopt(long, default_value = "0")]
+        fee: Decimal,
+        /// Receiver of the funds, the wallet address when not given
+        #[structopt(long)]
+        to: Option<Address>,
+        /// Write the withdrawal to a file instead of printing it
+        #[structopt(long)]
+        output: Option<PathBuf>,
+    },
+    /// Update a contract with proven deposit, withdraw or function-call updates
+    Call {
+        #[structopt(long)]
+        memo: Option<String>,
+        #[structopt(long)]
+        contract_id: ContractId,
+        /// JSON file of the updates and the state delta they make
+        #[structopt(long)]
+        update: PathBuf,
+        #[structopt(long, default_value = "0")]
+        fee: Decimal,
+    },
+    /// Download the state of a contract
+    State {
+        #[structopt(long)]
+        contract_id: ContractId,
+        /// Write the state to a file instead of printing it
+        #[structopt(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(StructOpt)]
+#[cfg(feature = "client")]
+enum CircuitsCliOptions {
+    /// Generate the proving and verifying keys of the MPN circuits
+    Setup {
+        /// Directory the keys are written to
+        #[structopt(long)]
+        output: Option<PathBuf>,
+        /// Circuits of `--small-mpn` dev chains
+        #[structopt(long)]
+        small_mpn: bool,
+        /// Circuits of the network described by a chain spec file
+        #[structopt(long, conflicts_with = "small_mpn")]
+        chain_spec: Option<PathBuf>,
+    },
 }
 
 #[derive(StructOpt)]
@@ -248,6 +522,109 @@ enum CliOptions {
 
     /// Chain subcommand
     Chain(ChainCliOptions),
+
+    /// Contract subcommand
+    Contract(ContractCliOptions),
+
+    /// MPN circuits subcommand
+    Circuits(CircuitsCliOptions),
+
+    #[cfg(feature = "client")]
+    /// Prove MPN works of a node
+    MpnWorker {
+        /// Node to get the works from
+        #[structopt(long)]
+        node: Option<PeerAddress>,
+        /// Directory of the MPN proving parameters
+        #[structopt(long)]
+        mpn_params: Option<PathBuf>,
+        /// Threads of the rayon pool the proofs are computed on, one per cpu when zero
+        #[structopt(long, default_value = "0")]
+        threads: usize,
+    },
+
+    #[cfg(feature = "node")]
+    /// Run a local network of several in-memory nodes
+    Devnet {
+        #[structopt(long, default_value = "4")]
+        nodes: usize,
+        #[structopt(long, default_value = "1")]
+        validators: usize,
+        /// Nodes listen on consecutive ports starting from this
+        #[structopt(long, default_value = "8800")]
+        base_port: u16,
+        /// Accept dummy MPN proofs (Needs the dev-proofs feature)
+        #[structopt(long)]
+        dummy_proofs: bool,
+    },
+}
+
+/// Serves HTTP requests on the given address by redirecting them to the node.
+/// Requests are marked as admin requests when an admin token is required.
+#[cfg(feature = "node")]
+async fn serve(
+    listen: SocketAddr,
+    inc_send: mpsc::UnboundedSender<NodeRequest>,
+    admin_token: Option<String>,
+) -> Result<(), NodeError> {
+    let arc_inc_send = Arc::new(inc_send);
+    let admin_token = Arc::new(admin_token);
+    Server::bind(&listen)
+        .serve(make_service_fn(|conn: &AddrStream| {
+            let client = conn.remote_addr();
+            let arc_inc_send = Arc::clone(&arc_inc_send);
+            let admin_token = Arc::clone(&admin_token);
+            async move {
+                Ok::<_, NodeError>(service_fn(move |req: Request<Body>| {
+                    let arc_inc_send = Arc::clone(&arc_inc_send);
+                    let admin_token = Arc::clone(&admin_token);
+                    async move {
+                        if let Some(admin_token) = admin_token.as_ref() {
+                            let given = req
+                                .headers()
+                                .get(ADMIN_TOKEN_HEADER)
+                                .map(|v| v.as_bytes())
+                                .unwrap_or_default();
+                            if !constant_time_eq(given, admin_token.as_bytes()) {
+                                let mut resp = Response::new(Body::from("Invalid admin token!"));
+                                *resp.status_mut() = StatusCode::UNAUTHORIZED;
+                                return Ok(resp);
+                            }
+                        }
+                        let (resp_snd, mut resp_rcv) =
+                            mpsc::unbounded_channel::<Result<Response<Body>, NodeError>>();
+                        let req = NodeRequest {
+                            limit: Limit::default(),
+                            socket_addr: Some(client),
+                            admin: admin_token.is_some(),
+                            body: req,
+                            resp: resp_snd,
+                        };
+                        arc_inc_send
+                            .send(req)
+                            .map_err(|_| NodeError::NotListeningError)?;
+                        Ok::<Response<Body>, NodeError>(
+                            match resp_rcv.recv().await.ok_or(NodeError::NotAnsweringError)? {
+                                Ok(resp) => resp,
+                                Err(e) => {
+                                    let mut resp =
+                                        Response::new(Body::from(format!("Error: {}", e)));
+                                    *resp.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                                    resp
+                                }
+                            },
+                        )
+                    }
+                }))
+            }
+        }))
+        .await?;
+    Ok(())
+}
+
+#[cfg(feature = "node")]
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
 }
 
 #[cfg(feature = "node")]
@@ -258,6 +635,7 @@ async fn run_node<K: KvStore, B: Blockchain<K>>(
     social_profiles: SocialProfiles,
     client_only: bool,
     network: String,
+    prover: Option<MpnProver>,
 ) -> Result<(), NodeError> {
     let address = if client_only {
         None
@@ -277,22 +655,85 @@ async fn run_node<K: KvStore, B: Blockchain<K>>(
     }
     println!("{} {}", "Network:".bright_yellow(), network);
 
+    let node_opts = bazuka_config.node_options()?;
+    let firewall_opts = bazuka_config.firewall_options()?;
+    node_opts.validate()?;
+    node_opts.validate_mpn_rewards(
+        &blockchain.config().mpn_config,
+        blockchain.min_validator_reward(wallet.clone().validator().tx_builder().get_address())?,
+    )?;
+    firewall_opts.validate()?;
+    if let Some(admin) = &bazuka_config.admin {
+        admin.validate()?;
+    }
+    println!(
+        "{}\n{}",
+        "Node options:".bright_yellow(),
+        serde_yaml::to_string(&node_opts).unwrap()
+    );
+    println!(
+        "{}\n{}",
+        "Firewall options:".bright_yellow(),
+        serde_yaml::to_string(&firewall_opts).unwrap()
+    );
+    if let Some(admin) = &bazuka_config.admin {
+        println!("{} {}", "Admin listening:".bright_yellow(), admin.listen);
+    }
+
+    launch_node(
+        blockchain,
+        bazuka_config,
+        wallet,
+        social_profiles,
+        address,
+        network,
+        prover,
+        None,
+    )
+    .await
+}
+
+/// Wires a node to its HTTP listeners and to the Internet, and runs it.
+/// Outgoing requests are sent from `local_ip` when given, so that peers see
+/// the node on its own IP.
+#[cfg(feature = "node")]
+#[allow(clippy::too_many_arguments)]
+async fn launch_node<K: KvStore, B: Blockchain<K>>(
+    blockchain: B,
+    bazuka_config: BazukaConfig,
+    wallet: WalletCollection,
+    social_profiles: SocialProfiles,
+    address: Option<PeerAddress>,
+    network: String,
+    prover: Option<MpnProver>,
+    local_ip: Option<IpAddr>,
+) -> Result<(), NodeError> {
     let (inc_send, inc_recv) = mpsc::unbounded_channel::<NodeRequest>();
     let (out_send, mut out_recv) = mpsc::unbounded_channel::<NodeRequest>();
 
     let bootstrap_nodes = bazuka_config.bootstrap.clone();
-
-    // (70 request, 100MB) per minute
-    let firewall = Firewall::new(70, 100 * MB);
+    let prover_wallet = wallet.clone().user(0).tx_builder();
+    let firewall = Firewall::new(bazuka_config.firewall_options()?);
+    let config_workers = bazuka_config
+        .mpn_workers
+        .iter()
+        .map(|w| w.clone().try_into().unwrap())
+        .collect();
+    let mpn_workers = if let Some(path) = &bazuka_config.mpn_worker_registry {
+        MpnWorkerRegistry::open(path.clone(), config_workers)?
+    } else {
+        MpnWorkerRegistry::new(config_workers)
+    };
 
     // Async loop that is responsible for answering external requests and gathering
     // data from external world through a heartbeat loop.
     let node = node_create(
-        config::node::get_node_options(),
+        bazuka_config.node_options()?,
         &network,
         address,
         bootstrap_nodes,
         blockchain,
+        Arc::new(SystemClock),
         0,
         wallet.clone().validator().tx_builder(),
         wallet.clone().user(0).tx_builder(),
@@ -300,53 +741,18 @@ async fn run_node<K: KvStore, B: Blockchain<K>>(
         inc_recv,
         out_send,
         Some(firewall),
-        bazuka_config
-            .mpn_workers
-            .iter()
-            .map(|w| w.clone().try_into().unwrap())
-            .collect(),
+        mpn_workers,
     );
 
-    // Async loop that is responsible for getting incoming HTTP requests through a
-    // socket and redirecting it to the node channels.
-    let server_loop = async {
-        let arc_inc_send = Arc::new(inc_send);
-        Server::bind(&bazuka_config.listen)
-            .serve(make_service_fn(|conn: &AddrStream| {
-                let client = conn.remote_addr();
-                let arc_inc_send = Arc::clone(&arc_inc_send);
-                async move {
-                    Ok::<_, NodeError>(service_fn(move |req: Request<Body>| {
-                        let arc_inc_send = Arc::clone(&arc_inc_send);
-                        async move {
-                            let (resp_snd, mut resp_rcv) =
-                                mpsc::unbounded_channel::<Result<Response<Body>, NodeError>>();
-                            let req = NodeRequest {
-                                limit: Limit::default(),
-                                socket_addr: Some(client),
-                                body: req,
-                                resp: resp_snd,
-                            };
-                            arc_inc_send
-                                .send(req)
-                                .map_err(|_| NodeError::NotListeningError)?;
-                            Ok::<Response<Body>, NodeError>(
-                                match resp_rcv.recv().await.ok_or(NodeError::NotAnsweringError)? {
-                                    Ok(resp) => resp,
-                                    Err(e) => {
-                                        let mut resp =
-                                            Response::new(Body::from(format!("Error: {}", e)));
-                                        *resp.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                        resp
-                                    }
-                                },
-                            )
-                        }
-                    }))
-                }
-            }))
-            .await?;
-        Ok::<(), NodeError>(())
+    // Async loops that are responsible for getting incoming HTTP requests through
+    // sockets and redirecting them to the node channels.
+    let server_loop = serve(bazuka_config.listen, inc_send.clone(), None);
+    let admin_loop = async {
+        if let Some(admin) = &bazuka_config.admin {
+            serve(admin.listen, inc_send.clone(), Some(admin.token.clone())).await
+        } else {
+            Ok(())
+        }
     };
 
     // Async loop that is responsible for redirecting node requests from its outgoing
@@ -355,7 +761,9 @@ async fn run_node<K: KvStore, B: Blockchain<K>>(
         while let Some(req) = out_recv.recv().await {
             tokio::spawn(async move {
                 let resp = async {
-                    let client = Client::new();
+                    let mut connector = HttpConnector::new();
+                    connector.set_local_address(local_ip);
+                    let client = Client::builder().build::<_, Body>(connector);
                     let resp = if let Some(time_limit) = req.limit.time {
                         tokio::time::timeout(time_limit, client.request(req.body)).await?
                     } else {
@@ -372,7 +780,28 @@ async fn run_node<K: KvStore, B: Blockchain<K>>(
         Ok::<(), NodeError>(())
     };
 
-    try_join!(server_loop, client_loop, node).unwrap();
+    // Async loop that proves the MPN works of the node, through its own HTTP listener.
+    let prover_loop = async {
+        if let Some(prover) = prover {
+            let (req_loop, client) = BazukaClient::connect(
+                prover_wallet.get_priv_key(),
+                PeerAddress(bazuka_config.listen),
+                network.clone(),
+                None,
+            );
+            try_join!(
+                crate::cli::mpn_worker::prove_mpn_works(
+                    client,
+                    prover_wallet.get_address(),
+                    Arc::new(prover),
+                ),
+                req_loop
+            )?;
+        }
+        Ok(())
+    };
+
+    try_join!(server_loop, admin_loop, client_loop, prover_loop, node).unwrap();
 
     Ok(())
 }
@@ -383,7 +812,8 @@ pub async fn initialize_cli() {
     let conf_path = home::home_dir().unwrap().join(Path::new(".bazuka.yaml"));
     let conf: Option<BazukaConfig> = std::fs::File::open(conf_path.clone())
         .ok()
-        .map(|f| serde_yaml::from_reader(f).unwrap());
+        .map(|f| serde_yaml::from_reader(f).unwrap())
+        .map(|conf: BazukaConfig| conf.with_admin(&conf_path));
     let wallet_path = home::home_dir().unwrap().join(Path::new(".bazuka-wallet"));
     let wallet = WalletCollection::open(wallet_path.clone()).unwrap();
 
@@ -413,7 +843,150 @@ pub async fn initialize_cli() {
             ChainCliOptions::HealthCheck {} => {
                 crate::cli::chain::health_check(&conf.expect(BAZUKA_NOT_INITILIZED));
             }
+            ChainCliOptions::Spec(spec_opts) => match spec_opts {
+                ChainSpecCliOptions::Export { output } => {
+                    crate::cli::chain::spec_export(output);
+                }
+            },
+        },
+        CliOptions::Circuits(circuits_opts) => match circuits_opts {
+            CircuitsCliOptions::Setup {
+                output,
+                small_mpn,
+                chain_spec,
+            } => {
+                crate::cli::circuits::circuits_setup(
+                    output.unwrap_or_else(crate::cli::mpn_worker::default_mpn_params_path),
+                    small_mpn,
+                    chain_spec,
+                );
+            }
         },
+        CliOptions::Contract(contract_opts) => match contract_opts {
+            ContractCliOptions::Deploy {
+                memo,
+                spec,
+                deposit,
+                fee,
+            } => {
+                crate::cli::contract::deploy(
+                    memo,
+                    spec,
+                    deposit,
+                    fee,
+                    conf.expect(BAZUKA_NOT_INITILIZED),
+                    wallet.expect(BAZUKA_NOT_INITILIZED),
+                    &wallet_path,
+                )
+                .await;
+            }
+            ContractCliOptions::Deposit {
+                memo,
+                contract_id,
+                circuit_id,
+                calldata,
+                nonce,
+                amount,
+                token_id,
+                fee,
+                output,
+            } => {
+                crate::cli::contract::deposit(
+                    memo,
+                    contract_id,
+                    circuit_id,
+                    calldata,
+                    nonce,
+                    amount,
+                    token_id,
+                    fee,
+                    output,
+                    conf.expect(BAZUKA_NOT_INITILIZED),
+                    wallet.expect(BAZUKA_NOT_INITILIZED),
+                    &wallet_path,
+                )
+                .await;
+            }
+            ContractCliOptions::Withdraw {
+                memo,
+                contract_id,
+                circuit_id,
+                calldata,
+                amount,
+                token_id,
+                fee,
+                to,
+                output,
+            } => {
+                crate::cli::contract::withdraw(
+                    memo,
+                    contract_id,
+                    circuit_id,
+                    calldata,
+                    amount,
+                    token_id,
+                    fee,
+                    to,
+                    output,
+                    conf.expect(BAZUKA_NOT_INITILIZED),
+                    wallet.expect(BAZUKA_NOT_INITILIZED),
+                )
+                .await;
+            }
+            ContractCliOptions::Call {
+                memo,
+                contract_id,
+                update,
+                fee,
+            } => {
+                crate::cli::contract::call(
+                    memo,
+                    contract_id,
+                    update,
+                    fee,
+                    conf.expect(BAZUKA_NOT_INITILIZED),
+                    wallet.expect(BAZUKA_NOT_INITILIZED),
+                    &wallet_path,
+                )
+                .await;
+            }
+            ContractCliOptions::State {
+                contract_id,
+                output,
+            } => {
+                crate::cli::contract::state(
+                    contract_id,
+                    output,
+                    conf.expect(BAZUKA_NOT_INITILIZED),
+                    wallet.expect(BAZUKA_NOT_INITILIZED),
+                )
+                .await;
+            }
+        },
+        #[cfg(feature = "client")]
+        CliOptions::MpnWorker {
+            node,
+            mpn_params,
+            threads,
+        } => {
+            crate::cli::mpn_worker::mpn_worker(
+                conf.expect(BAZUKA_NOT_INITILIZED),
+                wallet.expect(BAZUKA_NOT_INITILIZED),
+                node,
+                mpn_params,
+                threads,
+            )
+            .await;
+        }
+        #[cfg(feature = "node")]
+        CliOptions::Devnet {
+            nodes,
+            validators,
+            base_port,
+            dummy_proofs,
+        } => {
+            crate::cli::devnet::devnet(nodes, validators, base_port, dummy_proofs).await;
+        }
         #[cfg(feature = "node")]
         CliOptions::Node(node_opts) => match node_opts {
             NodeCliOptions::Start {
@@ -422,6 +995,13 @@ pub async fn initialize_cli() {
                 dev,
                 ram,
                 small_mpn,
+                log_file,
+                chain_spec,
+                mpn_keys,
+                dummy_proofs,
+                prove,
+                mpn_params,
+                prover_threads,
             } => {
                 crate::cli::node::start(
                     discord_handle,
@@ -431,6 +1011,13 @@ pub async fn initialize_cli() {
                     ram,
                     dev,
                     small_mpn,
+                    log_file,
+                    chain_spec,
+                    mpn_keys,
+                    dummy_proofs,
+                    prove.then(|| {
+                        crate::cli::mpn_worker::load_mpn_prover(mpn_params, prover_threads)
+                    }),
                 )
                 .await;
             }
@@ -449,6 +1036,14 @@ pub async fn initialize_cli() {
                 )
                 .await;
             }
+            NodeCliOptions::Admin(admin_opts) => {
+                crate::cli::node::admin(
+                    conf.expect(BAZUKA_NOT_INITILIZED),
+                    wallet.expect(BAZUKA_NOT_INITILIZED),
+                    admin_opts,
+                )
+                .await;
+            }
         },
         #[cfg(feature = "client")]
         CliOptions::Init {
@@ -0,0 +1,107 @@
+use crate::cli::BazukaConfig;
+
+use bazuka::{
+    client::{BazukaClient, Limit, NodeError, PeerAddress},
+    common::*,
+    core::{hash::Hash, Address, Hasher},
+    mpn::prover::MpnProver,
+    wallet::WalletCollection,
+};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const MPN_WORKER_POLL_INTERVAL: u32 = 5 * SECOND;
+
+/// Default directory of the proving parameters of the MPN circuits
+pub fn default_mpn_params_path() -> PathBuf {
+    home::home_dir().unwrap().join(".bazuka-mpn-params")
+}
+
+/// Loads the proving parameters and sizes the global rayon pool, on which the
+/// proofs are computed (`threads == 0` keeps one thread per cpu)
+pub fn load_mpn_prover(params: Option<PathBuf>, threads: usize) -> MpnProver {
+    if threads > 0 {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .expect("Cannot build the proving thread pool!");
+    }
+    let params = params.unwrap_or_else(default_mpn_params_path);
+    MpnProver::load(&params).expect("Cannot load the MPN proving parameters!")
+}
+
+/// Polls the node for the works assigned to `address`, proves them and
+/// posts the proofs back, forever
+pub async fn prove_mpn_works(
+    client: BazukaClient,
+    address: Address,
+    prover: Arc<MpnProver>,
+) -> Result<(), NodeError> {
+    // The node keeps handing out the same works until the next block, so
+    // works that are already proven are remembered.
+    let mut proven = HashSet::new();
+    loop {
+        match client.get_mpn_works(address.clone()).await {
+            Ok(resp) => {
+                let works = resp
+                    .works
+                    .into_iter()
+                    .filter(|(_, w)| {
+                        !proven.contains(&Hasher::hash(&bincode::serialize(w).unwrap()))
+                    })
+                    .collect::<std::collections::HashMap<_, _>>();
+                if works.is_empty() {
+                    if proven.len() > 1024 {
+                        proven.clear();
+                    }
+                } else {
+                    log::info!("Proving {} MPN works...", works.len());
+                    let prover = Arc::clone(&prover);
+                    let prover_address = address.clone();
+                    let to_prove = works.clone();
+                    let proofs = tokio::task::spawn_blocking(move || {
+                        prover.prove_works(&prover_address, &to_prove)
+                    })
+                    .await
+                    .expect("MPN prover panicked!");
+                    for (id, work) in works.iter() {
+                        if proofs.contains_key(id) {
+                            proven.insert(Hasher::hash(&bincode::serialize(work).unwrap()));
+                        }
+                    }
+                    match client.post_mpn_proof(address.clone(), proofs).await {
+                        Ok(resp) => log::info!("Accepted MPN proofs: {}", resp.accepted),
+                        Err(e) => log::warn!("Could not post the MPN proofs: {}", e),
+                    }
+                }
+            }
+            Err(e) => log::warn!("Could not get MPN works: {}", e),
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(
+            MPN_WORKER_POLL_INTERVAL as u64,
+        ))
+        .await;
+    }
+}
+
+pub async fn mpn_worker(
+    conf: BazukaConfig,
+    mut wallet: WalletCollection,
+    node: Option<PeerAddress>,
+    params: Option<PathBuf>,
+    threads: usize,
+) {
+    let tx_builder = wallet.user(0).tx_builder();
+    // Requests are signed by the worker, so rewards go to the wallet address
+    let address = tx_builder.get_address();
+    let prover = Arc::new(load_mpn_prover(params, threads));
+    let (req_loop, client) = BazukaClient::connect(
+        tx_builder.get_priv_key(),
+        node.unwrap_or_else(|| conf.random_node()),
+        conf.network.clone(),
+        Some(Limit::default().time(30 * SECOND)),
+    );
+    println!("Proving MPN works for {}...", address);
+    futures::try_join!(prove_mpn_works(client, address, prover), req_loop).unwrap();
+}
@@ -0,0 +1,56 @@
+use futures::try_join;
+
+use crate::cli::{BazukaConfig, NodeAdminOptions};
+
+use bazuka::{
+    client::{BazukaClient, Limit, NodeError, PeerAddress},
+    common::*,
+    wallet::WalletCollection,
+};
+
+pub async fn admin(conf: BazukaConfig, mut wallet: WalletCollection, opts: NodeAdminOptions) {
+    let admin = conf
+        .admin
+        .clone()
+        .expect("Admin API is not configured! Add an admin section to your config.");
+    let wallet = wallet.user(0).tx_builder();
+    let (req_loop, client) = BazukaClient::connect_admin(
+        wallet.get_priv_key(),
+        PeerAddress(admin.listen),
+        conf.network.clone(),
+        admin.token,
+        Some(Limit::default().time(5 * SECOND)),
+    );
+    try_join!(
+        async move {
+            match opts {
+                NodeAdminOptions::Shutdown {} => {
+                    client.shutdown().await?;
+                    println!("Node is shutting down...");
+                }
+                NodeAdminOptions::AddMpnWorker { address } => {
+                    println!("{:#?}", client.post_mpn_worker(address).await?);
+                }
+                NodeAdminOptions::RemoveMpnWorker { address } => {
+                    println!("{:#?}", client.remove_mpn_worker(address).await?);
+                }
+                NodeAdminOptions::Ban { ip, secs } => {
+                    client.ban(ip, secs).await?;
+                    println!("{} is banned for {} seconds!", ip, secs);
+                }
+                NodeAdminOptions::Unban { ip } => {
+                    println!("{:#?}", client.unban(ip).await?);
+                }
+                NodeAdminOptions::LogLevel { level } => {
+                    println!("{:#?}", client.set_log_level(level).await?);
+                }
+                NodeAdminOptions::FlushMempool {} => {
+                    println!("{:#?}", client.flush_mempool().await?);
+                }
+            }
+            Ok::<(), NodeError>(())
+        },
+        req_loop
+    )
+    .unwrap();
+}
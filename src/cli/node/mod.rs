@@ -1,7 +1,9 @@
 pub mod add_mpn_worker;
+pub mod admin;
 pub mod start;
 pub mod status;
 
 pub use add_mpn_worker::*;
+pub use admin::*;
 pub use start::*;
 pub use status::*;
@@ -1,10 +1,18 @@
-use crate::cli::{run_node, BazukaConfig, CURRENT_NETWORK};
+use crate::cli::chain::load_chain_spec;
+use crate::cli::{run_node, BazukaConfig};
 
 use bazuka::{
-    blockchain::KvStoreChain, client::messages::SocialProfiles, config, db::LevelDbKvStore,
-    db::RamKvStore, wallet::WalletCollection,
+    blockchain::KvStoreChain, client::messages::SocialProfiles, common::*, config,
+    db::LevelDbKvStore, db::RamKvStore, mpn::prover::MpnProver, mpn::setup::MpnVerifyingKeys,
+    wallet::WalletCollection,
 };
+use colored::Colorize;
+use std::path::PathBuf;
 
+const MAX_LOG_FILE_SIZE: u64 = 10 * MB;
+const MAX_LOG_FILES: usize = 5;
+
+#[allow(clippy::too_many_arguments)]
 pub async fn start(
     discord_handle: Option<String>,
     client_only: bool,
@@ -13,14 +21,75 @@ pub async fn start(
     ram: bool,
     dev: bool,
     small_mpn: bool,
+    log_file: Option<PathBuf>,
+    chain_spec: Option<PathBuf>,
+    mpn_keys: Option<PathBuf>,
+    dummy_proofs: bool,
+    prover: Option<MpnProver>,
 ) {
-    let blockchain_conf = if dev {
+    if let Some(log_file) = log_file {
+        bazuka::logs::enable_file_output(log_file, MAX_LOG_FILE_SIZE, MAX_LOG_FILES)
+            .expect("Cannot open the log file!");
+    }
+    let mut network: Option<String> = None;
+    let mut blockchain_conf = if dev {
         let validator_wallet = wallet.validator().tx_builder();
         let user_wallet = wallet.user(0).tx_builder();
-        config::blockchain::get_dev_blockchain_config(&validator_wallet, &user_wallet, small_mpn)
+        let mpn_keys = mpn_keys
+            .map(|dir| MpnVerifyingKeys::load(&dir).expect("Cannot load the MPN verifying keys!"));
+        config::blockchain::get_dev_blockchain_config(
+            &validator_wallet,
+            &user_wallet,
+            small_mpn,
+            mpn_keys.as_ref(),
+            dummy_proofs,
+        )
+        .expect("Invalid dev chain config!")
+    } else if let Some(path) = chain_spec {
+        let spec = match load_chain_spec(&path) {
+            Ok(spec) => spec,
+            Err(e) => {
+                println!("{} {}", "Error:".bright_red(), e);
+                return;
+            }
+        };
+        if spec.network != conf.network {
+            log::warn!(
+                "Chain spec network {} differs from the configured network {}!",
+                spec.network,
+                conf.network
+            );
+        }
+        network = Some(spec.network.clone());
+        spec.blockchain_config()
+            .expect("Chain spec is validated when loaded!")
     } else {
         config::blockchain::get_blockchain_config()
     };
+    for (height, hash) in conf.checkpoints.iter() {
+        match hex::decode(hash).ok().and_then(|h| h.try_into().ok()) {
+            Some(hash) => {
+                blockchain_conf.checkpoints.insert(*height, hash);
+            }
+            None => {
+                println!(
+                    "{} Checkpoint hash of height {} is invalid!",
+                    "Error:".bright_red(),
+                    height
+                );
+                return;
+            }
+        }
+    }
+    match conf.node_options() {
+        Ok(opts) => {
+            blockchain_conf.max_reorg_depth = Some(opts.max_reorg_depth);
+        }
+        Err(e) => {
+            println!("{} {}", "Error:".bright_red(), e);
+            return;
+        }
+    }
 
     if ram {
         run_node(
@@ -31,7 +100,8 @@ pub async fn start(
                 discord: discord_handle,
             },
             client_only,
-            "dev".into(),
+            network.unwrap_or_else(|| "dev".into()),
+            prover,
         )
         .await
         .unwrap();
@@ -44,7 +114,8 @@ pub async fn start(
                 discord: discord_handle,
             },
             client_only,
-            CURRENT_NETWORK.into(),
+            network.unwrap_or_else(|| conf.network.clone()),
+            prover,
         )
         .await
         .unwrap();
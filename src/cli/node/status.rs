@@ -1,6 +1,6 @@
 use futures::try_join;
 
-use crate::cli::{BazukaConfig, CURRENT_NETWORK};
+use crate::cli::BazukaConfig;
 
 use bazuka::{
     client::{BazukaClient, Limit, NodeError},
@@ -13,7 +13,7 @@ pub async fn status(conf: BazukaConfig, mut wallet: WalletCollection) {
     let (req_loop, client) = BazukaClient::connect(
         wallet.get_priv_key(),
         conf.random_node(),
-        CURRENT_NETWORK.into(),
+        conf.network.clone(),
         Some(Limit::default().time(2 * SECOND)),
     );
     try_join!(
@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 use tokio::try_join;
 
-use crate::cli::{BazukaConfig, CURRENT_NETWORK};
+use crate::cli::BazukaConfig;
 use bazuka::client::{BazukaClient, Limit, NodeError};
 use bazuka::common::*;
 use bazuka::core::{Address, ContractId, Decimal, Money, NonceGroup};
@@ -31,7 +31,7 @@ pub async fn delegate(
     let (req_loop, client) = BazukaClient::connect(
         tx_builder.get_priv_key(),
         conf.random_node(),
-        CURRENT_NETWORK.into(),
+        conf.network.clone(),
         Some(Limit::default().time(2 * SECOND)),
     );
     try_join!(
@@ -1,6 +1,6 @@
 use tokio::try_join;
 
-use crate::cli::{BazukaConfig, CURRENT_NETWORK};
+use crate::cli::BazukaConfig;
 use bazuka::client::{Limit, NodeError};
 use bazuka::common::*;
 use bazuka::core::{MpnAddress, NonceGroup};
@@ -16,7 +16,7 @@ pub async fn info(conf: BazukaConfig, mut wallet: WalletCollection, validator: b
     let (req_loop, client) = BazukaClient::connect(
         tx_builder.get_priv_key(),
         conf.random_node(),
-        CURRENT_NETWORK.into(),
+        conf.network.clone(),
         Some(Limit::default().time(2 * SECOND)),
     );
     try_join!(
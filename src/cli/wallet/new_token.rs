@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use crate::cli::{BazukaConfig, CURRENT_NETWORK};
+use crate::cli::BazukaConfig;
 use bazuka::client::{BazukaClient, Limit, NodeError};
 use bazuka::common::*;
 use bazuka::core::{ContractId, Decimal, Money, NonceGroup};
@@ -23,7 +23,7 @@ pub async fn new_token(
     let (req_loop, client) = BazukaClient::connect(
         tx_builder.get_priv_key(),
         conf.random_node(),
-        CURRENT_NETWORK.into(),
+        conf.network.clone(),
         Some(Limit::default().time(2 * SECOND)),
     );
     try_join!(
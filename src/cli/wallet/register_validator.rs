@@ -2,7 +2,7 @@ use std::path::PathBuf;
 
 use tokio::try_join;
 
-use crate::cli::{BazukaConfig, CURRENT_NETWORK};
+use crate::cli::BazukaConfig;
 use bazuka::client::{BazukaClient, Limit, NodeError};
 use bazuka::common::*;
 use bazuka::core::{ContractId, Decimal, Money, NonceGroup, Ratio};
@@ -21,7 +21,7 @@ pub async fn register_validator(
     let (req_loop, client) = BazukaClient::connect(
         tx_builder.get_priv_key(),
         conf.random_node(),
-        CURRENT_NETWORK.into(),
+        conf.network.clone(),
         Some(Limit::default().time(2 * SECOND)),
     );
     try_join!(
@@ -1,4 +1,4 @@
-use crate::cli::{BazukaConfig, CURRENT_NETWORK};
+use crate::cli::BazukaConfig;
 use bazuka::client::{BazukaClient, Limit, NodeError};
 use bazuka::common::*;
 
@@ -16,7 +16,7 @@ async fn resend_all_wallet_txs(
     let (req_loop, client) = BazukaClient::connect(
         tx_builder.get_priv_key(),
         conf.random_node(),
-        CURRENT_NETWORK.into(),
+        conf.network.clone(),
         Some(Limit::default().time(2 * SECOND)),
     );
     try_join!(
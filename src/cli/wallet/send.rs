@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use crate::cli::{BazukaConfig, CURRENT_NETWORK};
+use crate::cli::BazukaConfig;
 use bazuka::wallet::WalletCollection;
 use bazuka::{
     client::{BazukaClient, Limit, NodeError},
@@ -31,7 +31,7 @@ pub async fn send(
     let (req_loop, client) = BazukaClient::connect(
         tx_builder.get_priv_key(),
         conf.random_node(),
-        CURRENT_NETWORK.into(),
+        conf.network.clone(),
         Some(Limit::default().time(2 * SECOND)),
     );
     let tkn = if let Some(token_id) = token_id {
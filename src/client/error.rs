@@ -28,6 +28,8 @@ pub enum NodeError {
     QueryStringError(#[from] serde_qs::Error),
     #[error("bincode error happened: {0}")]
     BincodeError(#[from] bincode::Error),
+    #[error("io error happened: {0}")]
+    IoError(#[from] std::io::Error),
     #[error("utf8 error happened: {0}")]
     Utf8Error(#[from] std::str::Utf8Error),
     #[error("addr parse error happened: {0}")]
@@ -40,6 +42,8 @@ pub enum NodeError {
     GeneralParseAddressError(#[from] crate::core::ParseGeneralAddressError),
     #[error("cannot parse general address: {0}")]
     ContractIdParseError(#[from] crate::core::ParseContractIdError),
+    #[error("cannot parse data locator: {0}")]
+    ZkDataLocatorParseError(#[from] crate::zk::ParseZkDataLocatorError),
     #[error("timeout reached: {0}")]
     TimeoutError(#[from] tokio::time::error::Elapsed),
     #[error("http body size limit error")]
@@ -50,6 +54,8 @@ pub enum NodeError {
     InvalidSignatureHeader,
     #[error("signature required on this message")]
     SignatureRequired,
+    #[error("signed request is replayed")]
+    ReplayedRequest,
     #[error("zk error: {0}")]
     ZkError(#[from] ZkError),
     #[error("wrong network")]
@@ -64,4 +70,16 @@ pub enum NodeError {
     ValidatorNotExposed,
     #[error("request sender's ip address is unknown")]
     SenderIpUnknown,
+    #[error("invalid node options: {0}")]
+    InvalidNodeOptions(String),
+    #[error("cannot parse log level: {0}")]
+    LogLevelParseError(#[from] log::ParseLevelError),
+    #[error("state proof is missing or invalid")]
+    InvalidStateProof,
+    #[error("absence of the mpn account cannot be proven")]
+    MpnAccountAbsenceUnprovable,
+    #[error("contract state does not match its compressed state")]
+    InvalidContractState,
+    #[error("header {0} is not synced by the light client")]
+    HeaderNotSynced(u64),
 }
@@ -1,3 +1,4 @@
+use super::messages::InputError;
 use crate::core::{
     Address, Amount, Block, ContractDeposit, ContractUpdate, ContractUpdateData, ContractWithdraw,
     GeneralTransaction, Header, Money, MpnDeposit, MpnWithdraw, ProofOfStake, Token, Transaction,
@@ -60,6 +61,40 @@ impl From<&ZkDeltaPairs> for ExplorerDeltaPairs {
     }
 }
 
+impl TryFrom<&ExplorerDataPairs> for ZkDataPairs {
+    type Error = InputError;
+    fn try_from(obj: &ExplorerDataPairs) -> Result<Self, Self::Error> {
+        Ok(Self(
+            obj.data
+                .iter()
+                .map(|(k, v)| Some((k.parse().ok()?, v.parse().ok()?)))
+                .collect::<Option<_>>()
+                .ok_or(InputError::Invalid)?,
+        ))
+    }
+}
+
+impl TryFrom<&ExplorerDeltaPairs> for ZkDeltaPairs {
+    type Error = InputError;
+    fn try_from(obj: &ExplorerDeltaPairs) -> Result<Self, Self::Error> {
+        Ok(Self(
+            obj.data
+                .iter()
+                .map(|(k, v)| {
+                    Some((
+                        k.parse().ok()?,
+                        match v {
+                            Some(v) => Some(v.parse().ok()?),
+                            None => None,
+                        },
+                    ))
+                })
+                .collect::<Option<_>>()
+                .ok_or(InputError::Invalid)?,
+        ))
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ExplorerMpnAccount {
     pub tx_nonce: u32,
@@ -122,6 +157,7 @@ pub struct ExplorerHeader {
     pub parent_hash: String,
     pub number: u64,
     pub block_root: String,
+    pub state_root: String,
     pub proof_of_stake: ExplorerProofOfStake,
 }
 
@@ -131,6 +167,7 @@ impl From<&Header> for ExplorerHeader {
             parent_hash: hex::encode(&obj.parent_hash),
             number: obj.number,
             block_root: hex::encode(&obj.parent_hash),
+            state_root: hex::encode(obj.state_root),
             proof_of_stake: (&obj.proof_of_stake).into(),
         }
     }
@@ -0,0 +1,413 @@
+use crate::blockchain::{Blockchain, BlockchainConfig, BlockchainError, KvStoreChain, StateProof};
+use crate::core::{hash::Hash, Address, Amount, Hasher, Header, Staker, Vrf};
+use crate::crypto::VerifiableRandomFunction;
+use crate::db::{keys, Blob, KvStore, RamKvStore, StringKey};
+use crate::mpn::MpnConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::PeerAddress;
+
+/// Headers this deep from the tip are re-fetched on every sync, so that
+/// short forks can be switched to
+pub const LIGHT_CLIENT_REORG_DEPTH: u64 = 16;
+pub const LIGHT_CLIENT_HEADERS_PER_REQUEST: u64 = 256;
+/// Most headers taken from a single peer in one sync, clients which are
+/// further behind catch up in several syncs
+pub const LIGHT_CLIENT_MAX_HEADERS_PER_SYNC: u64 = 4096;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CheckpointStaker {
+    pub address: Address,
+    pub staker: Staker,
+    pub stake: Amount,
+    /// Proof of the stake against the state root of the checkpoint
+    pub stake_proof: StateProof,
+}
+
+/// Trusted point of a chain which a light client can start from, carrying
+/// the stakers which elect the validators of the next headers. Stakes are
+/// proven against the state root of the header, while the VRF keys of the
+/// stakers and the epoch randomness are trusted along with the header.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LightCheckpoint {
+    pub header: Header,
+    pub randomness: <Hasher as Hash>::Output,
+    pub stakers: Vec<CheckpointStaker>,
+}
+
+impl LightCheckpoint {
+    /// Checkpoint at the tip of the given chain
+    pub fn new<K: KvStore, B: Blockchain<K>>(chain: &B) -> Result<Self, BlockchainError> {
+        let mut stakers = Vec::new();
+        for (address, stake) in chain.get_stakers()? {
+            let staker = chain
+                .get_staker(address.clone())?
+                .ok_or(BlockchainError::Inconsistency)?;
+            stakers.push(CheckpointStaker {
+                stake_proof: chain.get_state_proof(keys::stake(&address))?,
+                address,
+                staker,
+                stake,
+            });
+        }
+        Ok(Self {
+            header: chain.get_tip()?,
+            randomness: chain.epoch_randomness()?,
+            stakers,
+        })
+    }
+}
+
+/// Keeps the headers of the heaviest chain seen among the peers, for
+/// checking the state proofs served by untrusted nodes. The validator proofs
+/// of the headers are checked against the stakers of the checkpoint the
+/// client has started from, so headers of the validators that have joined
+/// afterwards are rejected, and the client should be restarted from a newer
+/// checkpoint once the stakers change.
+#[derive(Debug, Clone)]
+pub struct LightClient {
+    pub mpn_config: MpnConfig,
+    pub peers: Vec<PeerAddress>,
+    // Number of the checkpoint header, which is the first kept header
+    base: u64,
+    chain_start_timestamp: u32,
+    slot_duration: u32,
+    slot_per_epoch: u32,
+    check_validator: bool,
+    checkpoints: HashMap<u64, <Hasher as Hash>::Output>,
+    // Stakers and their chances of being elected
+    stakers: HashMap<Address, (Staker, f32)>,
+    headers: Vec<Header>,
+    // Epoch randomness after each of the headers
+    randomness: Vec<<Hasher as Hash>::Output>,
+}
+
+fn power(headers: &[Header]) -> f64 {
+    headers
+        .iter()
+        .map(|h| {
+            h.proof_of_stake
+                .proof
+                .as_ref()
+                .map(|p| p.power())
+                .unwrap_or(1.)
+        })
+        .sum()
+}
+
+impl LightClient {
+    /// Light client starting from the genesis block
+    pub fn new(
+        config: &BlockchainConfig,
+        peers: Vec<PeerAddress>,
+    ) -> Result<Self, BlockchainError> {
+        // The genesis block is derived from the config, so it's trusted as is
+        let genesis = KvStoreChain::new(RamKvStore::new(), config.clone())?;
+        let checkpoint = LightCheckpoint::new(&genesis)?;
+        Ok(Self::start(config, checkpoint, peers))
+    }
+
+    /// Light client starting from a trusted checkpoint. The checkpoint should
+    /// match the checkpoints of the config, and its stakes should be proven.
+    pub fn from_checkpoint(
+        config: &BlockchainConfig,
+        checkpoint: LightCheckpoint,
+        peers: Vec<PeerAddress>,
+    ) -> Result<Self, BlockchainError> {
+        if let Some(hash) = config.checkpoints.get(&checkpoint.header.number) {
+            if *hash != checkpoint.header.hash() {
+                return Err(BlockchainError::CheckpointMismatch);
+            }
+        }
+        for s in checkpoint.stakers.iter() {
+            if !s.stake_proof.verify(
+                &checkpoint.header.state_root,
+                keys::stake(&s.address).0.as_bytes(),
+                Some(&Blob::from(s.stake).0),
+            ) {
+                return Err(BlockchainError::InvalidLightCheckpoint);
+            }
+        }
+        Ok(Self::start(config, checkpoint, peers))
+    }
+
+    fn start(
+        config: &BlockchainConfig,
+        checkpoint: LightCheckpoint,
+        peers: Vec<PeerAddress>,
+    ) -> Self {
+        let sum_stakes = checkpoint
+            .stakers
+            .iter()
+            .map(|s| u64::from(s.stake))
+            .sum::<u64>();
+        let stakers = checkpoint
+            .stakers
+            .into_iter()
+            .map(|s| {
+                let chance = (u64::from(s.stake) as f64 / sum_stakes as f64) as f32;
+                (s.address, (s.staker, chance))
+            })
+            .collect();
+        Self {
+            mpn_config: config.mpn_config.clone(),
+            peers,
+            base: checkpoint.header.number,
+            chain_start_timestamp: config.chain_start_timestamp,
+            slot_duration: config.slot_duration,
+            slot_per_epoch: config.slot_per_epoch,
+            check_validator: config.check_validator,
+            checkpoints: config.checkpoints.clone(),
+            stakers,
+            headers: vec![checkpoint.header],
+            randomness: vec![checkpoint.randomness],
+        }
+    }
+
+    pub fn height(&self) -> u64 {
+        self.base + self.headers.len() as u64
+    }
+
+    pub fn header(&self, number: u64) -> Option<&Header> {
+        number
+            .checked_sub(self.base)
+            .and_then(|i| self.headers.get(i as usize))
+    }
+
+    /// Number of the first header to fetch on a sync, so that the recent
+    /// headers are re-fetched and short forks can be switched to
+    pub fn sync_start(&self) -> u64 {
+        std::cmp::max(
+            self.base + 1,
+            self.height().saturating_sub(LIGHT_CLIENT_REORG_DEPTH),
+        )
+    }
+
+    fn epoch_slot(&self, timestamp: u32) -> (u32, u32) {
+        let slot_number = timestamp.saturating_sub(self.chain_start_timestamp) / self.slot_duration;
+        let epoch_number = slot_number / self.slot_per_epoch;
+        (epoch_number, slot_number % self.slot_per_epoch)
+    }
+
+    fn is_validator(
+        &self,
+        header: &Header,
+        randomness: &<Hasher as Hash>::Output,
+        is_epoch_switch: bool,
+    ) -> bool {
+        let proof = if let Some(proof) = &header.proof_of_stake.proof {
+            proof
+        } else {
+            return false;
+        };
+        if is_epoch_switch && proof.attempt != 0 {
+            return false;
+        }
+        let (staker, chance) =
+            if let Some(staker) = self.stakers.get(&header.proof_of_stake.validator) {
+                staker
+            } else {
+                return false;
+            };
+        if Into::<f32>::into(proof.vrf_output.clone()) > *chance {
+            return false;
+        }
+        let (epoch, slot) = self.epoch_slot(header.proof_of_stake.timestamp);
+        let preimage = format!(
+            "{}-{}-{}-{}",
+            hex::encode(randomness),
+            epoch,
+            slot,
+            proof.attempt
+        );
+        Vrf::verify(
+            &staker.vrf_pub_key,
+            preimage.as_bytes(),
+            &proof.vrf_output,
+            &proof.vrf_proof,
+        )
+    }
+
+    /// Replaces the headers since `from` with the given headers, if they are
+    /// linked to the known ones, are built by elected validators and are
+    /// heavier than the replaced headers
+    pub fn extend(&mut self, from: u64, headers: &[Header]) -> bool {
+        if from <= self.base || from > self.height() || headers.is_empty() {
+            return false;
+        }
+        let index = (from - self.base) as usize;
+        let mut last = &self.headers[index - 1];
+        let mut randomness = self.randomness[index - 1];
+        let mut new_randomness = Vec::new();
+        for h in headers.iter() {
+            let (last_epoch, last_slot) = self.epoch_slot(last.proof_of_stake.timestamp);
+            let (epoch, slot) = self.epoch_slot(h.proof_of_stake.timestamp);
+            if h.number != last.number + 1
+                || h.parent_hash != last.hash()
+                || h.proof_of_stake.timestamp <= last.proof_of_stake.timestamp
+                || [epoch, slot] <= [last_epoch, last_slot]
+            {
+                return false;
+            }
+            if let Some(checkpoint) = self.checkpoints.get(&h.number) {
+                if *checkpoint != h.hash() {
+                    return false;
+                }
+            }
+            let is_epoch_switch = epoch > last_epoch;
+            if self.check_validator && !self.is_validator(h, &randomness, is_epoch_switch) {
+                return false;
+            }
+            if is_epoch_switch {
+                // New randomness = H(H(tip) | VRF_out)
+                let mut preimage: Vec<u8> = last.hash().to_vec();
+                if let Some(proof) = &h.proof_of_stake.proof {
+                    preimage.extend(Into::<Vec<u8>>::into(proof.vrf_output.clone()));
+                }
+                randomness = Hasher::hash(&preimage);
+            }
+            new_randomness.push(randomness);
+            last = h;
+        }
+        if power(headers) <= power(&self.headers[index..]) {
+            return false;
+        }
+        self.headers.truncate(index);
+        self.headers.extend(headers.iter().cloned());
+        self.randomness.truncate(index);
+        self.randomness.extend(new_randomness);
+        true
+    }
+
+    /// Checks the value of a state key against the state root of the header
+    /// at `height`. Absent keys are read as default values, so a default
+    /// value is also accepted with a proof of absence.
+    pub fn verify(
+        &self,
+        height: u64,
+        key: &StringKey,
+        value: Blob,
+        is_default: bool,
+        proof: &StateProof,
+    ) -> bool {
+        if let Some(header) = self.header(height) {
+            let key = key.0.as_bytes();
+            proof.verify(&header.state_root, key, Some(&value.0))
+                || (is_default && proof.verify(&header.state_root, key, None))
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::{Blockchain, KvStoreChain};
+    use crate::core::{Amount, ContractId};
+    use crate::db::{keys, RamKvStore};
+    use crate::wallet::TxBuilder;
+
+    #[test]
+    fn test_light_client_verify() {
+        let validator = TxBuilder::new(&Vec::from("VALIDATOR"));
+        let abc = TxBuilder::new(&Vec::from("ABC"));
+        let mut conf = crate::config::blockchain::get_test_blockchain_config();
+        conf.check_validator = true;
+        let mut chain = KvStoreChain::new(RamKvStore::new(), conf.clone()).unwrap();
+        let mut light = LightClient::new(&conf, vec![]).unwrap();
+        let mut timestamp = 0;
+        for i in 0..5 {
+            let block = (1..100)
+                .find_map(|_| {
+                    timestamp += 30;
+                    chain.draft_block(timestamp, &[], &validator, true).unwrap()
+                })
+                .unwrap();
+            chain.extend(i as u64 + 1, &[block]).unwrap();
+        }
+        // Headers of unknown validators are rejected
+        let mut forged = chain.get_headers(1, 5).unwrap();
+        forged[0].proof_of_stake.validator = abc.get_address();
+        assert!(!light.extend(1, &forged));
+        // Headers with invalid validator proofs are rejected
+        let mut forged = chain.get_headers(1, 5).unwrap();
+        if let Some(proof) = forged[0].proof_of_stake.proof.as_mut() {
+            proof.attempt += 1;
+        }
+        assert!(!light.extend(1, &forged));
+
+        assert!(light.extend(1, &chain.get_headers(1, 5).unwrap()));
+        assert_eq!(light.height(), 6);
+        // Not heavier than the known headers
+        assert!(!light.extend(3, &chain.get_headers(3, 3).unwrap()));
+        // Not linked to the known headers
+        assert!(!light.extend(2, &chain.get_headers(3, 3).unwrap()));
+
+        let balance_key = keys::account_balance(&abc.get_address(), ContractId::Ziesha);
+        let balance = chain
+            .get_balance(abc.get_address(), ContractId::Ziesha)
+            .unwrap();
+        let proof = chain.get_state_proof(balance_key.clone()).unwrap();
+        assert!(light.verify(5, &balance_key, balance.into(), false, &proof));
+        assert!(!light.verify(5, &balance_key, (balance + Amount(1)).into(), false, &proof));
+        assert!(!light.verify(6, &balance_key, balance.into(), false, &proof));
+
+        // Absent keys are proven with proofs of absence
+        let nonce_key = keys::nonce(&abc.get_address());
+        let proof = chain.get_state_proof(nonce_key.clone()).unwrap();
+        assert!(light.verify(5, &nonce_key, 0u32.into(), true, &proof));
+        assert!(!light.verify(5, &nonce_key, 1u32.into(), false, &proof));
+        assert!(!light.verify(5, &balance_key, 0u32.into(), true, &proof));
+    }
+
+    #[test]
+    fn test_light_client_checkpoint() {
+        let validator = TxBuilder::new(&Vec::from("VALIDATOR"));
+        let mut conf = crate::config::blockchain::get_test_blockchain_config();
+        conf.check_validator = true;
+        let mut chain = KvStoreChain::new(RamKvStore::new(), conf.clone()).unwrap();
+        let mut timestamp = 0;
+        let mut checkpoint = None;
+        for i in 0..8 {
+            if i == 5 {
+                checkpoint = Some(LightCheckpoint::new(&chain).unwrap());
+            }
+            let block = (1..100)
+                .find_map(|_| {
+                    timestamp += 30;
+                    chain.draft_block(timestamp, &[], &validator, true).unwrap()
+                })
+                .unwrap();
+            chain.extend(i as u64 + 1, &[block]).unwrap();
+        }
+        let checkpoint = checkpoint.unwrap();
+        assert_eq!(checkpoint.header.number, 5);
+
+        // Stakes should match the state of the checkpoint
+        let mut forged = checkpoint.clone();
+        forged.stakers[0].stake += Amount(1);
+        assert!(matches!(
+            LightClient::from_checkpoint(&conf, forged, vec![]),
+            Err(BlockchainError::InvalidLightCheckpoint)
+        ));
+        // Checkpoint should match the checkpoints of the config
+        let mut other_conf = conf.clone();
+        other_conf.checkpoints.insert(5, Default::default());
+        assert!(matches!(
+            LightClient::from_checkpoint(&other_conf, checkpoint.clone(), vec![]),
+            Err(BlockchainError::CheckpointMismatch)
+        ));
+
+        let mut light = LightClient::from_checkpoint(&conf, checkpoint, vec![]).unwrap();
+        assert_eq!(light.height(), 6);
+        assert!(light.header(4).is_none());
+        assert_eq!(light.sync_start(), 6);
+        // Headers before the checkpoint cannot be replaced
+        assert!(!light.extend(5, &chain.get_headers(5, 4).unwrap()));
+        assert!(light.extend(6, &chain.get_headers(6, 3).unwrap()));
+        assert_eq!(light.height(), 9);
+        assert_eq!(light.header(8), chain.get_headers(8, 1).unwrap().first());
+    }
+}
@@ -1,16 +1,23 @@
-use crate::blockchain::{TransactionMetadata, TransactionStats};
+use crate::blockchain::{StateProof, TransactionMetadata, TransactionStats};
 use crate::core::{
-    Address, Amount, Block, GeneralAddress, GeneralTransaction, Header, Money, Signature, Token,
-    TransactionAndDelta, TransactionKind, Undelegation, ValidatorProof,
+    Address, Amount, Block, ContractAccount, ContractId, GeneralAddress, GeneralTransaction,
+    Header, Money, Signature, Token, TransactionAndDelta, TransactionKind, Undelegation,
+    ValidatorProof,
 };
-use crate::mpn::MpnWork;
+use crate::logs::LogRecord;
+use crate::mpn::{MpnRewardPolicy, MpnWork};
 use crate::zk;
 use std::collections::HashMap;
+use std::net::IpAddr;
 use thiserror::Error;
 
 use super::{
-    explorer::{ExplorerBlock, ExplorerGeneralTransaction, ExplorerMpnAccount, ExplorerStaker},
-    Peer, PeerAddress,
+    explorer::{
+        ExplorerBlock, ExplorerDataPairs, ExplorerDeltaPairs, ExplorerGeneralTransaction,
+        ExplorerMpnAccount, ExplorerStaker,
+    },
+    light::LightCheckpoint,
+    Peer, PeerAddress, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
 };
 use serde::{Deserialize, Serialize};
 
@@ -35,20 +42,35 @@ pub struct GetStatsResponse {
     pub epoch: u32,
     pub slot: u32,
     pub version: String,
+    pub protocol_version: u32,
     pub network: String,
     pub validator_claim: Option<ValidatorClaim>,
     pub median_fees: HashMap<TransactionKind, Amount>,
+    pub mpn_rewards: MpnRewardPolicy,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GetAccountRequest {
     pub address: String,
+    /// Also return merkle proofs of the values against the state root
+    #[serde(default)]
+    pub prove: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct AccountProof {
+    /// Number of the header whose state root the proofs are against
+    pub height: u64,
+    pub nonce: StateProof,
+    pub mpn_deposit_nonce: StateProof,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct GetAccountResponse {
     pub nonce: u32,
     pub mpn_deposit_nonce: u32,
+    #[serde(default)]
+    pub proof: Option<AccountProof>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -67,11 +89,119 @@ pub struct GetDelegationsResponse {
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GetMpnAccountRequest {
     pub address: String,
+    /// Also return merkle proofs of the account against the MPN contract
+    #[serde(default)]
+    pub prove: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MpnAccountProof {
+    /// Number of the header whose state root the contract account is proven
+    /// against
+    pub height: u64,
+    pub contract_account: ContractAccount,
+    pub contract_account_proof: StateProof,
+    pub account: zk::MpnAccountProof,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GetMpnAccountResponse {
     pub account: zk::MpnAccount,
+    /// Only given for existing accounts, the absence of an account cannot be
+    /// proven
+    #[serde(default)]
+    pub proof: Option<MpnAccountProof>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GetContractStateRequest {
+    pub contract_id: String,
+    /// Locator of the last scalar of the previous page
+    #[serde(default)]
+    pub after: Option<String>,
+    pub page_size: usize,
+    /// Also return a state proof of the contract account
+    #[serde(default)]
+    pub prove: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GetContractStateResponse {
+    /// Height of the contract state the page is taken from
+    pub height: u64,
+    pub compressed_state: zk::ZkCompressedState,
+    pub state_model: zk::ZkStateModel,
+    pub state: ExplorerDataPairs,
+    /// Locator to continue from, missing on the last page
+    pub next: Option<String>,
+    #[serde(default)]
+    pub proof: Option<ContractAccountProof>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GetContractDeltasRequest {
+    pub contract_id: String,
+    pub since_height: u64,
+    /// Also return a state proof of the contract account
+    #[serde(default)]
+    pub prove: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GetContractDeltasResponse {
+    /// Height of the contract state after applying the deltas
+    pub height: u64,
+    pub compressed_state: zk::ZkCompressedState,
+    /// Deltas of the heights after `since_height`, in order
+    pub deltas: Vec<ExplorerDeltaPairs>,
+    #[serde(default)]
+    pub proof: Option<ContractAccountProof>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ContractAccountProof {
+    /// Number of the header whose state root the contract account is proven
+    /// against
+    pub height: u64,
+    pub contract_account: ContractAccount,
+    pub proof: StateProof,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GetDepositNonceRequest {
+    pub address: String,
+    pub contract_id: String,
+    /// Also return a merkle proof of the nonce against the state root
+    #[serde(default)]
+    pub prove: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct DepositNonceProof {
+    /// Number of the header whose state root the proof is against
+    pub height: u64,
+    pub nonce: StateProof,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct GetDepositNonceResponse {
+    /// Nonce of the last deposit of the address to the contract
+    pub nonce: u32,
+    #[serde(default)]
+    pub proof: Option<DepositNonceProof>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GetDeltasRequest {
+    pub contract_id: ContractId,
+    pub since_height: u64,
+    pub count: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GetDeltasResponse {
+    /// Deltas of the heights after `since_height`, in order
+    pub deltas: Vec<zk::ZkDeltaPairs>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -85,18 +215,106 @@ pub struct GetExplorerMpnAccountsResponse {
     pub accounts: HashMap<u64, ExplorerMpnAccount>,
 }
 
+// Messages served by the nodes running this version of the protocol
+const SUPPORTED_MESSAGES: &[&str] = &[
+    "/stats",
+    "/peers",
+    "/bincode/peers",
+    "/bincode/headers",
+    "/bincode/blocks",
+    "/bincode/deltas",
+    "/bincode/mempool",
+    "/bincode/transact",
+    "/claim",
+    "/bincode/mpn/work",
+    "/bincode/mpn/solution",
+];
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Json,
+    Bincode,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ProtocolInfo {
+    pub version: u32,
+    pub min_version: u32,
+    pub messages: Vec<String>,
+    pub encodings: Vec<Encoding>,
+}
+
+impl ProtocolInfo {
+    pub fn current() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            min_version: MIN_PROTOCOL_VERSION,
+            messages: SUPPORTED_MESSAGES.iter().map(|m| m.to_string()).collect(),
+            encodings: vec![Encoding::Json, Encoding::Bincode],
+        }
+    }
+
+    /// Highest protocol version both sides are able to talk with
+    pub fn negotiate(&self, other: &ProtocolInfo) -> Option<u32> {
+        let version = std::cmp::min(self.version, other.version);
+        if version >= self.min_version && version >= other.min_version {
+            Some(version)
+        } else {
+            None
+        }
+    }
+
+    pub fn supports(&self, message: &str) -> bool {
+        self.messages.iter().any(|m| m == message)
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub enum HandshakeRequest {
     Node(PeerAddress),
     Client,
 }
 
+// New fields should only be appended to the end of the handshake response,
+// so that the older nodes are still able to decode it.
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct HandshakeResponse {
     pub peer: Peer,
     pub timestamp: u32,
     pub timestamp_offset: i32,
     pub validator_claim: Option<ValidatorClaim>,
+    pub protocol: ProtocolInfo,
+}
+
+// Handshake response of the nodes predating protocol negotiation
+#[derive(Deserialize, Serialize, Debug, Clone)]
+struct LegacyHandshakeResponse {
+    peer: Peer,
+    timestamp: u32,
+    timestamp_offset: i32,
+    validator_claim: Option<ValidatorClaim>,
+}
+
+impl HandshakeResponse {
+    /// Decodes a handshake response, responses of the nodes predating
+    /// protocol negotiation are reported as protocol version 0
+    pub fn decode(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize::<Self>(bytes).or_else(|e| {
+            let legacy = bincode::deserialize::<LegacyHandshakeResponse>(bytes).map_err(|_| e)?;
+            Ok(Self {
+                peer: legacy.peer,
+                timestamp: legacy.timestamp,
+                timestamp_offset: legacy.timestamp_offset,
+                validator_claim: legacy.validator_claim,
+                protocol: ProtocolInfo {
+                    version: 0,
+                    min_version: 0,
+                    messages: vec![],
+                    encodings: vec![Encoding::Json, Encoding::Bincode],
+                },
+            })
+        })
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -156,6 +374,14 @@ pub struct GetHeadersResponse {
     pub headers: Vec<Header>,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GetLightCheckpointRequest {}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GetLightCheckpointResponse {
+    pub checkpoint: LightCheckpoint,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct TransactRequest {
     pub tx: GeneralTransaction,
@@ -196,6 +422,16 @@ pub struct GetDebugDataResponse {
 pub struct GetBalanceRequest {
     pub address: String,
     pub token_id: String,
+    /// Also return a merkle proof of the balance against the state root
+    #[serde(default)]
+    pub prove: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct BalanceProof {
+    /// Number of the header whose state root the proof is against
+    pub height: u64,
+    pub balance: StateProof,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
@@ -203,6 +439,8 @@ pub struct GetBalanceResponse {
     pub balance: Amount,
     pub name: String,
     pub symbol: String,
+    #[serde(default)]
+    pub proof: Option<BalanceProof>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -368,6 +606,8 @@ pub struct GenerateBlockResponse {
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GetMpnWorkRequest {
     pub address: Address,
+    /// Increases with every signed request of the worker
+    pub nonce: u64,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -379,6 +619,8 @@ pub struct GetMpnWorkResponse {
 pub struct PostMpnSolutionRequest {
     pub prover: Address,
     pub proofs: HashMap<usize, zk::ZkProof>,
+    /// Increases with every signed request of the worker
+    pub nonce: u64,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -396,6 +638,61 @@ pub struct PostMpnWorkerResponse {
     pub accepted: bool,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GetMpnWorkersRequest {}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MpnWorkerInfo {
+    pub address: String,
+    pub registered: bool,
+    pub assigned: u64,
+    pub accepted: u64,
+    pub rewards: Amount,
+    pub late: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GetMpnWorkersResponse {
+    pub workers: Vec<MpnWorkerInfo>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RemoveMpnWorkerRequest {
+    pub address: Address,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RemoveMpnWorkerResponse {
+    pub removed: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BanRequest {
+    pub ip: IpAddr,
+    pub secs: u32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BanResponse {}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UnbanRequest {
+    pub ip: IpAddr,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UnbanResponse {
+    pub was_banned: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct FlushMempoolRequest {}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct FlushMempoolResponse {
+    pub removed: usize,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GetExplorerMempoolRequest {}
 
@@ -415,9 +712,76 @@ pub struct CheckTransactionResponse {
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct GetLogsRequest {}
+pub struct GetLogsRequest {
+    pub level: Option<String>,
+    pub module: Option<String>,
+    pub since: Option<u64>,
+}
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct GetLogsResponse {
-    pub logs: Vec<String>,
+    pub logs: Vec<LogRecord>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PostLogLevelRequest {
+    pub level: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PostLogLevelResponse {
+    pub level: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GetMetricsRequest {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handshake_response_decode() {
+        let legacy = LegacyHandshakeResponse {
+            peer: Peer {
+                address: "127.0.0.1:8765".parse().unwrap(),
+                pub_key: Default::default(),
+                height: 10,
+                power: 5.,
+            },
+            timestamp: 1234,
+            timestamp_offset: -2,
+            validator_claim: None,
+        };
+        let resp = HandshakeResponse {
+            peer: legacy.peer.clone(),
+            timestamp: legacy.timestamp,
+            timestamp_offset: legacy.timestamp_offset,
+            validator_claim: None,
+            protocol: ProtocolInfo::current(),
+        };
+
+        // Legacy nodes are able to decode the new responses
+        let new_bytes = bincode::serialize(&resp).unwrap();
+        assert_eq!(
+            bincode::deserialize::<LegacyHandshakeResponse>(&new_bytes)
+                .unwrap()
+                .timestamp,
+            1234
+        );
+        assert_eq!(
+            HandshakeResponse::decode(&new_bytes).unwrap().protocol,
+            ProtocolInfo::current()
+        );
+
+        // Legacy responses are decoded as incompatible protocol versions
+        let legacy_resp = HandshakeResponse::decode(&bincode::serialize(&legacy).unwrap()).unwrap();
+        assert_eq!(legacy_resp.timestamp_offset, -2);
+        assert_eq!(legacy_resp.protocol.version, 0);
+        assert!(ProtocolInfo::current()
+            .negotiate(&legacy_resp.protocol)
+            .is_none());
+
+        assert!(HandshakeResponse::decode(&[1, 2, 3]).is_err());
+    }
 }
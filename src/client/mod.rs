@@ -1,8 +1,9 @@
-use crate::blockchain::TransactionMetadata;
-use crate::core::{Address, ContractId, GeneralTransaction, MpnAddress, Signer};
+use crate::blockchain::{BlockchainConfig, StateProof, TransactionMetadata};
+use crate::core::{Address, Amount, ContractId, GeneralTransaction, MpnAddress, Signer, ZkHasher};
 use crate::crypto::ed25519;
 use crate::crypto::SignatureScheme;
-use crate::zk::ZkProof;
+use crate::db::{keys, Blob, StringKey};
+use crate::zk::{ZkCompressedState, ZkDataPairs, ZkDeltaPairs, ZkProof, ZkState, ZkStateModel};
 use hyper::body::{Bytes, HttpBody};
 use hyper::header::HeaderValue;
 use hyper::{Body, Method, Request, Response, StatusCode};
@@ -10,22 +11,51 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, RwLock};
 use tokio::time::timeout;
 
 mod error;
 pub mod explorer;
+pub mod light;
 pub mod messages;
 pub mod utils;
 pub use error::NodeError;
+use light::*;
 use messages::*;
 
 pub type Timestamp = u32;
 
+/// Number of the scalars fetched per request when downloading a contract state
+pub const CONTRACT_STATE_PAGE_SIZE: usize = 1024;
+
 pub const SIGNATURE_HEADER: &str = "X-ZIESHA-SIGNATURE";
 pub const NETWORK_HEADER: &str = "X-ZIESHA-NETWORK-NAME";
+pub const PROTOCOL_HEADER: &str = "X-ZIESHA-PROTOCOL-VERSION";
+pub const ADMIN_TOKEN_HEADER: &str = "X-ZIESHA-ADMIN-TOKEN";
+
+/// Version of the peer-to-peer protocol, bump on incompatible changes
+pub const PROTOCOL_VERSION: u32 = 2;
+/// Oldest protocol version this node is able to talk with
+pub const MIN_PROTOCOL_VERSION: u32 = 2;
+
+/// Nonce of a signed request, the milliseconds since the unix epoch, but
+/// always greater than the previous nonce
+fn next_request_nonce() -> u64 {
+    static LAST_NONCE: AtomicU64 = AtomicU64::new(0);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    let prev = LAST_NONCE
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+            Some(now.max(last + 1))
+        })
+        .unwrap();
+    now.max(prev + 1)
+}
 
 #[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PeerAddress(pub SocketAddr); // ip, port
@@ -60,6 +90,7 @@ pub struct Peer {
 pub struct NodeRequest {
     pub limit: Limit,
     pub socket_addr: Option<SocketAddr>,
+    pub admin: bool, // Request has come through the admin listener
     pub body: Request<Body>,
     pub resp: mpsc::UnboundedSender<Result<Response<Body>, NodeError>>,
 }
@@ -67,6 +98,7 @@ pub struct NodeRequest {
 pub struct OutgoingSender {
     pub priv_key: ed25519::PrivateKey,
     pub network: String,
+    pub admin_token: Option<String>,
     pub chan: mpsc::UnboundedSender<NodeRequest>,
 }
 
@@ -93,9 +125,18 @@ impl OutgoingSender {
             mpsc::unbounded_channel::<Result<Response<Body>, NodeError>>();
         body.headers_mut()
             .insert(NETWORK_HEADER, HeaderValue::from_str(&self.network)?);
+        body.headers_mut().insert(
+            PROTOCOL_HEADER,
+            HeaderValue::from_str(&PROTOCOL_VERSION.to_string())?,
+        );
+        if let Some(admin_token) = &self.admin_token {
+            body.headers_mut()
+                .insert(ADMIN_TOKEN_HEADER, HeaderValue::from_str(admin_token)?);
+        }
         let req = NodeRequest {
             limit: limit.clone(),
             socket_addr: None,
+            admin: false,
             body,
             resp: resp_snd,
         };
@@ -189,6 +230,25 @@ impl OutgoingSender {
         Ok(resp)
     }
 
+    /// Handshakes with a node, tolerating the nodes predating protocol negotiation
+    pub async fn handshake(
+        &self,
+        addr: String,
+        req: HandshakeRequest,
+        limit: Limit,
+    ) -> Result<HandshakeResponse, NodeError> {
+        let bytes = bincode::serialize(&req)?;
+        let req = self.sign(
+            Request::builder()
+                .method(Method::POST)
+                .uri(&addr)
+                .header("content-type", "application/octet-stream"),
+            bytes,
+        )?;
+        let body = self.raw(req, limit).await?;
+        Ok(HandshakeResponse::decode(&body)?)
+    }
+
     pub async fn json_post<Req: serde::Serialize, Resp: serde::de::DeserializeOwned>(
         &self,
         addr: String,
@@ -230,11 +290,35 @@ impl OutgoingSender {
     }
 }
 
+/// Local copy of the state of a contract, for executing it off-chain
+#[derive(Debug, Clone)]
+pub struct ContractState {
+    pub height: u64,
+    pub state_model: ZkStateModel,
+    pub data: ZkState,
+}
+
+impl ContractState {
+    fn check(&self, compressed_state: ZkCompressedState) -> Result<(), NodeError> {
+        match self
+            .data
+            .data
+            .compress::<ZkHasher>(self.state_model.clone())
+        {
+            Ok(state) if state == compressed_state => Ok(()),
+            _ => Err(NodeError::InvalidContractState),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct BazukaClient {
     pub peer: PeerAddress,
     pub sender: Arc<OutgoingSender>,
     pub limit: Option<Limit>,
+    /// In light mode, account data served by the node is verified against
+    /// the headers synced from the light client peers
+    pub light: Option<Arc<RwLock<LightClient>>>,
 }
 
 impl BazukaClient {
@@ -243,6 +327,27 @@ impl BazukaClient {
         peer: PeerAddress,
         network: String,
         limit: Option<Limit>,
+    ) -> (impl futures::Future<Output = Result<(), NodeError>>, Self) {
+        Self::connect_with_token(priv_key, peer, network, None, limit)
+    }
+
+    /// Connects to the admin listener of a node
+    pub fn connect_admin(
+        priv_key: ed25519::PrivateKey,
+        peer: PeerAddress,
+        network: String,
+        admin_token: String,
+        limit: Option<Limit>,
+    ) -> (impl futures::Future<Output = Result<(), NodeError>>, Self) {
+        Self::connect_with_token(priv_key, peer, network, Some(admin_token), limit)
+    }
+
+    fn connect_with_token(
+        priv_key: ed25519::PrivateKey,
+        peer: PeerAddress,
+        network: String,
+        admin_token: Option<String>,
+        limit: Option<Limit>,
     ) -> (impl futures::Future<Output = Result<(), NodeError>>, Self) {
         let (sender_send, mut sender_recv) = mpsc::unbounded_channel::<NodeRequest>();
         let client_loop = async move {
@@ -268,12 +373,111 @@ impl BazukaClient {
                 sender: Arc::new(OutgoingSender {
                     priv_key,
                     network,
+                    admin_token,
                     chan: sender_send,
                 }),
                 limit,
+                light: None,
             },
         )
     }
+
+    /// Switches to light mode, where headers are synced from `peers`
+    pub fn light(
+        mut self,
+        config: &BlockchainConfig,
+        peers: Vec<PeerAddress>,
+    ) -> Result<Self, NodeError> {
+        self.light = Some(Arc::new(RwLock::new(LightClient::new(config, peers)?)));
+        Ok(self)
+    }
+
+    /// Switches to light mode, starting from a trusted checkpoint instead of
+    /// the genesis block
+    pub fn light_from(
+        mut self,
+        config: &BlockchainConfig,
+        checkpoint: LightCheckpoint,
+        peers: Vec<PeerAddress>,
+    ) -> Result<Self, NodeError> {
+        self.light = Some(Arc::new(RwLock::new(LightClient::from_checkpoint(
+            config, checkpoint, peers,
+        )?)));
+        Ok(self)
+    }
+
+    /// Syncs the headers of the light client with its peers and returns the
+    /// synced height. Peers which fail to answer are skipped, and at most
+    /// `LIGHT_CLIENT_MAX_HEADERS_PER_SYNC` headers are taken from each peer.
+    pub async fn sync_headers(&self) -> Result<u64, NodeError> {
+        let light = if let Some(light) = &self.light {
+            light
+        } else {
+            return Ok(0);
+        };
+        let peers = light.read().await.peers.clone();
+        for peer in peers {
+            let from = light.read().await.sync_start();
+            let mut headers = Vec::new();
+            while (headers.len() as u64) < LIGHT_CLIENT_MAX_HEADERS_PER_SYNC {
+                let count = std::cmp::min(
+                    LIGHT_CLIENT_HEADERS_PER_REQUEST,
+                    LIGHT_CLIENT_MAX_HEADERS_PER_SYNC - headers.len() as u64,
+                );
+                match self
+                    .sender
+                    .bincode_get::<GetHeadersRequest, GetHeadersResponse>(
+                        format!("http://{}/bincode/headers", peer),
+                        GetHeadersRequest {
+                            since: from + headers.len() as u64,
+                            count,
+                        },
+                        self.limit.clone().unwrap_or_default(),
+                    )
+                    .await
+                {
+                    Ok(resp) if resp.headers.len() as u64 > count => {
+                        log::warn!("Peer {} sent more headers than requested!", peer);
+                        break;
+                    }
+                    Ok(resp) if !resp.headers.is_empty() => {
+                        headers.extend(resp.headers);
+                    }
+                    Ok(_) => break,
+                    Err(e) => {
+                        log::warn!("Cannot sync headers with {}: {}", peer, e);
+                        break;
+                    }
+                }
+            }
+            light.write().await.extend(from, &headers);
+        }
+        let height = light.read().await.height();
+        Ok(height)
+    }
+
+    async fn verify_state(
+        &self,
+        height: u64,
+        key: StringKey,
+        value: Blob,
+        is_default: bool,
+        proof: &StateProof,
+    ) -> Result<(), NodeError> {
+        if let Some(light) = &self.light {
+            if light.read().await.height() <= height {
+                self.sync_headers().await?;
+            }
+            let light = light.read().await;
+            if light.height() <= height {
+                return Err(NodeError::HeaderNotSynced(height));
+            }
+            if !light.verify(height, &key, value, is_default, proof) {
+                return Err(NodeError::InvalidStateProof);
+            }
+        }
+        Ok(())
+    }
     pub async fn shutdown(&self) -> Result<(), NodeError> {
         self.sender
             .json_post::<ShutdownRequest, ShutdownResponse>(
@@ -284,6 +488,20 @@ impl BazukaClient {
             .await?;
         Ok(())
     }
+    /// Checkpoint at the tip of the node, to start light clients from. The
+    /// node should be trusted.
+    pub async fn get_light_checkpoint(&self) -> Result<LightCheckpoint, NodeError> {
+        Ok(self
+            .sender
+            .bincode_get::<GetLightCheckpointRequest, GetLightCheckpointResponse>(
+                format!("http://{}/bincode/light/checkpoint", self.peer),
+                GetLightCheckpointRequest {},
+                self.limit.clone().unwrap_or_default(),
+            )
+            .await?
+            .checkpoint)
+    }
+
     pub async fn stats(&self) -> Result<GetStatsResponse, NodeError> {
         self.sender
             .json_get::<GetStatsRequest, GetStatsResponse>(
@@ -328,15 +546,38 @@ impl BazukaClient {
     }
 
     pub async fn get_account(&self, address: Address) -> Result<GetAccountResponse, NodeError> {
-        self.sender
+        let resp = self
+            .sender
             .json_get::<GetAccountRequest, GetAccountResponse>(
                 format!("http://{}/account", self.peer),
                 GetAccountRequest {
                     address: address.to_string(),
+                    prove: self.light.is_some(),
                 },
                 self.limit.clone().unwrap_or_default(),
             )
-            .await
+            .await?;
+        if let Some(light) = &self.light {
+            let proof = resp.proof.as_ref().ok_or(NodeError::InvalidStateProof)?;
+            let mpn_contract_id = light.read().await.mpn_config.mpn_contract_id;
+            self.verify_state(
+                proof.height,
+                keys::nonce(&address),
+                resp.nonce.into(),
+                resp.nonce == 0,
+                &proof.nonce,
+            )
+            .await?;
+            self.verify_state(
+                proof.height,
+                keys::deposit_nonce(&address, &mpn_contract_id),
+                resp.mpn_deposit_nonce.into(),
+                resp.mpn_deposit_nonce == 0,
+                &proof.mpn_deposit_nonce,
+            )
+            .await?;
+        }
+        Ok(resp)
     }
 
     pub async fn get_delegations(
@@ -361,16 +602,30 @@ impl BazukaClient {
         address: Address,
         token_id: ContractId,
     ) -> Result<GetBalanceResponse, NodeError> {
-        self.sender
+        let resp = self
+            .sender
             .json_get::<GetBalanceRequest, GetBalanceResponse>(
                 format!("http://{}/balance", self.peer),
                 GetBalanceRequest {
                     address: address.to_string(),
                     token_id: token_id.to_string(),
+                    prove: self.light.is_some(),
                 },
                 self.limit.clone().unwrap_or_default(),
             )
-            .await
+            .await?;
+        if self.light.is_some() {
+            let proof = resp.proof.as_ref().ok_or(NodeError::InvalidStateProof)?;
+            self.verify_state(
+                proof.height,
+                keys::account_balance(&address, token_id),
+                resp.balance.into(),
+                resp.balance == Amount(0),
+                &proof.balance,
+            )
+            .await?;
+        }
+        Ok(resp)
     }
 
     pub async fn get_token(&self, token_id: ContractId) -> Result<GetTokenInfoResponse, NodeError> {
@@ -389,17 +644,223 @@ impl BazukaClient {
         &self,
         mpn_address: MpnAddress,
     ) -> Result<GetMpnAccountResponse, NodeError> {
-        self.sender
+        let resp = self
+            .sender
             .json_get::<GetMpnAccountRequest, GetMpnAccountResponse>(
                 format!("http://{}/mpn/account", self.peer),
                 GetMpnAccountRequest {
                     address: mpn_address.to_string(),
+                    prove: self.light.is_some(),
+                },
+                self.limit.clone().unwrap_or_default(),
+            )
+            .await?;
+        if let Some(light) = &self.light {
+            // The node could prove any empty slot, which says nothing about
+            // where the account would be
+            if resp.account == Default::default() {
+                return Err(NodeError::MpnAccountAbsenceUnprovable);
+            }
+            let proof = resp.proof.as_ref().ok_or(NodeError::InvalidStateProof)?;
+            let mpn_config = light.read().await.mpn_config.clone();
+            self.verify_state(
+                proof.height,
+                keys::contract_account(&mpn_config.mpn_contract_id),
+                proof.contract_account.clone().into(),
+                false,
+                &proof.contract_account_proof,
+            )
+            .await?;
+            let valid = resp.account.address == mpn_address.pub_key.0.decompress()
+                && proof.account.verify::<ZkHasher>(
+                    &resp.account,
+                    &proof.contract_account.compressed_state,
+                    mpn_config.log4_tree_size,
+                    mpn_config.log4_token_tree_size,
+                );
+            if !valid {
+                return Err(NodeError::InvalidStateProof);
+            }
+        }
+        Ok(resp)
+    }
+
+    pub async fn get_contract_state(
+        &self,
+        contract_id: ContractId,
+        after: Option<String>,
+        page_size: usize,
+    ) -> Result<GetContractStateResponse, NodeError> {
+        self.sender
+            .json_get::<GetContractStateRequest, GetContractStateResponse>(
+                format!("http://{}/contract/state", self.peer),
+                GetContractStateRequest {
+                    contract_id: contract_id.to_string(),
+                    after,
+                    page_size,
+                    prove: self.light.is_some(),
                 },
                 self.limit.clone().unwrap_or_default(),
             )
             .await
     }
 
+    pub async fn get_deposit_nonce(
+        &self,
+        address: Address,
+        contract_id: ContractId,
+    ) -> Result<GetDepositNonceResponse, NodeError> {
+        let resp = self
+            .sender
+            .json_get::<GetDepositNonceRequest, GetDepositNonceResponse>(
+                format!("http://{}/contract/deposit_nonce", self.peer),
+                GetDepositNonceRequest {
+                    address: address.to_string(),
+                    contract_id: contract_id.to_string(),
+                    prove: self.light.is_some(),
+                },
+                self.limit.clone().unwrap_or_default(),
+            )
+            .await?;
+        if self.light.is_some() {
+            let proof = resp.proof.as_ref().ok_or(NodeError::InvalidStateProof)?;
+            self.verify_state(
+                proof.height,
+                keys::deposit_nonce(&address, &contract_id),
+                resp.nonce.into(),
+                resp.nonce == 0,
+                &proof.nonce,
+            )
+            .await?;
+        }
+        Ok(resp)
+    }
+
+    pub async fn get_contract_deltas(
+        &self,
+        contract_id: ContractId,
+        since_height: u64,
+    ) -> Result<GetContractDeltasResponse, NodeError> {
+        self.sender
+            .json_get::<GetContractDeltasRequest, GetContractDeltasResponse>(
+                format!("http://{}/contract/deltas", self.peer),
+                GetContractDeltasRequest {
+                    contract_id: contract_id.to_string(),
+                    since_height,
+                    prove: self.light.is_some(),
+                },
+                self.limit.clone().unwrap_or_default(),
+            )
+            .await
+    }
+
+    /// Checks a contract state against the compressed state given by the
+    /// node. In light mode, the compressed state is instead taken from the
+    /// contract account, proven against the synced headers.
+    async fn check_contract_state(
+        &self,
+        contract_id: ContractId,
+        state: &ContractState,
+        compressed_state: ZkCompressedState,
+        proof: Option<&ContractAccountProof>,
+    ) -> Result<(), NodeError> {
+        if self.light.is_none() {
+            return state.check(compressed_state);
+        }
+        let proof = proof.ok_or(NodeError::InvalidStateProof)?;
+        self.verify_state(
+            proof.height,
+            keys::contract_account(&contract_id),
+            proof.contract_account.clone().into(),
+            false,
+            &proof.proof,
+        )
+        .await?;
+        if proof.contract_account.height != state.height {
+            return Err(NodeError::InvalidContractState);
+        }
+        state.check(proof.contract_account.compressed_state)
+    }
+
+    /// Downloads the whole state of a contract, page by page, and checks it
+    /// (See `check_contract_state`). Fails if the contract gets updated in
+    /// the middle of the download.
+    pub async fn download_contract_state(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<ContractState, NodeError> {
+        let first = self
+            .get_contract_state(contract_id, None, CONTRACT_STATE_PAGE_SIZE)
+            .await?;
+        let mut state = ContractState {
+            height: first.height,
+            state_model: first.state_model.clone(),
+            data: ZkState::default(),
+        };
+        let mut page = first.state.clone();
+        let mut next = first.next.clone();
+        loop {
+            state
+                .data
+                .apply_delta(&ZkDataPairs::try_from(&page)?.as_delta());
+            let after = if let Some(after) = next {
+                after
+            } else {
+                break;
+            };
+            let resp = self
+                .get_contract_state(contract_id, Some(after), CONTRACT_STATE_PAGE_SIZE)
+                .await?;
+            if resp.height != state.height {
+                return Err(NodeError::InvalidContractState);
+            }
+            page = resp.state;
+            next = resp.next;
+        }
+        self.check_contract_state(
+            contract_id,
+            &state,
+            first.compressed_state,
+            first.proof.as_ref(),
+        )
+        .await?;
+        Ok(state)
+    }
+
+    /// Applies the deltas of the contract since the height of the local
+    /// state, and checks the result (See `check_contract_state`). The state
+    /// has to be downloaded again if the contract got rolled back below its
+    /// height.
+    pub async fn sync_contract_state(
+        &self,
+        contract_id: ContractId,
+        state: &mut ContractState,
+    ) -> Result<(), NodeError> {
+        let mut synced = state.clone();
+        loop {
+            let resp = self.get_contract_deltas(contract_id, synced.height).await?;
+            if resp.height < synced.height {
+                return Err(NodeError::InvalidContractState);
+            }
+            if resp.deltas.is_empty() {
+                self.check_contract_state(
+                    contract_id,
+                    &synced,
+                    resp.compressed_state,
+                    resp.proof.as_ref(),
+                )
+                .await?;
+                *state = synced;
+                return Ok(());
+            }
+            for delta in resp.deltas.iter() {
+                synced.data.apply_delta(&ZkDeltaPairs::try_from(delta)?);
+            }
+            synced.height = resp.height;
+            synced.check(resp.compressed_state)?;
+        }
+    }
+
     pub async fn transact(&self, tx: GeneralTransaction) -> Result<TransactResponse, NodeError> {
         self.sender
             .bincode_post::<TransactRequest, TransactResponse>(
@@ -429,7 +890,20 @@ impl BazukaClient {
         self.sender
             .bincode_get::<GetMpnWorkRequest, GetMpnWorkResponse>(
                 format!("http://{}/bincode/mpn/work", self.peer),
-                GetMpnWorkRequest { address },
+                GetMpnWorkRequest {
+                    address,
+                    nonce: next_request_nonce(),
+                },
+                self.limit.clone().unwrap_or_default(),
+            )
+            .await
+    }
+
+    pub async fn get_mpn_workers(&self) -> Result<GetMpnWorkersResponse, NodeError> {
+        self.sender
+            .json_get::<GetMpnWorkersRequest, GetMpnWorkersResponse>(
+                format!("http://{}/mpn/workers", self.peer),
+                GetMpnWorkersRequest {},
                 self.limit.clone().unwrap_or_default(),
             )
             .await
@@ -448,6 +922,59 @@ impl BazukaClient {
             .await
     }
 
+    pub async fn remove_mpn_worker(
+        &self,
+        address: Address,
+    ) -> Result<RemoveMpnWorkerResponse, NodeError> {
+        self.sender
+            .bincode_post::<RemoveMpnWorkerRequest, RemoveMpnWorkerResponse>(
+                format!("http://{}/bincode/mpn/worker/remove", self.peer),
+                RemoveMpnWorkerRequest { address },
+                self.limit.clone().unwrap_or_default(),
+            )
+            .await
+    }
+
+    pub async fn ban(&self, ip: IpAddr, secs: u32) -> Result<BanResponse, NodeError> {
+        self.sender
+            .json_post::<BanRequest, BanResponse>(
+                format!("http://{}/ban", self.peer),
+                BanRequest { ip, secs },
+                self.limit.clone().unwrap_or_default(),
+            )
+            .await
+    }
+
+    pub async fn unban(&self, ip: IpAddr) -> Result<UnbanResponse, NodeError> {
+        self.sender
+            .json_post::<UnbanRequest, UnbanResponse>(
+                format!("http://{}/unban", self.peer),
+                UnbanRequest { ip },
+                self.limit.clone().unwrap_or_default(),
+            )
+            .await
+    }
+
+    pub async fn set_log_level(&self, level: String) -> Result<PostLogLevelResponse, NodeError> {
+        self.sender
+            .json_post::<PostLogLevelRequest, PostLogLevelResponse>(
+                format!("http://{}/log_level", self.peer),
+                PostLogLevelRequest { level },
+                self.limit.clone().unwrap_or_default(),
+            )
+            .await
+    }
+
+    pub async fn flush_mempool(&self) -> Result<FlushMempoolResponse, NodeError> {
+        self.sender
+            .json_post::<FlushMempoolRequest, FlushMempoolResponse>(
+                format!("http://{}/mempool/flush", self.peer),
+                FlushMempoolRequest {},
+                self.limit.clone().unwrap_or_default(),
+            )
+            .await
+    }
+
     pub async fn post_mpn_proof(
         &self,
         prover: Address,
@@ -456,7 +983,11 @@ impl BazukaClient {
         self.sender
             .bincode_post::<PostMpnSolutionRequest, PostMpnSolutionResponse>(
                 format!("http://{}/bincode/mpn/solution", self.peer),
-                PostMpnSolutionRequest { prover, proofs },
+                PostMpnSolutionRequest {
+                    prover,
+                    proofs,
+                    nonce: next_request_nonce(),
+                },
                 self.limit.clone().unwrap_or_default(),
             )
             .await
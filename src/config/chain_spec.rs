@@ -0,0 +1,485 @@
+use super::blockchain::{get_mpn_contract, get_ziesha_token_creation_tx};
+
+use crate::blockchain::BlockchainConfig;
+use crate::core::{
+    Address, Amount, Block, ContractId, Header, Money, MpnAddress, ProofOfStake, Ratio,
+    RegularSendEntry, Signature, Transaction, TransactionData,
+};
+use crate::crypto::vrf::PublicKey as VrfPublicKey;
+use crate::mpn::setup::MpnVerifyingKeys;
+use crate::mpn::MpnConfig;
+use crate::zk;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ChainSpecError {
+    #[error("checkpoint hash of height {0} is invalid")]
+    InvalidCheckpoint(u64),
+    #[error("dummy proofs are only supported by dev-proofs builds")]
+    DummyProofsNotSupported,
+    #[error("network name is empty")]
+    EmptyNetwork,
+    #[error("{0} should be greater than zero")]
+    ZeroValue(&'static str),
+    #[error("{0} should not be larger than {MAX_LOG4_TREE_SIZE}")]
+    TreeSizeTooLarge(&'static str),
+    #[error("validators are checked but there are no genesis validators")]
+    NoValidators,
+    #[error("genesis validator {0} has no stake")]
+    ZeroStake(String),
+}
+
+/// Merkle tree indices are 64-bit, so a tree may not have more than 4^31 leaves
+const MAX_LOG4_TREE_SIZE: u8 = 31;
+
+/// Values which have a human readable string form are kept as strings
+mod as_string {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    pub fn serialize<T: Display, S: Serializer>(v: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(v)
+    }
+
+    pub fn deserialize<'de, T: FromStr, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(|_| de::Error::custom("invalid value"))
+    }
+}
+
+/// Verifying keys are kept as hex-encoded bincode
+mod vk_hex {
+    use crate::zk::groth16::Groth16VerifyingKey;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        vk: &Groth16VerifyingKey,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let bytes = bincode::serialize(vk).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Groth16VerifyingKey, D::Error> {
+        let bytes = hex::decode(String::deserialize(deserializer)?).map_err(de::Error::custom)?;
+        bincode::deserialize(&bytes).map_err(de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisAllocation {
+    #[serde(with = "as_string")]
+    pub address: Address,
+    pub amount: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisMpnAllocation {
+    #[serde(with = "as_string")]
+    pub address: MpnAddress,
+    pub amount: Amount,
+}
+
+/// A staker which is registered and delegated to in the genesis block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisValidator {
+    #[serde(with = "as_string")]
+    pub address: Address,
+    #[serde(with = "as_string")]
+    pub vrf_pub_key: VrfPublicKey,
+    pub commission: Ratio,
+    pub stake: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MpnSpec {
+    pub log4_tree_size: u8,
+    pub log4_token_tree_size: u8,
+    pub log4_deposit_batch_size: u8,
+    pub log4_withdraw_batch_size: u8,
+    pub log4_update_batch_size: u8,
+    pub num_update_batches: usize,
+    pub num_deposit_batches: usize,
+    pub num_withdraw_batches: usize,
+    #[serde(with = "vk_hex")]
+    pub deposit_vk: zk::groth16::Groth16VerifyingKey,
+    #[serde(with = "vk_hex")]
+    pub withdraw_vk: zk::groth16::Groth16VerifyingKey,
+    #[serde(with = "vk_hex")]
+    pub update_vk: zk::groth16::Groth16VerifyingKey,
+    /// Accept dummy proofs instead of checking them with the verifying keys,
+    /// so that MPN works are solved instantly on development networks
+    #[serde(default)]
+    pub dummy_proofs: bool,
+    /// Balances of the MPN accounts in the genesis state of the MPN contract
+    pub allocations: Vec<GenesisMpnAllocation>,
+}
+
+impl MpnSpec {
+    /// Switches the MPN circuits to the ones the given keys are generated for
+    pub fn set_verifying_keys(&mut self, keys: &MpnVerifyingKeys) {
+        self.log4_tree_size = keys.deposit.0.log4_tree_size;
+        self.log4_token_tree_size = keys.deposit.0.log4_token_tree_size;
+        self.log4_deposit_batch_size = keys.deposit.0.log4_batch_size;
+        self.log4_withdraw_batch_size = keys.withdraw.0.log4_batch_size;
+        self.log4_update_batch_size = keys.update.0.log4_batch_size;
+        self.deposit_vk = keys.deposit.1.clone();
+        self.withdraw_vk = keys.withdraw.1.clone();
+        self.update_vk = keys.update.1.clone();
+    }
+
+    /// Deposit, withdraw and update verifier keys of the MPN contract
+    pub fn verifier_keys(
+        &self,
+    ) -> Result<(zk::ZkVerifierKey, zk::ZkVerifierKey, zk::ZkVerifierKey), ChainSpecError> {
+        if self.dummy_proofs {
+            #[cfg(any(test, feature = "dev-proofs"))]
+            return Ok((
+                zk::ZkVerifierKey::Dummy,
+                zk::ZkVerifierKey::Dummy,
+                zk::ZkVerifierKey::Dummy,
+            ));
+            #[cfg(not(any(test, feature = "dev-proofs")))]
+            return Err(ChainSpecError::DummyProofsNotSupported);
+        }
+        Ok((
+            zk::ZkVerifierKey::Groth16(Box::new(self.deposit_vk.clone())),
+            zk::ZkVerifierKey::Groth16(Box::new(self.withdraw_vk.clone())),
+            zk::ZkVerifierKey::Groth16(Box::new(self.update_vk.clone())),
+        ))
+    }
+}
+
+/// Everything needed for building the genesis block and the consensus
+/// parameters of a network
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainSpec {
+    pub network: String,
+    pub chain_start_timestamp: u32,
+    pub slot_duration: u32,
+    pub slot_per_epoch: u32,
+    /// 1/reward_ratio of the Treasury supply is rewarded per block
+    pub reward_ratio: u64,
+    pub max_block_size: usize,
+    pub max_memo_length: usize,
+    pub testnet_height_limit: Option<u64>,
+    pub check_validator: bool,
+    pub max_validator_commission: Ratio,
+    pub teleport_log4_tree_size: u8,
+    #[serde(with = "as_string")]
+    pub teleport_contract_id: ContractId,
+    pub mpn: MpnSpec,
+    pub validators: Vec<GenesisValidator>,
+    pub allocations: Vec<GenesisAllocation>,
+    /// Block height -> hex of the header hash
+    #[serde(default)]
+    pub checkpoints: BTreeMap<u64, String>,
+}
+
+impl ChainSpec {
+    /// Checks the values which would otherwise stall or crash the chain once
+    /// it is running
+    pub fn validate(&self) -> Result<(), ChainSpecError> {
+        if self.network.trim().is_empty() {
+            return Err(ChainSpecError::EmptyNetwork);
+        }
+        for (name, value) in [
+            ("slot_duration", self.slot_duration as u64),
+            ("slot_per_epoch", self.slot_per_epoch as u64),
+            ("reward_ratio", self.reward_ratio),
+            ("max_block_size", self.max_block_size as u64),
+        ] {
+            if value == 0 {
+                return Err(ChainSpecError::ZeroValue(name));
+            }
+        }
+        for (name, value) in [
+            ("teleport_log4_tree_size", self.teleport_log4_tree_size),
+            ("mpn.log4_tree_size", self.mpn.log4_tree_size),
+            ("mpn.log4_token_tree_size", self.mpn.log4_token_tree_size),
+            (
+                "mpn.log4_deposit_batch_size",
+                self.mpn.log4_deposit_batch_size,
+            ),
+            (
+                "mpn.log4_withdraw_batch_size",
+                self.mpn.log4_withdraw_batch_size,
+            ),
+            (
+                "mpn.log4_update_batch_size",
+                self.mpn.log4_update_batch_size,
+            ),
+        ] {
+            if value > MAX_LOG4_TREE_SIZE {
+                return Err(ChainSpecError::TreeSizeTooLarge(name));
+            }
+        }
+        if self.check_validator && self.validators.is_empty() {
+            return Err(ChainSpecError::NoValidators);
+        }
+        if let Some(v) = self.validators.iter().find(|v| v.stake == Amount(0)) {
+            return Err(ChainSpecError::ZeroStake(v.address.to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn blockchain_config(&self) -> Result<BlockchainConfig, ChainSpecError> {
+        self.validate()?;
+        let mpn_allocations = self
+            .mpn
+            .allocations
+            .iter()
+            .map(|a| (a.address.clone(), a.amount))
+            .collect::<Vec<_>>();
+        let (deposit_vk, withdraw_vk, update_vk) = self.mpn.verifier_keys()?;
+        let mpn_tx_delta = get_mpn_contract(
+            self.mpn.log4_tree_size,
+            self.mpn.log4_token_tree_size,
+            self.mpn.log4_deposit_batch_size,
+            self.mpn.log4_withdraw_batch_size,
+            &deposit_vk,
+            &withdraw_vk,
+            &update_vk,
+            &mpn_allocations,
+        );
+        let mpn_contract_id = ContractId::new(&mpn_tx_delta.tx);
+
+        let ziesha_token_creation_tx = get_ziesha_token_creation_tx();
+        let ziesha_token_id = ContractId::new(&ziesha_token_creation_tx);
+
+        let mut blk = Block {
+            header: Header {
+                parent_hash: Default::default(),
+                number: 0,
+                block_root: Default::default(),
+                state_root: Default::default(),
+                proof_of_stake: ProofOfStake {
+                    timestamp: self.chain_start_timestamp,
+                    validator: Default::default(),
+                    proof: None,
+                },
+            },
+            body: vec![ziesha_token_creation_tx, mpn_tx_delta.tx],
+        };
+
+        for validator in self.validators.iter() {
+            blk.body.push(Transaction {
+                memo: "Very first staker created!".into(),
+                src: Some(validator.address.clone()),
+                data: TransactionData::UpdateStaker {
+                    vrf_pub_key: validator.vrf_pub_key.clone(),
+                    commission: validator.commission,
+                },
+                nonce: 0,
+                fee: Money::ziesha(0),
+                sig: Signature::Unsigned,
+            });
+            blk.body.push(Transaction {
+                memo: "Very first delegation!".into(),
+                src: None,
+                data: TransactionData::Delegate {
+                    to: validator.address.clone(),
+                    amount: validator.stake,
+                },
+                nonce: 0,
+                fee: Money::ziesha(0),
+                sig: Signature::Unsigned,
+            });
+        }
+
+        for alloc in self.allocations.iter() {
+            blk.body.push(Transaction {
+                memo: "".into(),
+                src: None,
+                data: TransactionData::RegularSend {
+                    entries: vec![RegularSendEntry {
+                        dst: alloc.address.clone(),
+                        amount: Money {
+                            token_id: ContractId::Ziesha,
+                            amount: alloc.amount,
+                        },
+                    }],
+                },
+                nonce: 0,
+                fee: Money::ziesha(0),
+                sig: Signature::Unsigned,
+            });
+        }
+
+        let mut checkpoints = std::collections::HashMap::new();
+        for (height, hash) in self.checkpoints.iter() {
+            checkpoints.insert(
+                *height,
+                hex::decode(hash)
+                    .ok()
+                    .and_then(|h| h.try_into().ok())
+                    .ok_or(ChainSpecError::InvalidCheckpoint(*height))?,
+            );
+        }
+
+        Ok(BlockchainConfig {
+            limited_miners: None,
+            mpn_config: MpnConfig {
+                mpn_contract_id,
+                log4_tree_size: self.mpn.log4_tree_size,
+                log4_token_tree_size: self.mpn.log4_token_tree_size,
+                log4_deposit_batch_size: self.mpn.log4_deposit_batch_size,
+                log4_withdraw_batch_size: self.mpn.log4_withdraw_batch_size,
+                log4_update_batch_size: self.mpn.log4_update_batch_size,
+                mpn_num_update_batches: self.mpn.num_update_batches,
+                mpn_num_deposit_batches: self.mpn.num_deposit_batches,
+                mpn_num_withdraw_batches: self.mpn.num_withdraw_batches,
+                deposit_vk,
+                withdraw_vk,
+                update_vk,
+            },
+
+            ziesha_token_id,
+            genesis: blk,
+            reward_ratio: self.reward_ratio,
+            max_block_size: self.max_block_size,
+
+            testnet_height_limit: self.testnet_height_limit,
+            max_memo_length: self.max_memo_length,
+            slot_duration: self.slot_duration,
+            slot_per_epoch: self.slot_per_epoch,
+            chain_start_timestamp: self.chain_start_timestamp,
+            check_validator: self.check_validator,
+            max_validator_commission: self.max_validator_commission,
+
+            teleport_log4_tree_size: self.teleport_log4_tree_size,
+            teleport_contract_id: self.teleport_contract_id,
+            dummy_proofs: self.mpn.dummy_proofs,
+            checkpoints,
+            max_reorg_depth: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_spec_json_roundtrip() {
+        let mut spec = crate::config::blockchain::get_chain_spec();
+        spec.allocations.truncate(10);
+        spec.mpn.allocations.truncate(10);
+        spec.checkpoints.insert(12, hex::encode([1u8; 32]));
+        let json = serde_json::to_string(&spec).unwrap();
+        let parsed: ChainSpec = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, spec);
+        assert_eq!(
+            parsed.blockchain_config().unwrap().genesis,
+            spec.blockchain_config().unwrap().genesis
+        );
+
+        spec.checkpoints.insert(13, "abcd".into());
+        assert!(matches!(
+            spec.blockchain_config(),
+            Err(ChainSpecError::InvalidCheckpoint(13))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_chain_spec() {
+        let spec = crate::config::blockchain::get_chain_spec();
+        assert!(spec.validate().is_ok());
+
+        let mut invalid = spec.clone();
+        invalid.network = "".into();
+        assert!(matches!(
+            invalid.blockchain_config(),
+            Err(ChainSpecError::EmptyNetwork)
+        ));
+
+        let mut invalid = spec.clone();
+        invalid.slot_per_epoch = 0;
+        assert!(matches!(
+            invalid.validate(),
+            Err(ChainSpecError::ZeroValue("slot_per_epoch"))
+        ));
+
+        let mut invalid = spec.clone();
+        invalid.mpn.log4_tree_size = 32;
+        assert!(matches!(
+            invalid.validate(),
+            Err(ChainSpecError::TreeSizeTooLarge("mpn.log4_tree_size"))
+        ));
+
+        let mut invalid = spec.clone();
+        invalid.check_validator = true;
+        invalid.validators.clear();
+        assert!(matches!(
+            invalid.validate(),
+            Err(ChainSpecError::NoValidators)
+        ));
+
+        let mut invalid = spec;
+        invalid.validators[0].stake = Amount(0);
+        assert!(matches!(
+            invalid.validate(),
+            Err(ChainSpecError::ZeroStake(_))
+        ));
+    }
+
+    #[test]
+    fn test_devnet_chain_spec() {
+        let validators = (0..3)
+            .map(|i| crate::wallet::TxBuilder::new(&Vec::from(format!("VALIDATOR{}", i))))
+            .collect::<Vec<_>>();
+        let users = (0..4)
+            .map(|i| crate::wallet::TxBuilder::new(&Vec::from(format!("USER{}", i))))
+            .collect::<Vec<_>>();
+        let spec = crate::config::blockchain::get_devnet_chain_spec(&validators, &users);
+        let conf = spec.blockchain_config().unwrap();
+        // Token and MPN creation, a staker and a delegation per validator,
+        // and a payment per user
+        assert_eq!(conf.genesis.body.len(), 2 + 3 * 2 + 4);
+        assert_eq!(conf.testnet_height_limit, None);
+    }
+
+    #[test]
+    fn test_dummy_proofs_chain_spec() {
+        let mut spec = crate::config::blockchain::get_chain_spec();
+        spec.mpn.allocations.clear();
+        let real = spec.blockchain_config().unwrap();
+        spec.mpn.dummy_proofs = true;
+        let dummy = spec.blockchain_config().unwrap();
+        assert_eq!(dummy.mpn_config.update_vk, zk::ZkVerifierKey::Dummy);
+        // The MPN contract is a different contract
+        assert_ne!(
+            dummy.mpn_config.mpn_contract_id,
+            real.mpn_config.mpn_contract_id
+        );
+    }
+
+    #[test]
+    fn test_small_mpn_with_dummy_proofs() {
+        let validator = crate::wallet::TxBuilder::new(&Vec::from("VALIDATOR"));
+        let user = crate::wallet::TxBuilder::new(&Vec::from("USER"));
+        let conf = crate::config::blockchain::get_dev_blockchain_config(
+            &validator, &user, true, None, true,
+        )
+        .unwrap();
+        assert_eq!(
+            conf.mpn_config.log4_tree_size,
+            crate::config::blockchain::SMALL_MPN_LOG4_TREE_SIZE
+        );
+        assert_eq!(conf.mpn_config.update_vk, zk::ZkVerifierKey::Dummy);
+    }
+}
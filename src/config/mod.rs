@@ -1,9 +1,13 @@
 pub mod blockchain;
+pub mod chain_spec;
 mod initials;
 
 #[cfg(feature = "node")]
 pub mod node;
 
+/// Name of the built-in network
+pub const NETWORK: &str = "deruny-5";
+
 pub const CODE: &str = "ZSH";
 pub const SYMBOL: &str = "ℤ";
 pub const UNIT_ZEROS: u8 = 9;
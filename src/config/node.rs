@@ -1,3 +1,4 @@
+use crate::core::Amount;
 use crate::node::{HeartbeatIntervals, NodeOptions};
 use std::time::Duration;
 
@@ -12,6 +13,7 @@ pub fn get_node_options() -> NodeOptions {
             sync_clock: Duration::from_secs(10),
             sync_blocks: Duration::from_secs(10),
             sync_mempool: Duration::from_secs(30),
+            sync_deltas: Duration::from_secs(10),
             generate_block: Duration::from_secs(3),
         },
         num_peers: 8,
@@ -25,6 +27,16 @@ pub fn get_node_options() -> NodeOptions {
         mempool_max_fetch: 1000,
         max_block_time_difference: 120,
         automatic_block_generation: true,
+        max_reorg_depth: 1000,
+        max_orphan_blocks: 64,
+        max_orphan_blocks_per_peer: 8,
+        max_orphan_block_age: 600,
+        mempool_min_balance_per_tx: Amount(1_000_000_000),
+        mempool_max_txs_per_sender: 1000,
+        mpn_work_deadline: 60,
+        mpn_work_redundancy: 1,
+        mpn_works_per_worker: 2,
+        mpn_rewards: Default::default(),
     }
 }
 
@@ -39,6 +51,7 @@ pub fn get_simulator_options() -> NodeOptions {
             sync_clock: Duration::from_millis(300),
             sync_blocks: Duration::from_millis(300),
             sync_mempool: Duration::from_millis(300),
+            sync_deltas: Duration::from_millis(300),
             generate_block: Duration::from_millis(300),
         },
         num_peers: 8,
@@ -52,5 +65,15 @@ pub fn get_simulator_options() -> NodeOptions {
         mempool_max_fetch: 1000,
         max_block_time_difference: 120,
         automatic_block_generation: false,
+        max_reorg_depth: 1000,
+        max_orphan_blocks: 64,
+        max_orphan_blocks_per_peer: 8,
+        max_orphan_block_age: 600,
+        mempool_min_balance_per_tx: Amount(1_000_000_000),
+        mempool_max_txs_per_sender: 1000,
+        mpn_work_deadline: 10,
+        mpn_work_redundancy: 1,
+        mpn_works_per_worker: 2,
+        mpn_rewards: Default::default(),
     }
 }
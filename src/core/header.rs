@@ -33,6 +33,8 @@ pub struct Header<H: Hash, S: SignatureScheme, V: VerifiableRandomFunction> {
     pub number: u64,
     /// the merkle root of current block
     pub block_root: H::Output,
+    /// the root of the state tree after applying current block
+    pub state_root: H::Output,
     /// aux data for Proof-of-Stake consensus
     pub proof_of_stake: ProofOfStake<S, V>,
 }
@@ -380,6 +380,14 @@ impl<H: Hash, S: SignatureScheme, V: VerifiableRandomFunction> Transaction<H, S,
         clean.sig = Signature::Unsigned;
         clean
     }
+    /// Whether the contract state/delta, which is not signed, is given
+    pub fn has_state(&self) -> bool {
+        match &self.data {
+            TransactionData::UpdateContract { delta, .. } => delta.is_some(),
+            TransactionData::CreateContract { state, .. } => state.is_some(),
+            _ => true,
+        }
+    }
     pub fn hash(&self) -> H::Output {
         H::hash(&bincode::serialize(&self.sig_state_excluded()).unwrap())
     }
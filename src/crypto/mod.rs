@@ -7,6 +7,7 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 pub mod merkle;
+pub mod sparse_merkle;
 
 pub mod ed25519;
 pub mod jubjub;
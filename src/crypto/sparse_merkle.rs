@@ -0,0 +1,163 @@
+use crate::core::hash::Hash;
+use serde::{Deserialize, Serialize};
+
+/// A leaf is placed in the tree by the bits of the hash of its key
+pub const SPARSE_MERKLE_DEPTH: usize = 256;
+
+/// Bit of the path which decides the side of the node at `depth + 1`
+pub fn path_bit(path: &[u8], depth: usize) -> bool {
+    (path[depth / 8] >> (7 - depth % 8)) & 1 == 1
+}
+
+/// Path of the sibling of the node at `depth` on the given path
+pub fn sibling_path<H: Hash>(path: &H::Output, depth: usize) -> H::Output {
+    let mut sibling = *path;
+    sibling.as_mut()[(depth - 1) / 8] ^= 1 << (7 - (depth - 1) % 8);
+    sibling
+}
+
+pub fn leaf_hash<H: Hash>(path: &H::Output, value: &[u8]) -> H::Output {
+    let mut inp = vec![0u8];
+    inp.extend(path.as_ref());
+    inp.extend(value);
+    H::hash(&inp)
+}
+
+/// Empty subtrees hash to zero, so that they never have to be stored
+pub fn merge_hash<H: Hash>(left: &H::Output, right: &H::Output) -> H::Output {
+    let zero = H::Output::default();
+    if *left == zero && *right == zero {
+        return zero;
+    }
+    let mut inp = vec![1u8];
+    inp.extend(left.as_ref());
+    inp.extend(right.as_ref());
+    H::hash(&inp)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SparseMerkleProof<H: Hash> {
+    /// Non-empty siblings of the path as (depth, hash), from the leaf to the root
+    pub siblings: Vec<(u16, H::Output)>,
+}
+
+impl<H: Hash> SparseMerkleProof<H> {
+    /// Root of the tree in which `key` has `value`, or `None` if the key is absent
+    pub fn root(&self, key: &[u8], value: Option<&[u8]>) -> Option<H::Output> {
+        let path = H::hash(key);
+        let mut curr = value.map(|v| leaf_hash::<H>(&path, v)).unwrap_or_default();
+        let mut siblings = self.siblings.iter().peekable();
+        for depth in (1..=SPARSE_MERKLE_DEPTH).rev() {
+            let sibling = match siblings.peek() {
+                Some((d, h)) if *d as usize == depth => {
+                    siblings.next();
+                    *h
+                }
+                _ => Default::default(),
+            };
+            curr = if path_bit(path.as_ref(), depth - 1) {
+                merge_hash::<H>(&sibling, &curr)
+            } else {
+                merge_hash::<H>(&curr, &sibling)
+            };
+        }
+        if siblings.next().is_some() {
+            return None;
+        }
+        Some(curr)
+    }
+
+    pub fn verify(&self, root: &H::Output, key: &[u8], value: Option<&[u8]>) -> bool {
+        self.root(key, value).as_ref() == Some(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hash::Sha3Hasher;
+    use std::collections::HashMap;
+
+    // Keeps every node in memory, the same way the blockchain keeps them in
+    // its database
+    #[derive(Default)]
+    struct MemoryTree(HashMap<(usize, Vec<u8>), [u8; 32]>);
+
+    impl MemoryTree {
+        fn node(&self, depth: usize, path: &[u8; 32]) -> [u8; 32] {
+            let mut prefix = *path;
+            for i in depth..SPARSE_MERKLE_DEPTH {
+                prefix[i / 8] &= !(1 << (7 - i % 8));
+            }
+            self.0
+                .get(&(depth, prefix.to_vec()))
+                .cloned()
+                .unwrap_or_default()
+        }
+        fn set(&mut self, depth: usize, path: &[u8; 32], value: [u8; 32]) {
+            let mut prefix = *path;
+            for i in depth..SPARSE_MERKLE_DEPTH {
+                prefix[i / 8] &= !(1 << (7 - i % 8));
+            }
+            self.0.insert((depth, prefix.to_vec()), value);
+        }
+        fn update(&mut self, key: &[u8], value: Option<&[u8]>) {
+            let path = Sha3Hasher::hash(key);
+            let mut curr = value
+                .map(|v| leaf_hash::<Sha3Hasher>(&path, v))
+                .unwrap_or_default();
+            for depth in (1..=SPARSE_MERKLE_DEPTH).rev() {
+                self.set(depth, &path, curr);
+                let sibling = self.node(depth, &sibling_path::<Sha3Hasher>(&path, depth));
+                curr = if path_bit(&path, depth - 1) {
+                    merge_hash::<Sha3Hasher>(&sibling, &curr)
+                } else {
+                    merge_hash::<Sha3Hasher>(&curr, &sibling)
+                };
+            }
+            self.set(0, &path, curr);
+        }
+        fn prove(&self, key: &[u8]) -> SparseMerkleProof<Sha3Hasher> {
+            let path = Sha3Hasher::hash(key);
+            SparseMerkleProof {
+                siblings: (1..=SPARSE_MERKLE_DEPTH)
+                    .rev()
+                    .map(|d| {
+                        (
+                            d as u16,
+                            self.node(d, &sibling_path::<Sha3Hasher>(&path, d)),
+                        )
+                    })
+                    .filter(|(_, h)| *h != [0u8; 32])
+                    .collect(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_sparse_merkle_proof() {
+        let mut tree = MemoryTree::default();
+        assert_eq!(tree.node(0, &[0u8; 32]), [0u8; 32]);
+        for i in 0..20u8 {
+            tree.update(&[i], Some(&[i * 2]));
+        }
+        let root = tree.node(0, &[0u8; 32]);
+        for i in 0..20u8 {
+            let proof = tree.prove(&[i]);
+            assert!(proof.verify(&root, &[i], Some(&[i * 2])));
+            assert!(!proof.verify(&root, &[i], Some(&[i * 2 + 1])));
+            assert!(!proof.verify(&root, &[i], None));
+        }
+
+        // Absence proofs
+        let proof = tree.prove(&[100]);
+        assert!(proof.verify(&root, &[100], None));
+        assert!(!proof.verify(&root, &[100], Some(&[0])));
+
+        // Removing all the leaves brings back the empty tree
+        for i in 0..20u8 {
+            tree.update(&[i], None);
+        }
+        assert_eq!(tree.node(0, &[0u8; 32]), [0u8; 32]);
+    }
+}
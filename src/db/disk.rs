@@ -83,9 +83,9 @@ impl KvStore for LevelDbKvStore {
             Err(_) => Err(KvStoreError::Failure),
         }
     }
-    fn pairs(&self, prefix: StringKey) -> Result<QueryResult, KvStoreError> {
+    fn pairs_from(&self, prefix: StringKey, from: StringKey) -> Result<QueryResult, KvStoreError> {
         let it = self.0.iter(ReadOptions::new());
-        it.seek(&prefix);
+        it.seek(&from);
         Ok(QueryResult::LevelDb { db: it, prefix })
     }
 }
@@ -101,9 +101,9 @@ impl<'a> KvStore for LevelDbSnapshot<'a> {
     fn update(&mut self, _: &[WriteOp]) -> Result<(), KvStoreError> {
         panic!("Cannot update!");
     }
-    fn pairs(&self, prefix: StringKey) -> Result<QueryResult, KvStoreError> {
+    fn pairs_from(&self, prefix: StringKey, from: StringKey) -> Result<QueryResult, KvStoreError> {
         let it = self.0.iter(ReadOptions::new());
-        it.seek(&prefix);
+        it.seek(&from);
         Ok(QueryResult::LevelDb { db: it, prefix })
     }
 }
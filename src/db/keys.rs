@@ -311,6 +311,42 @@ pub fn mpn_account_count() -> StringKey {
     "MPN-CNT".into()
 }
 
+/// Update of a contract at `height` whose delta is missing locally. A
+/// contract is outdated as long as it has any of these.
+pub struct MissingDeltaDbKey {
+    pub contract_id: ContractId,
+    pub height: u64,
+}
+impl Into<StringKey> for MissingDeltaDbKey {
+    fn into(self) -> StringKey {
+        format!("{}{:016x}", Self::prefix(&self.contract_id), self.height).into()
+    }
+}
+impl TryFrom<StringKey> for MissingDeltaDbKey {
+    type Error = ParseDbKeyError;
+    fn try_from(key: StringKey) -> Result<Self, ParseDbKeyError> {
+        let splitted = key.0.split("-").collect::<Vec<_>>();
+        if splitted.len() != 3 {
+            return Err(ParseDbKeyError::Invalid);
+        }
+
+        let contract_id: ContractId = splitted[1].parse().map_err(|_| ParseDbKeyError::Invalid)?;
+        let height = u64::from_str_radix(&splitted[2], 16).map_err(|_| ParseDbKeyError::Invalid)?;
+        Ok(Self {
+            contract_id,
+            height,
+        })
+    }
+}
+impl MissingDeltaDbKey {
+    pub fn prefix(contract_id: &ContractId) -> String {
+        format!("{}{}-", Self::any_prefix(), contract_id)
+    }
+    pub fn any_prefix() -> String {
+        "MDL-".into()
+    }
+}
+
 pub fn local_prefix(contract_id: &ContractId) -> String {
     format!("S-{}", contract_id)
 }
@@ -323,6 +359,16 @@ pub fn local_root(contract_id: &ContractId) -> StringKey {
     format!("{}-RT", local_prefix(contract_id)).into()
 }
 
+/// Delta which brought the local state of a contract to `height`
+pub fn local_delta(contract_id: &ContractId, height: u64) -> StringKey {
+    format!("{}-DLT-{:016x}", local_prefix(contract_id), height).into()
+}
+
+/// Compressed local state of a contract right after its delta at `height`
+pub fn local_delta_root(contract_id: &ContractId, height: u64) -> StringKey {
+    format!("{}-DRT-{:016x}", local_prefix(contract_id), height).into()
+}
+
 pub fn local_tree_aux(
     contract_id: &ContractId,
     tree_loc: &ZkDataLocator,
@@ -355,3 +401,23 @@ pub fn local_value(
     )
     .into()
 }
+
+/// Keys committed in the state root of the headers: account balances,
+/// nonces, stakes and contract accounts
+pub fn is_state_key(key: &StringKey) -> bool {
+    ["ACB-", "NNC-", "DNC-", "STK-", "CAC-"]
+        .iter()
+        .any(|p| key.0.starts_with(p))
+}
+
+/// Node of the state tree at `depth`, on the way to the leaf at `path`
+pub fn state_node(depth: usize, path: &<Hasher as Hash>::Output) -> StringKey {
+    let mut prefix = path[..depth.div_ceil(8)].to_vec();
+    let rem = depth % 8;
+    if rem > 0 {
+        if let Some(last) = prefix.last_mut() {
+            *last &= 0xffu8 << (8 - rem);
+        }
+    }
+    format!("SMT-{:03}-{}", depth, hex::encode(prefix)).into()
+}
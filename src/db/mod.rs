@@ -1,5 +1,6 @@
 pub mod keys;
 
+use crate::blockchain::MissingDelta;
 use crate::core::{
     hash::Hash, Amount, Block, ContractAccount, ContractId, Delegate, Hasher, Header, Ratio,
     Staker, Token, Undelegation,
@@ -114,6 +115,7 @@ gen_try_into!(
     ZkDeltaPairs,
     Token,
     Amount,
+    MissingDelta,
     ()
 );
 gen_from!(
@@ -142,6 +144,7 @@ gen_from!(
     &ZkDeltaPairs,
     &Token,
     Amount,
+    MissingDelta,
     ()
 );
 
@@ -314,7 +317,12 @@ impl<'a> QueryResult<'a> {
 pub trait KvStore {
     fn get(&self, k: StringKey) -> Result<Option<Blob>, KvStoreError>;
     fn update(&mut self, ops: &[WriteOp]) -> Result<(), KvStoreError>;
-    fn pairs(&self, prefix: StringKey) -> Result<QueryResult, KvStoreError>;
+    fn pairs(&self, prefix: StringKey) -> Result<QueryResult, KvStoreError> {
+        self.pairs_from(prefix.clone(), prefix)
+    }
+    /// Pairs of the keys starting with `prefix`, in the order of their keys,
+    /// skipping the keys before `from`
+    fn pairs_from(&self, prefix: StringKey, from: StringKey) -> Result<QueryResult, KvStoreError>;
     fn mirror(&self) -> RamMirrorKvStore<'_, Self>
     where
         Self: Sized,
@@ -373,12 +381,12 @@ impl<'a, K: KvStore> KvStore for RamMirrorKvStore<'a, K> {
         }
         Ok(())
     }
-    fn pairs(&self, prefix: StringKey) -> Result<QueryResult, KvStoreError> {
+    fn pairs_from(&self, prefix: StringKey, from: StringKey) -> Result<QueryResult, KvStoreError> {
         Ok(QueryResult::Mirror {
-            actual: Box::new(self.store.pairs(prefix.clone())?),
+            actual: Box::new(self.store.pairs_from(prefix.clone(), from.clone())?),
             overwrite: self
                 .overwrite
-                .range((Bound::Included(prefix.clone()), Bound::Unbounded)),
+                .range((Bound::Included(from), Bound::Unbounded)),
             prefix,
         })
     }
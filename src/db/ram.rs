@@ -27,11 +27,9 @@ impl KvStore for RamKvStore {
         }
         Ok(())
     }
-    fn pairs(&self, prefix: StringKey) -> Result<QueryResult, KvStoreError> {
+    fn pairs_from(&self, prefix: StringKey, from: StringKey) -> Result<QueryResult, KvStoreError> {
         Ok(QueryResult::Ram {
-            range: self
-                .0
-                .range((Bound::Included(prefix.clone()), Bound::Unbounded)),
+            range: self.0.range((Bound::Included(from), Bound::Unbounded)),
             prefix,
         })
     }
@@ -228,3 +228,32 @@ fn test_mirror_rollback() -> Result<(), KvStoreError> {
 
     Ok(())
 }
+
+#[test]
+fn test_pairs_from() -> Result<(), KvStoreError> {
+    let mut ram = RamKvStore::default();
+    ram.update(&[
+        WriteOp::Put("a-1".into(), Blob(vec![1])),
+        WriteOp::Put("a-3".into(), Blob(vec![3])),
+        WriteOp::Put("b-1".into(), Blob(vec![4])),
+    ])?;
+    let mut mirror = RamMirrorKvStore::new(&ram);
+    mirror.update(&[
+        WriteOp::Put("a-2".into(), Blob(vec![2])),
+        WriteOp::Remove("a-3".into()),
+        WriteOp::Put("a-4".into(), Blob(vec![5])),
+    ])?;
+
+    let keys = |res: QueryResult| res.into_iter().map(|(k, _)| k.0).collect::<Vec<_>>();
+    assert_eq!(
+        keys(ram.pairs_from("a-".into(), "a-2".into())?),
+        vec!["a-3"]
+    );
+    assert_eq!(
+        keys(mirror.pairs_from("a-".into(), "a-2".into())?),
+        vec!["a-2", "a-4"]
+    );
+    assert_eq!(keys(mirror.pairs("a-".into())?), vec!["a-1", "a-2", "a-4"]);
+
+    Ok(())
+}
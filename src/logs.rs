@@ -0,0 +1,343 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+const MAX_LOG_CAP: usize = 1000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    pub id: u64,
+    pub timestamp: u32,
+    pub level: String,
+    pub module: String,
+    pub message: String,
+    pub fields: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    /// Minimum level of the records (E.g. `Warn` returns warnings and errors)
+    pub level: Option<Level>,
+    /// Prefix of the module path
+    pub module: Option<String>,
+    /// Only return records with an id greater than this
+    pub since: Option<u64>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        if let Some(since) = self.since {
+            if record.id <= since {
+                return false;
+            }
+        }
+        if let Some(level) = self.level {
+            match record.level.parse::<Level>() {
+                Ok(record_level) if record_level <= level => {}
+                _ => return false,
+            }
+        }
+        if let Some(module) = &self.module {
+            if !record.module.starts_with(module) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Ring buffer of the latest log records
+pub struct LogBuffer {
+    capacity: usize,
+    next_id: u64,
+    records: VecDeque<LogRecord>,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            next_id: 1,
+            records: VecDeque::new(),
+        }
+    }
+
+    pub fn push(
+        &mut self,
+        timestamp: u32,
+        level: Level,
+        module: &str,
+        message: String,
+        fields: HashMap<String, String>,
+    ) -> LogRecord {
+        let record = LogRecord {
+            id: self.next_id,
+            timestamp,
+            level: level.to_string(),
+            module: module.into(),
+            message,
+            fields,
+        };
+        self.next_id += 1;
+        self.records.push_back(record.clone());
+        while self.records.len() > self.capacity {
+            self.records.pop_front();
+        }
+        record
+    }
+
+    pub fn query(&self, filter: &LogFilter) -> Vec<LogRecord> {
+        self.records
+            .iter()
+            .filter(|r| filter.matches(r))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Appends JSON lines to a file, keeping at most `max_files` rotated files
+/// (`<path>.1` being the newest) of size `max_size`.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn open(path: PathBuf, max_size: u64, max_files: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_size,
+            max_files,
+            file,
+            size,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_files > 0 {
+            for i in (1..self.max_files).rev() {
+                let from = self.rotated_path(i);
+                if from.exists() {
+                    std::fs::rename(from, self.rotated_path(i + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    pub fn write_record(&mut self, record: &LogRecord) -> io::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        self.file.write_all(&line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// Verbosity of bazuka's own modules, which can be changed at runtime
+static OWN_LEVEL: AtomicUsize = AtomicUsize::new(LevelFilter::Info as usize);
+/// Highest verbosity `RUST_LOG` enables for the dependencies
+static DEPS_LEVEL: AtomicUsize = AtomicUsize::new(LevelFilter::Off as usize);
+
+fn to_level_filter(level: usize) -> LevelFilter {
+    LevelFilter::iter()
+        .find(|l| *l as usize == level)
+        .unwrap_or(LevelFilter::Off)
+}
+
+fn is_own_target(target: &str) -> bool {
+    target == "bazuka" || target.starts_with("bazuka::")
+}
+
+/// Records of bazuka's own modules are filtered by the runtime level and the
+/// rest by `RUST_LOG`, so that raising the level does not flood the output
+/// with the logs of every dependency
+struct Logger {
+    deps: env_logger::Logger,
+    own: env_logger::Logger,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        if is_own_target(metadata.target()) {
+            metadata.level() <= level()
+        } else {
+            self.deps.enabled(metadata)
+        }
+    }
+
+    fn log(&self, record: &Record) {
+        if is_own_target(record.target()) {
+            if record.level() <= level() {
+                self.own.log(record);
+            }
+        } else {
+            self.deps.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.deps.flush();
+        self.own.flush();
+    }
+}
+
+/// Installs the global logger. `deps` filters the records of the dependencies
+/// while `own` should accept everything, the initial level of bazuka's own
+/// modules is the one `deps` gives to the `bazuka` target.
+pub fn init(deps: env_logger::Logger, own: env_logger::Logger) -> Result<(), log::SetLoggerError> {
+    let own_level = LevelFilter::iter()
+        .filter(|l| {
+            l.to_level()
+                .map(|l| deps.enabled(&Metadata::builder().target("bazuka").level(l).build()))
+                .unwrap_or(true)
+        })
+        .last()
+        .unwrap_or(LevelFilter::Off);
+    DEPS_LEVEL.store(deps.filter() as usize, Ordering::Relaxed);
+    log::set_boxed_logger(Box::new(Logger { deps, own }))?;
+    set_level(own_level);
+    Ok(())
+}
+
+lazy_static! {
+    static ref GLOBAL_LOGS: Mutex<LogBuffer> = Mutex::new(LogBuffer::new(MAX_LOG_CAP));
+    static ref GLOBAL_LOG_FILE: Mutex<Option<RotatingFile>> = Mutex::new(None);
+}
+
+/// Keeps a log record in memory, and writes it to the log file if enabled
+pub fn report(record: &log::Record) {
+    let mut fields = HashMap::new();
+    if let Some(file) = record.file() {
+        fields.insert("file".into(), file.into());
+    }
+    if let Some(line) = record.line() {
+        fields.insert("line".into(), line.to_string());
+    }
+    let record = GLOBAL_LOGS.lock().unwrap().push(
+        crate::utils::local_timestamp(),
+        record.level(),
+        record.module_path().unwrap_or(record.target()),
+        record.args().to_string(),
+        fields,
+    );
+    if let Some(file) = GLOBAL_LOG_FILE.lock().unwrap().as_mut() {
+        if let Err(e) = file.write_record(&record) {
+            eprintln!("Cannot write to the log file: {}", e);
+        }
+    }
+}
+
+pub fn query(filter: &LogFilter) -> Vec<LogRecord> {
+    GLOBAL_LOGS.lock().unwrap().query(filter)
+}
+
+/// Changes the verbosity of bazuka's own logs at runtime
+pub fn set_level(level: LevelFilter) {
+    OWN_LEVEL.store(level as usize, Ordering::Relaxed);
+    log::set_max_level(level.max(to_level_filter(DEPS_LEVEL.load(Ordering::Relaxed))));
+}
+
+pub fn level() -> LevelFilter {
+    to_level_filter(OWN_LEVEL.load(Ordering::Relaxed))
+}
+
+/// Starts writing the log records as JSON lines into the given file
+pub fn enable_file_output(path: PathBuf, max_size: u64, max_files: usize) -> io::Result<()> {
+    *GLOBAL_LOG_FILE.lock().unwrap() = Some(RotatingFile::open(path, max_size, max_files)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_own_level() {
+        assert!(is_own_target("bazuka"));
+        assert!(is_own_target("bazuka::node::api"));
+        assert!(!is_own_target("bazukas"));
+        assert!(!is_own_target("hyper::proto"));
+
+        DEPS_LEVEL.store(LevelFilter::Warn as usize, Ordering::Relaxed);
+        set_level(LevelFilter::Debug);
+        assert_eq!(level(), LevelFilter::Debug);
+        assert_eq!(log::max_level(), LevelFilter::Debug);
+        set_level(LevelFilter::Error);
+        assert_eq!(level(), LevelFilter::Error);
+        // Dependencies keep the level given by `RUST_LOG`
+        assert_eq!(log::max_level(), LevelFilter::Warn);
+    }
+
+    #[test]
+    fn test_log_filters() {
+        let mut buff = LogBuffer::new(3);
+        buff.push(10, Level::Info, "bazuka::node", "a".into(), HashMap::new());
+        buff.push(
+            11,
+            Level::Warn,
+            "bazuka::node::api",
+            "b".into(),
+            HashMap::new(),
+        );
+        buff.push(12, Level::Debug, "hyper::proto", "c".into(), HashMap::new());
+        buff.push(13, Level::Error, "bazuka::cli", "d".into(), HashMap::new());
+
+        let msgs = |filter: LogFilter| {
+            buff.query(&filter)
+                .into_iter()
+                .map(|r| r.message)
+                .collect::<Vec<_>>()
+        };
+
+        // Oldest record is dropped
+        assert_eq!(msgs(LogFilter::default()), vec!["b", "c", "d"]);
+        assert_eq!(
+            msgs(LogFilter {
+                level: Some(Level::Warn),
+                ..Default::default()
+            }),
+            vec!["b", "d"]
+        );
+        assert_eq!(
+            msgs(LogFilter {
+                module: Some("bazuka::node".into()),
+                ..Default::default()
+            }),
+            vec!["b"]
+        );
+        assert_eq!(
+            msgs(LogFilter {
+                since: Some(3),
+                ..Default::default()
+            }),
+            vec!["d"]
+        );
+    }
+}
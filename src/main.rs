@@ -2,7 +2,8 @@
 pub mod cli;
 
 use crate::cli::initialize_cli;
-use log::LevelFilter;
+use env_logger::{fmt::Formatter, Builder, Env};
+use log::{LevelFilter, Record};
 use std::io::Write;
 
 #[cfg(feature = "client")]
@@ -12,20 +13,27 @@ use bazuka::client::NodeError;
 #[cfg(feature = "client")]
 #[tokio::main]
 async fn main() -> Result<(), NodeError> {
-    env_logger::builder()
-        .filter(None, LevelFilter::Info)
-        .format(|buf, record| {
-            let ts = buf.timestamp();
-            bazuka::report_log(&format!("{}: {}: {}", ts, record.level(), record.args()));
-            writeln!(
-                buf,
-                "{}: {}: {}",
-                ts,
-                buf.default_styled_level(record.level()),
-                record.args()
-            )
-        })
-        .init();
+    let format = |buf: &mut Formatter, record: &Record| {
+        let ts = buf.timestamp();
+        bazuka::logs::report(record);
+        writeln!(
+            buf,
+            "{}: {}: {}",
+            ts,
+            buf.default_styled_level(record.level()),
+            record.args()
+        )
+    };
+    // Dependencies are filtered by `RUST_LOG`, bazuka's own verbosity starts
+    // from it and can be changed at runtime.
+    let deps = Builder::from_env(Env::default().default_filter_or("info"))
+        .format(format)
+        .build();
+    let own = Builder::new()
+        .filter(None, LevelFilter::Trace)
+        .format(format)
+        .build();
+    bazuka::logs::init(deps, own).expect("Cannot initialize the logger!");
     initialize_cli().await;
     Ok(())
 }
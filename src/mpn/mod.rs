@@ -1,5 +1,7 @@
 pub mod circuits;
 pub mod deposit;
+pub mod prover;
+pub mod setup;
 pub mod update;
 pub mod withdraw;
 
@@ -17,7 +19,7 @@ use crate::zk::{
 };
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -28,6 +30,8 @@ pub enum MpnError {
     KvStoreError(#[from] KvStoreError),
     #[error("insufficient workers in the pool")]
     InsufficientWorkers,
+    #[error("prover rewards are more than the block reward")]
+    InsufficientBlockReward,
 }
 
 fn extract_delta(ops: &[WriteOp]) -> ZkDeltaPairs {
@@ -60,12 +64,142 @@ pub struct MpnSolution {
     proof: ZkProof,
 }
 
+/// How the works of a pool are handed out to the workers
+#[derive(Debug, Clone, Copy)]
+pub struct MpnAssignmentPolicy {
+    /// Seconds a worker has for solving an assigned work
+    pub deadline: u32,
+    /// Number of workers a work is assigned to at the same time
+    pub redundancy: usize,
+    /// Number of works a fully reliable worker gets at the same time
+    pub works_per_worker: usize,
+}
+
+impl Default for MpnAssignmentPolicy {
+    fn default() -> Self {
+        Self {
+            deadline: 60,
+            redundancy: 1,
+            works_per_worker: 2,
+        }
+    }
+}
+
+/// Reward of the prover of a work
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MpnReward {
+    /// Fixed amount, paid from the block reward
+    Fixed(Amount),
+    /// Percentage of the block reward
+    Percent(u8),
+    /// Percentage of the Ziesha fees collected by the transactions of the work
+    FeeShare(u8),
+}
+
+impl MpnReward {
+    fn percent_of(amount: Amount, percent: u8) -> Amount {
+        Amount((u64::from(amount) as u128 * percent as u128 / 100) as u64)
+    }
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Self::Fixed(_) => true,
+            Self::Percent(p) | Self::FeeShare(p) => *p <= 100,
+        }
+    }
+    /// Part of the reward which is paid from the block reward
+    pub fn from_block_reward(&self, block_reward: Amount) -> Amount {
+        match self {
+            Self::Fixed(amount) => *amount,
+            Self::Percent(p) => Self::percent_of(block_reward, *p),
+            Self::FeeShare(_) => Amount(0),
+        }
+    }
+    /// Part of the reward which is paid from the collected fees
+    pub fn from_fees(&self, fees: Amount) -> Amount {
+        match self {
+            Self::FeeShare(p) => Self::percent_of(fees, *p),
+            _ => Amount(0),
+        }
+    }
+}
+
+/// Rewards a validator pays to the provers of its MPN works
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MpnRewardPolicy {
+    pub deposit: MpnReward,
+    pub withdraw: MpnReward,
+    pub update: MpnReward,
+}
+
+impl MpnRewardPolicy {
+    /// Part of the block reward which is left after paying the provers of
+    /// all the works of a block, None if the rewards don't fit in it
+    pub fn remaining_block_reward(
+        &self,
+        config: &MpnConfig,
+        block_reward: Amount,
+    ) -> Option<Amount> {
+        [
+            (config.mpn_num_deposit_batches, self.deposit),
+            (config.mpn_num_withdraw_batches, self.withdraw),
+            (config.mpn_num_update_batches, self.update),
+        ]
+        .into_iter()
+        .try_fold(u64::from(block_reward), |remaining, (batches, reward)| {
+            remaining.checked_sub(
+                (batches as u64).checked_mul(u64::from(reward.from_block_reward(block_reward)))?,
+            )
+        })
+        .map(Amount)
+    }
+}
+
+impl Default for MpnRewardPolicy {
+    fn default() -> Self {
+        Self {
+            deposit: MpnReward::Percent(5),
+            withdraw: MpnReward::Percent(5),
+            update: MpnReward::Percent(15),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MpnWorkerStats {
+    /// Works handed out to the worker
+    pub assigned: u64,
+    /// Works the worker has been the first to solve
+    pub solved: u64,
+    /// Sum of the rewards of the solved works which are included in the chain
+    pub rewards: Amount,
+    /// Works the worker didn't solve before their deadline
+    pub late: u64,
+}
+
+impl MpnWorkerStats {
+    /// Ratio of the finished assignments which were not late. New workers are
+    /// trusted.
+    pub fn reliability(&self) -> f64 {
+        (self.solved + 1) as f64 / (self.solved + self.late + 1) as f64
+    }
+}
+
+struct MpnAssignment {
+    worker: Address,
+    deadline: u32,
+}
+
 pub struct MpnWorkPool {
     config: MpnConfig,
+    policy: MpnAssignmentPolicy,
     final_delta: ZkDeltaPairs,
     works: HashMap<usize, MpnWork>,
     solutions: HashMap<usize, MpnSolution>,
-    assignees: HashMap<Address, Vec<usize>>,
+    assignments: HashMap<usize, Vec<MpnAssignment>>,
+    /// Workers which missed the deadline of a work
+    expired: HashMap<usize, HashSet<Address>>,
 }
 
 impl MpnWorkPool {
@@ -76,36 +210,101 @@ impl MpnWorkPool {
         }
         remaining
     }
-    pub fn get_works(&mut self, address: Address) -> HashMap<usize, MpnWork> {
-        let selected = if let Some(works) = self.assignees.get(&address) {
-            works.clone()
-        } else {
-            let mut not_assigned: HashMap<usize, MpnWork> =
-                self.remaining_works().into_iter().collect();
-            for wid in self.assignees.values().flatten() {
-                not_assigned.remove(wid);
-            }
+    /// Number of solved works and the total number of works
+    pub fn progress(&self) -> (usize, usize) {
+        (self.solutions.len(), self.works.len())
+    }
+    pub fn with_policy(mut self, policy: MpnAssignmentPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+    /// Drops the assignments whose deadline has passed, counting them as late.
+    /// Only the workers which already have an entry in `stats` (The registered
+    /// ones) are accounted, here and in `get_works`/`prove`.
+    pub fn expire(&mut self, now: u32, stats: &mut HashMap<Address, MpnWorkerStats>) {
+        for (id, assignments) in self.assignments.iter_mut() {
+            assignments.retain(|a| {
+                if a.deadline > now {
+                    return true;
+                }
+                log::warn!("Worker {} is late on work {}!", a.worker, id);
+                if let Some(worker_stats) = stats.get_mut(&a.worker) {
+                    worker_stats.late += 1;
+                }
+                self.expired
+                    .entry(*id)
+                    .or_default()
+                    .insert(a.worker.clone());
+                false
+            });
+        }
+    }
+    /// Works assigned to a worker. Workers get fewer works as they miss
+    /// deadlines, and works whose deadline is missed are only reassigned to
+    /// other registered workers.
+    pub fn get_works(
+        &mut self,
+        address: Address,
+        registered: bool,
+        now: u32,
+        stats: &mut HashMap<Address, MpnWorkerStats>,
+    ) -> HashMap<usize, MpnWork> {
+        self.expire(now, stats);
+        let reliability = stats
+            .get(&address)
+            .map(|s| s.reliability())
+            .unwrap_or_else(|| MpnWorkerStats::default().reliability());
+        let capacity =
+            ((self.policy.works_per_worker as f64 * reliability).round() as usize).max(1);
 
-            let selected = not_assigned
-                .keys()
-                .cloned()
-                .choose_multiple(&mut rand::thread_rng(), 2);
-            self.assignees.insert(address, selected.clone());
-            selected
-        };
+        let mut selected = self
+            .assignments
+            .iter()
+            .filter(|(_, assignments)| assignments.iter().any(|a| a.worker == address))
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        if selected.len() < capacity {
+            let mut candidates = self
+                .remaining_works()
+                .into_keys()
+                .filter(|id| !selected.contains(id))
+                .filter(|id| {
+                    self.assignments.get(id).map(|a| a.len()).unwrap_or(0) < self.policy.redundancy
+                })
+                .filter(|id| {
+                    self.expired
+                        .get(id)
+                        .map(|workers| registered && !workers.contains(&address))
+                        .unwrap_or(true)
+                })
+                .collect::<Vec<_>>();
+            // Works with the fewest workers first
+            candidates.shuffle(&mut rand::thread_rng());
+            candidates.sort_by_key(|id| self.assignments.get(id).map(|a| a.len()).unwrap_or(0));
+            for id in candidates.into_iter().take(capacity - selected.len()) {
+                self.assignments.entry(id).or_default().push(MpnAssignment {
+                    worker: address.clone(),
+                    deadline: now + self.policy.deadline,
+                });
+                if let Some(worker_stats) = stats.get_mut(&address) {
+                    worker_stats.assigned += 1;
+                }
+                selected.push(id);
+            }
+        }
 
         selected
             .iter()
-            .filter_map(|i| {
-                if let Some(w) = self.works.get(i) {
-                    Some((*i, w.clone()))
-                } else {
-                    None
-                }
-            })
+            .filter_map(|i| self.works.get(i).map(|w| (*i, w.clone())))
             .collect()
     }
-    pub fn prove(&mut self, id: usize, prover: &Address, proof: &ZkProof) -> bool {
+    pub fn prove(
+        &mut self,
+        id: usize,
+        prover: &Address,
+        proof: &ZkProof,
+        stats: &mut HashMap<Address, MpnWorkerStats>,
+    ) -> bool {
         if !self.solutions.contains_key(&id) {
             if let Some(work) = self.works.get(&id) {
                 if work.verify(prover, proof) {
@@ -116,6 +315,10 @@ impl MpnWorkPool {
                             proof: proof.clone(),
                         },
                     );
+                    self.assignments.remove(&id);
+                    if let Some(prover_stats) = stats.get_mut(prover) {
+                        prover_stats.solved += 1;
+                    }
                     true
                 } else {
                     false
@@ -127,6 +330,15 @@ impl MpnWorkPool {
             false
         }
     }
+    /// Solves the works of the circuits that accept dummy proofs
+    #[cfg(any(test, feature = "dev-proofs"))]
+    pub fn solve_dummy_works(&mut self, prover: &Address) {
+        for id in self.remaining_works().into_keys() {
+            if self.works[&id].vk() == ZkVerifierKey::Dummy {
+                self.prove(id, prover, &ZkProof::Dummy(true), &mut HashMap::new());
+            }
+        }
+    }
     pub fn ready(&self, tx_builder: &TxBuilder, nonce: u32) -> Option<TransactionAndDelta> {
         if self.works.len() == self.solutions.len() {
             let mut updates = vec![];
@@ -278,14 +490,17 @@ impl MpnWork {
         }
         .clone()
     }
+    /// Binds a proof to its prover and reward, so that it can't be stolen
+    pub fn commitment(&self, prover: &Address) -> ZkScalar {
+        ZkScalar::new(
+            Hasher::hash(&bincode::serialize(&(prover.clone(), self.reward)).unwrap()).as_ref(),
+        )
+    }
     pub fn verify(&self, prover: &Address, proof: &ZkProof) -> bool {
         let vk = self.vk();
-        let commitment = ZkScalar::new(
-            Hasher::hash(&bincode::serialize(&(prover.clone(), self.reward)).unwrap()).as_ref(),
-        );
         check_proof(
             &vk,
-            commitment,
+            self.commitment(prover),
             self.public_inputs.height,
             self.public_inputs.state,
             self.public_inputs.aux_data,
@@ -303,9 +518,7 @@ pub fn prepare_works<K: KvStore, B: Blockchain<K>>(
     withdraws: Vec<MpnWithdraw>,
     updates: Vec<MpnTransaction>,
     block_reward: Amount,
-    deposit_reward: Amount,
-    withdraw_reward: Amount,
-    update_reward: Amount,
+    rewards: &MpnRewardPolicy,
     validator_tx_builder_deposit_nonce: u32,
     validator_tx_builder: TxBuilder,
     user_tx_builder: TxBuilder,
@@ -322,14 +535,25 @@ pub fn prepare_works<K: KvStore, B: Blockchain<K>>(
     let mut worker_id = 0;
     let mut new_account_indices = HashMap::<MpnAddress, u64>::new();
 
-    let remaining_reward: Amount = (u64::from(block_reward)
-        - config.mpn_num_deposit_batches as u64 * u64::from(deposit_reward)
-        - config.mpn_num_withdraw_batches as u64 * u64::from(withdraw_reward)
-        - config.mpn_num_update_batches as u64 * u64::from(update_reward))
-    .into();
+    let remaining_reward = rewards
+        .remaining_block_reward(config, block_reward)
+        .ok_or(MpnError::InsufficientBlockReward)?;
 
     let validator_balance =
         db.get_balance(validator_tx_builder.get_address(), ContractId::Ziesha)?;
+    // Fee shares are paid before the fees of the works are collected, so
+    // they are capped by what the validator already has.
+    let mut fee_share_budget = validator_balance;
+    let mut work_reward = |rule: MpnReward, fees: Vec<&Money>| {
+        let fees = fees
+            .into_iter()
+            .filter(|f| f.token_id == ContractId::Ziesha)
+            .map(|f| f.amount)
+            .fold(Amount(0), |a, b| a + b);
+        let fee_share = std::cmp::min(rule.from_fees(fees), fee_share_budget);
+        fee_share_budget -= fee_share;
+        rule.from_block_reward(block_reward) + fee_share
+    };
     mirror.database_mut().update(&[WriteOp::Put(
         crate::db::keys::account_balance(&validator_tx_builder.get_address(), ContractId::Ziesha),
         (validator_balance + remaining_reward).into(),
@@ -362,12 +586,16 @@ pub fn prepare_works<K: KvStore, B: Blockchain<K>>(
             true,
         )?;
         log::info!("Made MPN-Deposit block of {} txs.", transitions.len());
+        let reward = work_reward(
+            rewards.deposit,
+            transitions.iter().map(|t| &t.tx.payment.fee).collect(),
+        );
         works.push(MpnWork {
             config: config.clone(),
             public_inputs,
             new_root,
             data: MpnWorkData::Deposit(transitions),
-            reward: deposit_reward,
+            reward,
         });
         worker_id = (worker_id + 1) % workers.len();
     }
@@ -382,12 +610,16 @@ pub fn prepare_works<K: KvStore, B: Blockchain<K>>(
             &mut new_account_indices,
         )?;
         log::info!("Made MPN-Withdraw block of {} txs.", transitions.len());
+        let reward = work_reward(
+            rewards.withdraw,
+            transitions.iter().map(|t| &t.tx.payment.fee).collect(),
+        );
         works.push(MpnWork {
             config: config.clone(),
             public_inputs,
             new_root,
             data: MpnWorkData::Withdraw(transitions),
-            reward: withdraw_reward,
+            reward,
         });
         worker_id = (worker_id + 1) % workers.len();
     }
@@ -403,24 +635,37 @@ pub fn prepare_works<K: KvStore, B: Blockchain<K>>(
             &mut new_account_indices,
         )?;
         log::info!("Made MPN-Update block of {} txs.", transitions.len());
+        let reward = work_reward(
+            rewards.update,
+            transitions.iter().map(|t| &t.tx.fee).collect(),
+        );
         works.push(MpnWork {
             config: config.clone(),
             public_inputs,
             new_root,
             data: MpnWorkData::Update(transitions),
-            reward: update_reward,
+            reward,
         });
         worker_id = (worker_id + 1) % workers.len();
     }
     let ops = mirror.database().to_ops();
     let final_delta = extract_delta(&ops);
-    Ok(MpnWorkPool {
+    let pool = MpnWorkPool {
         config: config.clone(),
+        policy: Default::default(),
         works: works.into_iter().enumerate().collect(),
         final_delta,
         solutions: HashMap::new(),
-        assignees: HashMap::new(),
-    })
+        assignments: HashMap::new(),
+        expired: HashMap::new(),
+    };
+    #[cfg(any(test, feature = "dev-proofs"))]
+    let pool = {
+        let mut pool = pool;
+        pool.solve_dummy_works(&user_tx_builder.get_address());
+        pool
+    };
+    Ok(pool)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -546,3 +791,6 @@ mod tests {
         KvStoreChain::new(RamKvStore::new(), conf).unwrap()
     }
 }
+
+#[cfg(test)]
+mod test;
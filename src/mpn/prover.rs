@@ -0,0 +1,222 @@
+use super::circuits::{DepositCircuit, UpdateCircuit, WithdrawCircuit};
+use super::setup::{load_proving_key, MpnCircuitKind, MpnSetupError};
+use super::{DepositTransition, MpnWork, MpnWorkData, UpdateTransition, WithdrawTransition};
+use crate::core::{Address, ContractId};
+use crate::zk::groth16::Groth16VerifyingKey;
+use crate::zk::{ZkProof, ZkVerifierKey};
+use bellman::groth16::{create_random_proof, Parameters};
+use bellman::SynthesisError;
+use bls12_381::Bls12;
+use rand::rngs::OsRng;
+use std::collections::HashMap;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum MpnProverError {
+    #[error("setup error happened: {0}")]
+    SetupError(#[from] MpnSetupError),
+    #[error("synthesis error happened: {0}")]
+    SynthesisError(#[from] SynthesisError),
+    #[error("proving parameters do not match the verifier key of the work")]
+    VerifierKeyMismatch,
+}
+
+/// Proves MPN works with the Groth16 proving parameters of the circuits.
+/// Proofs are computed by `bellman` on the global rayon pool.
+pub struct MpnProver {
+    deposit: Parameters<Bls12>,
+    withdraw: Parameters<Bls12>,
+    update: Parameters<Bls12>,
+}
+
+fn pad<T: Clone>(mut transitions: Vec<T>, log4_batch_size: u8, null: T) -> Vec<T> {
+    transitions.resize(1 << (2 * log4_batch_size), null);
+    transitions
+}
+
+impl MpnProver {
+    pub fn new(
+        deposit: Parameters<Bls12>,
+        withdraw: Parameters<Bls12>,
+        update: Parameters<Bls12>,
+    ) -> Self {
+        Self {
+            deposit,
+            withdraw,
+            update,
+        }
+    }
+
+    /// Loads the proving keys written by the circuits setup into a directory
+    pub fn load(dir: &Path) -> Result<Self, MpnProverError> {
+        Ok(Self::new(
+            load_proving_key(dir, MpnCircuitKind::Deposit)?.1,
+            load_proving_key(dir, MpnCircuitKind::Withdraw)?.1,
+            load_proving_key(dir, MpnCircuitKind::Update)?.1,
+        ))
+    }
+
+    fn params(&self, work: &MpnWork) -> Result<&Parameters<Bls12>, MpnProverError> {
+        let params = match &work.data {
+            MpnWorkData::Deposit(_) => &self.deposit,
+            MpnWorkData::Withdraw(_) => &self.withdraw,
+            MpnWorkData::Update(_) => &self.update,
+        };
+        let vk = ZkVerifierKey::Groth16(Box::new(Groth16VerifyingKey::from(params.vk.clone())));
+        if vk != work.vk() {
+            return Err(MpnProverError::VerifierKeyMismatch);
+        }
+        Ok(params)
+    }
+
+    /// Proves a single work, on behalf of `prover`
+    pub fn prove(&self, prover: &Address, work: &MpnWork) -> Result<ZkProof, MpnProverError> {
+        let params = self.params(work)?;
+        let config = &work.config;
+        let inputs = &work.public_inputs;
+        let commitment = work.commitment(prover);
+        let proof = match work.data.clone() {
+            MpnWorkData::Deposit(transitions) => create_random_proof(
+                DepositCircuit {
+                    log4_tree_size: config.log4_tree_size,
+                    log4_token_tree_size: config.log4_token_tree_size,
+                    log4_deposit_batch_size: config.log4_deposit_batch_size,
+                    commitment,
+                    height: inputs.height,
+                    state: inputs.state,
+                    aux_data: inputs.aux_data,
+                    next_state: inputs.next_state,
+                    transitions: pad(
+                        transitions,
+                        config.log4_deposit_batch_size,
+                        DepositTransition::null(config.log4_tree_size, config.log4_token_tree_size),
+                    ),
+                },
+                params,
+                &mut OsRng,
+            )?,
+            MpnWorkData::Withdraw(transitions) => create_random_proof(
+                WithdrawCircuit {
+                    log4_tree_size: config.log4_tree_size,
+                    log4_token_tree_size: config.log4_token_tree_size,
+                    log4_withdraw_batch_size: config.log4_withdraw_batch_size,
+                    commitment,
+                    height: inputs.height,
+                    state: inputs.state,
+                    aux_data: inputs.aux_data,
+                    next_state: inputs.next_state,
+                    transitions: pad(
+                        transitions,
+                        config.log4_withdraw_batch_size,
+                        WithdrawTransition::null(
+                            config.log4_tree_size,
+                            config.log4_token_tree_size,
+                        ),
+                    ),
+                },
+                params,
+                &mut OsRng,
+            )?,
+            MpnWorkData::Update(transitions) => create_random_proof(
+                UpdateCircuit {
+                    log4_tree_size: config.log4_tree_size,
+                    log4_token_tree_size: config.log4_token_tree_size,
+                    log4_update_batch_size: config.log4_update_batch_size,
+                    commitment,
+                    height: inputs.height,
+                    state: inputs.state,
+                    aux_data: inputs.aux_data,
+                    next_state: inputs.next_state,
+                    // Update works only collect Ziesha fees (See `prepare_works`)
+                    fee_token: ContractId::Ziesha,
+                    transitions: pad(
+                        transitions,
+                        config.log4_update_batch_size,
+                        UpdateTransition::null(config.log4_tree_size, config.log4_token_tree_size),
+                    ),
+                },
+                params,
+                &mut OsRng,
+            )?,
+        };
+        Ok(ZkProof::Groth16(Box::new(proof.into())))
+    }
+
+    /// Proves the works one by one, since a single proof already keeps the
+    /// rayon pool busy (And `bellman` can't be called from within the pool).
+    /// Works that can't be proven are logged and left out.
+    pub fn prove_works(
+        &self,
+        prover: &Address,
+        works: &HashMap<usize, MpnWork>,
+    ) -> HashMap<usize, ZkProof> {
+        works
+            .iter()
+            .filter_map(|(id, work)| match self.prove(prover, work) {
+                Ok(proof) => Some((*id, proof)),
+                Err(e) => {
+                    log::error!("Could not prove MPN work {}: {}", id, e);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Amount, ZkHasher};
+    use crate::mpn::circuits::MpnCircuit;
+    use crate::mpn::{MpnConfig, ZkPublicInputs};
+    use crate::zk::{ZkCompressedState, ZkScalar, ZkStateModel};
+    use bellman::groth16::generate_random_parameters;
+
+    #[test]
+    fn test_prove_empty_deposit_work() {
+        let params =
+            generate_random_parameters::<Bls12, _, _>(DepositCircuit::empty(3, 3, 1), &mut OsRng)
+                .unwrap();
+        let vk = ZkVerifierKey::Groth16(Box::new(params.vk.clone().into()));
+        let config = MpnConfig {
+            log4_tree_size: 3,
+            log4_token_tree_size: 3,
+            log4_deposit_batch_size: 1,
+            log4_withdraw_batch_size: 1,
+            log4_update_batch_size: 1,
+            mpn_contract_id: ContractId::Ziesha,
+            mpn_num_update_batches: 0,
+            mpn_num_deposit_batches: 1,
+            mpn_num_withdraw_batches: 0,
+            deposit_vk: vk.clone(),
+            withdraw_vk: vk.clone(),
+            update_vk: vk,
+        };
+        let deposits_model = ZkStateModel::List {
+            item_type: Box::new(ZkStateModel::Struct {
+                field_types: vec![ZkStateModel::Scalar; 4],
+            }),
+            log4_size: 1,
+        };
+        let work = MpnWork {
+            config,
+            public_inputs: ZkPublicInputs {
+                height: 0,
+                state: ZkScalar::from(123),
+                aux_data: deposits_model.compress_default::<ZkHasher>(),
+                next_state: ZkScalar::from(123),
+            },
+            data: MpnWorkData::Deposit(vec![]),
+            new_root: ZkCompressedState::new(ZkScalar::from(123), 0),
+            reward: Amount(10),
+        };
+        let prover = MpnProver::new(params.clone(), params.clone(), params);
+        let alice = crate::wallet::TxBuilder::new(&Vec::from("ALICE")).get_address();
+        let bob = crate::wallet::TxBuilder::new(&Vec::from("BOB")).get_address();
+
+        let proofs = prover.prove_works(&alice, &[(3, work.clone())].into());
+        assert!(work.verify(&alice, &proofs[&3]));
+        assert!(!work.verify(&bob, &proofs[&3]));
+    }
+}
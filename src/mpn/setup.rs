@@ -0,0 +1,334 @@
+use super::circuits::{DepositCircuit, MpnCircuit, UpdateCircuit, WithdrawCircuit};
+use crate::zk::groth16::gadgets::BellmanFr;
+use crate::zk::groth16::Groth16VerifyingKey;
+use bellman::groth16::{generate_random_parameters, Parameters, VerifyingKey};
+use bellman::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+use bls12_381::Bls12;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::Path;
+use thiserror::Error;
+
+/// Magic bytes of the proving-key and verifying-key files
+pub const MPN_PROVING_KEY_MAGIC: &[u8; 4] = b"BZPK";
+pub const MPN_VERIFYING_KEY_MAGIC: &[u8; 4] = b"BZVK";
+pub const MPN_KEYS_VERSION: u32 = 1;
+
+#[derive(Error, Debug)]
+pub enum MpnSetupError {
+    #[error("io error happened: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("bincode error happened: {0}")]
+    BincodeError(#[from] bincode::Error),
+    #[error("synthesis error happened: {0}")]
+    SynthesisError(#[from] SynthesisError),
+    #[error("not an mpn key file")]
+    InvalidMagic,
+    #[error("unsupported key file version: {0}")]
+    UnsupportedVersion(u32),
+    #[error("key file is for the {0:?} circuit, not the {1:?} circuit")]
+    CircuitMismatch(MpnCircuitKind, MpnCircuitKind),
+    #[error("circuits are generated for different tree sizes")]
+    InconsistentTreeSizes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MpnCircuitKind {
+    Deposit,
+    Withdraw,
+    Update,
+}
+
+impl MpnCircuitKind {
+    pub const ALL: [MpnCircuitKind; 3] = [Self::Deposit, Self::Withdraw, Self::Update];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Deposit => "deposit",
+            Self::Withdraw => "withdraw",
+            Self::Update => "update",
+        }
+    }
+    pub fn proving_key_file(&self) -> String {
+        format!("{}.pk", self.name())
+    }
+    pub fn verifying_key_file(&self) -> String {
+        format!("{}.vk", self.name())
+    }
+}
+
+/// Sizes of the circuit a key file is generated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MpnCircuitParams {
+    pub kind: MpnCircuitKind,
+    pub log4_tree_size: u8,
+    pub log4_token_tree_size: u8,
+    pub log4_batch_size: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MpnKeysHeader {
+    version: u32,
+    circuit: MpnCircuitParams,
+}
+
+fn write_header<W: Write>(
+    mut writer: W,
+    magic: &[u8; 4],
+    circuit: MpnCircuitParams,
+) -> Result<W, MpnSetupError> {
+    writer.write_all(magic)?;
+    bincode::serialize_into(
+        &mut writer,
+        &MpnKeysHeader {
+            version: MPN_KEYS_VERSION,
+            circuit,
+        },
+    )?;
+    Ok(writer)
+}
+
+fn read_header<R: Read>(
+    mut reader: R,
+    magic: &[u8; 4],
+) -> Result<(R, MpnCircuitParams), MpnSetupError> {
+    let mut file_magic = [0u8; 4];
+    reader.read_exact(&mut file_magic)?;
+    if &file_magic != magic {
+        return Err(MpnSetupError::InvalidMagic);
+    }
+    let header: MpnKeysHeader = bincode::deserialize_from(&mut reader)?;
+    if header.version != MPN_KEYS_VERSION {
+        return Err(MpnSetupError::UnsupportedVersion(header.version));
+    }
+    Ok((reader, header.circuit))
+}
+
+/// Counts the constraints of a circuit without computing its witnesses
+#[derive(Default)]
+struct ConstraintCounter {
+    num_inputs: usize,
+    num_aux: usize,
+    num_constraints: usize,
+}
+
+impl ConstraintSystem<BellmanFr> for ConstraintCounter {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, _annotation: A, _f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<BellmanFr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.num_aux += 1;
+        Ok(Variable::new_unchecked(Index::Aux(self.num_aux - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _annotation: A, _f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<BellmanFr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.num_inputs += 1;
+        Ok(Variable::new_unchecked(Index::Input(self.num_inputs)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _annotation: A, _a: LA, _b: LB, _c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<BellmanFr>) -> LinearCombination<BellmanFr>,
+        LB: FnOnce(LinearCombination<BellmanFr>) -> LinearCombination<BellmanFr>,
+        LC: FnOnce(LinearCombination<BellmanFr>) -> LinearCombination<BellmanFr>,
+    {
+        self.num_constraints += 1;
+    }
+
+    fn push_namespace<NR, N>(&mut self, _name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self) {}
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+/// Result of the Groth16 setup of an MPN circuit
+pub struct MpnCircuitSetup {
+    pub circuit: MpnCircuitParams,
+    pub params: Parameters<Bls12>,
+    pub num_constraints: usize,
+}
+
+fn setup_circuit<C: Circuit<BellmanFr> + MpnCircuit + Clone, R: RngCore>(
+    circuit: MpnCircuitParams,
+    rng: &mut R,
+) -> Result<MpnCircuitSetup, MpnSetupError> {
+    let empty = C::empty(
+        circuit.log4_tree_size,
+        circuit.log4_token_tree_size,
+        circuit.log4_batch_size,
+    );
+    let mut counter = ConstraintCounter::default();
+    empty.clone().synthesize(&mut counter)?;
+    Ok(MpnCircuitSetup {
+        circuit,
+        params: generate_random_parameters::<Bls12, _, _>(empty, rng)?,
+        num_constraints: counter.num_constraints,
+    })
+}
+
+impl MpnCircuitSetup {
+    /// Runs a Groth16 setup of the circuit. The randomness of `rng` has to be
+    /// thrown away for the keys to be secure.
+    pub fn generate<R: RngCore>(
+        circuit: MpnCircuitParams,
+        rng: &mut R,
+    ) -> Result<Self, MpnSetupError> {
+        match circuit.kind {
+            MpnCircuitKind::Deposit => setup_circuit::<DepositCircuit, _>(circuit, rng),
+            MpnCircuitKind::Withdraw => setup_circuit::<WithdrawCircuit, _>(circuit, rng),
+            MpnCircuitKind::Update => setup_circuit::<UpdateCircuit, _>(circuit, rng),
+        }
+    }
+
+    pub fn verifying_key(&self) -> Groth16VerifyingKey {
+        self.params.vk.clone().into()
+    }
+
+    /// Writes `<circuit>.pk` and `<circuit>.vk` in the given directory
+    pub fn save(&self, dir: &Path) -> Result<(), MpnSetupError> {
+        let pk = std::fs::File::create(dir.join(self.circuit.kind.proving_key_file()))?;
+        let mut pk = write_header(
+            std::io::BufWriter::new(pk),
+            MPN_PROVING_KEY_MAGIC,
+            self.circuit,
+        )?;
+        self.params.write(&mut pk)?;
+        pk.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        let vk = std::fs::File::create(dir.join(self.circuit.kind.verifying_key_file()))?;
+        let mut vk = write_header(
+            std::io::BufWriter::new(vk),
+            MPN_VERIFYING_KEY_MAGIC,
+            self.circuit,
+        )?;
+        self.params.vk.write(&mut vk)?;
+        vk.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        Ok(())
+    }
+}
+
+fn check_kind(circuit: &MpnCircuitParams, expected: MpnCircuitKind) -> Result<(), MpnSetupError> {
+    if circuit.kind != expected {
+        return Err(MpnSetupError::CircuitMismatch(circuit.kind, expected));
+    }
+    Ok(())
+}
+
+/// Reads the proving key of a circuit from its file in the given directory
+pub fn load_proving_key(
+    dir: &Path,
+    kind: MpnCircuitKind,
+) -> Result<(MpnCircuitParams, Parameters<Bls12>), MpnSetupError> {
+    let file = std::fs::File::open(dir.join(kind.proving_key_file()))?;
+    let (reader, circuit) = read_header(std::io::BufReader::new(file), MPN_PROVING_KEY_MAGIC)?;
+    check_kind(&circuit, kind)?;
+    Ok((circuit, Parameters::read(reader, false)?))
+}
+
+/// Reads the verifying key of a circuit from its file in the given directory
+pub fn load_verifying_key(
+    dir: &Path,
+    kind: MpnCircuitKind,
+) -> Result<(MpnCircuitParams, Groth16VerifyingKey), MpnSetupError> {
+    let file = std::fs::File::open(dir.join(kind.verifying_key_file()))?;
+    let (reader, circuit) = read_header(std::io::BufReader::new(file), MPN_VERIFYING_KEY_MAGIC)?;
+    check_kind(&circuit, kind)?;
+    Ok((circuit, VerifyingKey::<Bls12>::read(reader)?.into()))
+}
+
+/// Verifying keys of all the MPN circuits, along with their sizes
+#[derive(Debug, Clone)]
+pub struct MpnVerifyingKeys {
+    pub deposit: (MpnCircuitParams, Groth16VerifyingKey),
+    pub withdraw: (MpnCircuitParams, Groth16VerifyingKey),
+    pub update: (MpnCircuitParams, Groth16VerifyingKey),
+}
+
+impl MpnVerifyingKeys {
+    /// Reads the verifying keys of a directory, which should all be generated
+    /// for the same tree sizes
+    pub fn load(dir: &Path) -> Result<Self, MpnSetupError> {
+        let keys = Self {
+            deposit: load_verifying_key(dir, MpnCircuitKind::Deposit)?,
+            withdraw: load_verifying_key(dir, MpnCircuitKind::Withdraw)?,
+            update: load_verifying_key(dir, MpnCircuitKind::Update)?,
+        };
+        for (circuit, _) in [&keys.withdraw, &keys.update] {
+            if circuit.log4_tree_size != keys.deposit.0.log4_tree_size
+                || circuit.log4_token_tree_size != keys.deposit.0.log4_token_tree_size
+            {
+                return Err(MpnSetupError::InconsistentTreeSizes);
+            }
+        }
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_setup_keys_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("bazuka-mpn-keys-{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let circuit = MpnCircuitParams {
+            kind: MpnCircuitKind::Deposit,
+            log4_tree_size: 3,
+            log4_token_tree_size: 3,
+            log4_batch_size: 1,
+        };
+        let setup = MpnCircuitSetup::generate(circuit, &mut OsRng).unwrap();
+        assert!(setup.num_constraints > 0);
+        setup.save(&dir).unwrap();
+
+        let (vk_circuit, vk) = load_verifying_key(&dir, MpnCircuitKind::Deposit).unwrap();
+        assert_eq!(vk_circuit, circuit);
+        assert_eq!(vk, setup.verifying_key());
+        let (pk_circuit, pk) = load_proving_key(&dir, MpnCircuitKind::Deposit).unwrap();
+        assert_eq!(pk_circuit, circuit);
+        assert_eq!(Groth16VerifyingKey::from(pk.vk), setup.verifying_key());
+
+        // Files of other circuits and other kinds of keys are rejected
+        std::fs::copy(
+            dir.join(MpnCircuitKind::Deposit.verifying_key_file()),
+            dir.join(MpnCircuitKind::Update.verifying_key_file()),
+        )
+        .unwrap();
+        assert!(matches!(
+            load_verifying_key(&dir, MpnCircuitKind::Update),
+            Err(MpnSetupError::CircuitMismatch(..))
+        ));
+        std::fs::copy(
+            dir.join(MpnCircuitKind::Deposit.proving_key_file()),
+            dir.join(MpnCircuitKind::Withdraw.verifying_key_file()),
+        )
+        .unwrap();
+        assert!(matches!(
+            load_verifying_key(&dir, MpnCircuitKind::Withdraw),
+            Err(MpnSetupError::InvalidMagic)
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
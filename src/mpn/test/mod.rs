@@ -0,0 +1,189 @@
+use super::*;
+use crate::zk::ZkVerifierKey;
+
+fn dummy_pool(num_works: usize, policy: MpnAssignmentPolicy) -> MpnWorkPool {
+    let config = MpnConfig {
+        log4_tree_size: 1,
+        log4_token_tree_size: 1,
+        log4_deposit_batch_size: 1,
+        log4_withdraw_batch_size: 1,
+        log4_update_batch_size: 1,
+        mpn_contract_id: ContractId::Ziesha,
+        mpn_num_update_batches: 0,
+        mpn_num_deposit_batches: num_works,
+        mpn_num_withdraw_batches: 0,
+        deposit_vk: ZkVerifierKey::Dummy,
+        withdraw_vk: ZkVerifierKey::Dummy,
+        update_vk: ZkVerifierKey::Dummy,
+    };
+    let work = MpnWork {
+        config: config.clone(),
+        public_inputs: ZkPublicInputs {
+            height: 0,
+            state: Default::default(),
+            aux_data: Default::default(),
+            next_state: Default::default(),
+        },
+        data: MpnWorkData::Deposit(vec![]),
+        new_root: Default::default(),
+        reward: Amount(10),
+    };
+    MpnWorkPool {
+        config,
+        policy: Default::default(),
+        final_delta: Default::default(),
+        works: (0..num_works).map(|i| (i, work.clone())).collect(),
+        solutions: HashMap::new(),
+        assignments: HashMap::new(),
+        expired: HashMap::new(),
+    }
+    .with_policy(policy)
+}
+
+fn addr(name: &str) -> Address {
+    TxBuilder::new(&Vec::from(name)).get_address()
+}
+
+#[test]
+fn test_work_deadlines_and_reassignment() {
+    let mut pool = dummy_pool(
+        4,
+        MpnAssignmentPolicy {
+            deadline: 10,
+            redundancy: 1,
+            works_per_worker: 2,
+        },
+    );
+    let (alice, bob, carol) = (addr("ALICE"), addr("BOB"), addr("CAROL"));
+    let mut stats: HashMap<Address, MpnWorkerStats> = [&alice, &bob, &carol]
+        .into_iter()
+        .map(|a| (a.clone(), Default::default()))
+        .collect();
+
+    let alice_works = pool.get_works(alice.clone(), true, 0, &mut stats);
+    assert_eq!(alice_works.len(), 2);
+    // Same works are returned before the deadline
+    assert_eq!(
+        pool.get_works(alice.clone(), true, 5, &mut stats)
+            .into_keys()
+            .collect::<HashSet<_>>(),
+        alice_works.keys().cloned().collect()
+    );
+    let bob_works = pool.get_works(bob.clone(), false, 5, &mut stats);
+    assert_eq!(bob_works.len(), 2);
+    assert!(bob_works.keys().all(|id| !alice_works.contains_key(id)));
+    // Nothing left for a third worker
+    assert!(pool
+        .get_works(carol.clone(), true, 5, &mut stats)
+        .is_empty());
+
+    // Bob solves one of his works
+    let (bob_solved, _) = bob_works.iter().next().unwrap();
+    assert!(pool.prove(*bob_solved, &bob, &ZkProof::Dummy(true), &mut stats));
+    assert!(!pool.prove(*bob_solved, &alice, &ZkProof::Dummy(true), &mut stats));
+
+    // Alice misses her deadline, her works go to Carol which is registered
+    let carol_works = pool.get_works(carol.clone(), true, 10, &mut stats);
+    assert_eq!(stats[&alice].late, 2);
+    assert_eq!(
+        carol_works.into_keys().collect::<HashSet<_>>(),
+        alice_works.keys().cloned().collect()
+    );
+    assert_eq!(stats[&bob].solved, 1);
+
+    // Bob misses his deadline too. Its work is not reassigned to Bob himself,
+    // nor to Alice, and not to unregistered workers.
+    pool.expire(15, &mut stats);
+    assert_eq!(stats[&bob].late, 1);
+    assert!(pool.get_works(bob.clone(), true, 15, &mut stats).is_empty());
+    assert!(pool
+        .get_works(addr("DAVE"), false, 15, &mut stats)
+        .is_empty());
+    // Workers without stats are not accounted
+    assert!(!stats.contains_key(&addr("DAVE")));
+
+    // Late workers get fewer works
+    assert_eq!(stats[&alice].reliability(), 1. / 3.);
+    assert_eq!(pool.get_works(alice, true, 15, &mut stats).len(), 1);
+}
+
+#[test]
+fn test_work_redundancy() {
+    let mut pool = dummy_pool(
+        1,
+        MpnAssignmentPolicy {
+            deadline: 10,
+            redundancy: 2,
+            works_per_worker: 2,
+        },
+    );
+    let mut stats: HashMap<Address, MpnWorkerStats> = [addr("ALICE"), addr("BOB")]
+        .into_iter()
+        .map(|a| (a, Default::default()))
+        .collect();
+    assert_eq!(pool.get_works(addr("ALICE"), false, 0, &mut stats).len(), 1);
+    assert_eq!(pool.get_works(addr("BOB"), false, 0, &mut stats).len(), 1);
+    assert!(pool
+        .get_works(addr("CAROL"), false, 0, &mut stats)
+        .is_empty());
+    assert!(pool.prove(0, &addr("BOB"), &ZkProof::Dummy(true), &mut stats));
+    // Solved works are not assigned anymore, and nobody is late on them
+    pool.expire(100, &mut stats);
+    assert!(stats.values().all(|s| s.late == 0));
+    assert!(pool
+        .get_works(addr("CAROL"), false, 0, &mut stats)
+        .is_empty());
+}
+
+#[test]
+fn test_reward_rules() {
+    let block_reward = Amount(1000);
+    let fees = Amount(30);
+    assert_eq!(
+        MpnReward::Fixed(Amount(7)).from_block_reward(block_reward),
+        Amount(7)
+    );
+    assert_eq!(MpnReward::Fixed(Amount(7)).from_fees(fees), Amount(0));
+    assert_eq!(
+        MpnReward::Percent(15).from_block_reward(block_reward),
+        Amount(150)
+    );
+    assert_eq!(MpnReward::Percent(15).from_fees(fees), Amount(0));
+    assert_eq!(
+        MpnReward::FeeShare(50).from_block_reward(block_reward),
+        Amount(0)
+    );
+    assert_eq!(MpnReward::FeeShare(50).from_fees(fees), Amount(15));
+    assert!(MpnReward::Percent(100).is_valid());
+    assert!(!MpnReward::FeeShare(101).is_valid());
+}
+
+#[test]
+fn test_reward_policy_fits_block_reward() {
+    let pool_config = |deposits, updates| MpnConfig {
+        mpn_num_deposit_batches: deposits,
+        mpn_num_update_batches: updates,
+        ..dummy_pool(0, Default::default()).config
+    };
+    let policy = MpnRewardPolicy {
+        deposit: MpnReward::Fixed(Amount(100)),
+        withdraw: MpnReward::FeeShare(100),
+        update: MpnReward::Percent(20),
+    };
+    assert_eq!(
+        policy.remaining_block_reward(&pool_config(2, 3), Amount(1000)),
+        Some(Amount(200))
+    );
+    assert_eq!(
+        policy.remaining_block_reward(&pool_config(2, 4), Amount(1000)),
+        Some(Amount(0))
+    );
+    assert_eq!(
+        policy.remaining_block_reward(&pool_config(2, 5), Amount(1000)),
+        None
+    );
+    assert_eq!(
+        policy.remaining_block_reward(&pool_config(usize::MAX, 0), Amount(1000)),
+        None
+    );
+}
@@ -0,0 +1,17 @@
+use super::messages::{BanRequest, BanResponse};
+use super::{NodeContext, NodeError};
+use crate::blockchain::Blockchain;
+use crate::db::KvStore;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub async fn ban<K: KvStore, B: Blockchain<K>>(
+    context: Arc<RwLock<NodeContext<K, B>>>,
+    req: BanRequest,
+) -> Result<BanResponse, NodeError> {
+    let mut context = context.write().await;
+    let now = context.local_timestamp();
+    log::warn!("Banning {} for {} seconds...", req.ip, req.secs);
+    context.peer_manager.punish_ip_for(now, req.ip, req.secs);
+    Ok(BanResponse {})
+}
@@ -0,0 +1,16 @@
+use super::messages::{FlushMempoolRequest, FlushMempoolResponse};
+use super::{NodeContext, NodeError};
+use crate::blockchain::Blockchain;
+use crate::db::KvStore;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub async fn flush_mempool<K: KvStore, B: Blockchain<K>>(
+    context: Arc<RwLock<NodeContext<K, B>>>,
+    _req: FlushMempoolRequest,
+) -> Result<FlushMempoolResponse, NodeError> {
+    let mut context = context.write().await;
+    let removed = context.mempool.clear();
+    log::info!("Removed {} transactions from the mempool!", removed);
+    Ok(FlushMempoolResponse { removed })
+}
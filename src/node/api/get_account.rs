@@ -1,7 +1,8 @@
-use super::messages::{GetAccountRequest, GetAccountResponse};
+use super::messages::{AccountProof, GetAccountRequest, GetAccountResponse};
 use super::{NodeContext, NodeError};
 use crate::blockchain::Blockchain;
-use crate::db::KvStore;
+use crate::core::Address;
+use crate::db::{keys, KvStore};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -10,12 +11,25 @@ pub async fn get_account<K: KvStore, B: Blockchain<K>>(
     req: GetAccountRequest,
 ) -> Result<GetAccountResponse, NodeError> {
     let context = context.read().await;
+    let address: Address = req.address.parse()?;
+    let mpn_contract_id = context.blockchain.config().mpn_config.mpn_contract_id;
+    let proof = if req.prove {
+        Some(AccountProof {
+            height: context.blockchain.get_tip()?.number,
+            nonce: context.blockchain.get_state_proof(keys::nonce(&address))?,
+            mpn_deposit_nonce: context
+                .blockchain
+                .get_state_proof(keys::deposit_nonce(&address, &mpn_contract_id))?,
+        })
+    } else {
+        None
+    };
     Ok(GetAccountResponse {
-        nonce: context.blockchain.get_nonce(req.address.parse()?)?,
-        mpn_deposit_nonce: context.blockchain.get_deposit_nonce(
-            req.address.parse()?,
-            context.blockchain.config().mpn_config.mpn_contract_id,
-        )?,
+        nonce: context.blockchain.get_nonce(address.clone())?,
+        mpn_deposit_nonce: context
+            .blockchain
+            .get_deposit_nonce(address, mpn_contract_id)?,
+        proof,
     })
 }
 
@@ -37,6 +51,7 @@ mod tests {
             ctx.clone(),
             GetAccountRequest {
                 address: abc_addr.to_string(),
+                prove: false,
             },
         )
         .await
@@ -45,6 +60,7 @@ mod tests {
             ctx.clone(),
             GetAccountRequest {
                 address: treasury_addr.to_string(),
+                prove: false,
             },
         )
         .await
@@ -53,20 +69,23 @@ mod tests {
             resp,
             GetAccountResponse {
                 nonce: 0,
-                mpn_deposit_nonce: 0
+                mpn_deposit_nonce: 0,
+                proof: None,
             }
         );
         assert_eq!(
             resp_treasury,
             GetAccountResponse {
                 nonce: 0,
-                mpn_deposit_nonce: 0
+                mpn_deposit_nonce: 0,
+                proof: None,
             }
         );
         let resp_invalid = get_account(
             ctx.clone(),
             GetAccountRequest {
                 address: "invalid".into(),
+                prove: false,
             },
         )
         .await;
@@ -1,8 +1,8 @@
-use super::messages::{GetBalanceRequest, GetBalanceResponse};
+use super::messages::{BalanceProof, GetBalanceRequest, GetBalanceResponse};
 use super::{NodeContext, NodeError};
 use crate::blockchain::Blockchain;
-use crate::core::ContractId;
-use crate::db::KvStore;
+use crate::core::{Address, ContractId};
+use crate::db::{keys, KvStore};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -16,12 +16,22 @@ pub async fn get_balance<K: KvStore, B: Blockchain<K>>(
         .blockchain
         .get_token(token_id)?
         .ok_or(crate::blockchain::BlockchainError::TokenNotFound)?;
+    let address: Address = req.address.parse()?;
+    let proof = if req.prove {
+        Some(BalanceProof {
+            height: context.blockchain.get_tip()?.number,
+            balance: context
+                .blockchain
+                .get_state_proof(keys::account_balance(&address, token_id))?,
+        })
+    } else {
+        None
+    };
     Ok(GetBalanceResponse {
-        balance: context
-            .blockchain
-            .get_balance(req.address.parse()?, token_id)?,
+        balance: context.blockchain.get_balance(address, token_id)?,
         name: tkn.name,
         symbol: tkn.symbol,
+        proof,
     })
 }
 
@@ -43,6 +53,7 @@ mod tests {
             GetBalanceRequest {
                 token_id: "Ziesha".into(),
                 address: abc_addr.to_string(),
+                prove: false,
             },
         )
         .await
@@ -53,10 +64,40 @@ mod tests {
                 balance: Amount(10000),
                 name: "Ziesha".into(),
                 symbol: "ZSH".into(),
+                proof: None,
             }
         );
     }
 
+    #[tokio::test]
+    async fn test_get_balance_proof() {
+        let ctx = test_context();
+        let abc_addr = TxBuilder::new(&Vec::from("ABC")).get_address();
+        let resp = get_balance(
+            ctx.clone(),
+            GetBalanceRequest {
+                token_id: "Ziesha".into(),
+                address: abc_addr.to_string(),
+                prove: true,
+            },
+        )
+        .await
+        .unwrap();
+        let proof = resp.proof.unwrap();
+        let header = ctx
+            .read()
+            .await
+            .blockchain
+            .get_header(proof.height)
+            .unwrap();
+        let key = keys::account_balance(&abc_addr, ContractId::Ziesha);
+        assert!(proof.balance.verify(
+            &header.state_root,
+            key.0.as_bytes(),
+            Some(&crate::db::Blob::from(resp.balance).0)
+        ));
+    }
+
     #[tokio::test]
     async fn test_get_balance_invalid_token() {
         let ctx = test_context();
@@ -66,6 +107,7 @@ mod tests {
             GetBalanceRequest {
                 token_id: "abcd".into(),
                 address: abc_addr.to_string(),
+                prove: false,
             },
         )
         .await;
@@ -85,6 +127,7 @@ mod tests {
             GetBalanceRequest {
                 token_id: "Ziesha".into(),
                 address: "abcd".into(),
+                prove: false,
             },
         )
         .await;
@@ -106,6 +149,7 @@ mod tests {
                 token_id: "0x0000000000000000000000000000000000000000000000000000000000000000"
                     .into(),
                 address: abc_addr.to_string(),
+                prove: false,
             },
         )
         .await;
@@ -0,0 +1,144 @@
+use super::messages::{GetContractDeltasRequest, GetContractDeltasResponse};
+use super::{NodeContext, NodeError};
+use crate::blockchain::{Blockchain, BlockchainError};
+use crate::core::{ContractId, ZkHasher};
+use crate::db::KvStore;
+use crate::zk::{KvStoreStateManager, ZkCompressedState, ZkDeltaPairs};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Locally applied deltas of a contract after `since_height`, at most `count`
+/// of them, along with the height and the compressed state they lead to
+pub fn contract_deltas<K: KvStore, B: Blockchain<K>>(
+    blockchain: &B,
+    contract_id: ContractId,
+    since_height: u64,
+    count: u64,
+) -> Result<(u64, ZkCompressedState, Vec<ZkDeltaPairs>), NodeError> {
+    let db = blockchain.database();
+    let height = KvStoreStateManager::<ZkHasher>::height_of(db, contract_id)
+        .map_err(BlockchainError::from)?;
+    let until = std::cmp::min(height, since_height.saturating_add(count));
+    let mut deltas = Vec::new();
+    let mut compressed_state =
+        KvStoreStateManager::<ZkHasher>::root(db, contract_id).map_err(BlockchainError::from)?;
+    for h in since_height.saturating_add(1)..=until {
+        let (delta, state) = KvStoreStateManager::<ZkHasher>::delta_of(db, contract_id, h)
+            .map_err(BlockchainError::from)?;
+        deltas.push(delta);
+        compressed_state = state;
+    }
+    Ok((until, compressed_state, deltas))
+}
+
+pub async fn get_contract_deltas<K: KvStore, B: Blockchain<K>>(
+    context: Arc<RwLock<NodeContext<K, B>>>,
+    req: GetContractDeltasRequest,
+) -> Result<GetContractDeltasResponse, NodeError> {
+    let context = context.read().await;
+    let contract_id: ContractId = req.contract_id.parse()?;
+    let (height, compressed_state, deltas) = contract_deltas(
+        &context.blockchain,
+        contract_id,
+        req.since_height,
+        context.opts.max_blocks_fetch,
+    )?;
+    Ok(GetContractDeltasResponse {
+        height,
+        compressed_state,
+        deltas: deltas.iter().map(|d| d.into()).collect(),
+        proof: if req.prove {
+            Some(super::prove_contract_account(
+                &context.blockchain,
+                contract_id,
+            )?)
+        } else {
+            None
+        },
+    })
+}
+
+#[cfg(test)]
+use super::tests::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk::ZkState;
+
+    #[tokio::test]
+    async fn test_get_contract_deltas() {
+        let ctx = test_context();
+        let mpn_contract_id = ctx
+            .read()
+            .await
+            .blockchain
+            .config()
+            .mpn_config
+            .mpn_contract_id;
+        let account = ctx
+            .read()
+            .await
+            .blockchain
+            .get_contract_account(mpn_contract_id)
+            .unwrap();
+        let model = ctx
+            .read()
+            .await
+            .blockchain
+            .get_contract(mpn_contract_id)
+            .unwrap()
+            .state_model;
+
+        let resp = get_contract_deltas(
+            ctx.clone(),
+            GetContractDeltasRequest {
+                contract_id: mpn_contract_id.to_string(),
+                since_height: 0,
+                prove: false,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.height, account.height);
+        assert_eq!(resp.deltas.len() as u64, account.height);
+        let mut state = ZkState::default();
+        for delta in resp.deltas.iter() {
+            state.apply_delta(&ZkDeltaPairs::try_from(delta).unwrap());
+        }
+        assert_eq!(
+            state.data.compress::<ZkHasher>(model).unwrap(),
+            resp.compressed_state
+        );
+        assert_eq!(resp.compressed_state, account.compressed_state);
+
+        let resp_synced = get_contract_deltas(
+            ctx.clone(),
+            GetContractDeltasRequest {
+                contract_id: mpn_contract_id.to_string(),
+                since_height: account.height + 5,
+                prove: false,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(resp_synced.deltas.is_empty());
+        assert_eq!(resp_synced.height, account.height);
+
+        let resp_max = get_contract_deltas(
+            ctx.clone(),
+            GetContractDeltasRequest {
+                contract_id: mpn_contract_id.to_string(),
+                since_height: u64::MAX,
+                prove: true,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(resp_max.deltas.is_empty());
+        assert_eq!(
+            resp_max.proof.unwrap().contract_account.compressed_state,
+            account.compressed_state
+        );
+    }
+}
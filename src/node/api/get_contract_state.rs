@@ -0,0 +1,116 @@
+use super::messages::{ContractAccountProof, GetContractStateRequest, GetContractStateResponse};
+use super::{NodeContext, NodeError};
+use crate::blockchain::{Blockchain, BlockchainError};
+use crate::client::CONTRACT_STATE_PAGE_SIZE;
+use crate::core::{ContractId, ZkHasher};
+use crate::db::{keys, KvStore};
+use crate::zk::{KvStoreStateManager, ZkDataLocator};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Proof of the account of a contract against the state root of the tip
+pub fn prove_contract_account<K: KvStore, B: Blockchain<K>>(
+    blockchain: &B,
+    contract_id: ContractId,
+) -> Result<ContractAccountProof, NodeError> {
+    Ok(ContractAccountProof {
+        height: blockchain.get_tip()?.number,
+        contract_account: blockchain.get_contract_account(contract_id)?,
+        proof: blockchain.get_state_proof(keys::contract_account(&contract_id))?,
+    })
+}
+
+pub async fn get_contract_state<K: KvStore, B: Blockchain<K>>(
+    context: Arc<RwLock<NodeContext<K, B>>>,
+    req: GetContractStateRequest,
+) -> Result<GetContractStateResponse, NodeError> {
+    let context = context.read().await;
+    let contract_id: ContractId = req.contract_id.parse()?;
+    let after = req
+        .after
+        .map(|loc| loc.parse::<ZkDataLocator>())
+        .transpose()?;
+    let page_size = std::cmp::min(req.page_size, CONTRACT_STATE_PAGE_SIZE);
+    let db = context.blockchain.database();
+    let state_model =
+        KvStoreStateManager::<ZkHasher>::type_of(db, contract_id).map_err(BlockchainError::from)?;
+    let (state, next) =
+        KvStoreStateManager::<ZkHasher>::get_data_pairs(db, contract_id, after.as_ref(), page_size)
+            .map_err(BlockchainError::from)?;
+    Ok(GetContractStateResponse {
+        height: KvStoreStateManager::<ZkHasher>::height_of(db, contract_id)
+            .map_err(BlockchainError::from)?,
+        compressed_state: KvStoreStateManager::<ZkHasher>::root(db, contract_id)
+            .map_err(BlockchainError::from)?,
+        state_model,
+        state: (&state).into(),
+        next: next.map(|loc| loc.to_string()),
+        proof: if req.prove {
+            Some(prove_contract_account(&context.blockchain, contract_id)?)
+        } else {
+            None
+        },
+    })
+}
+
+#[cfg(test)]
+use super::tests::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk::ZkDataPairs;
+
+    #[tokio::test]
+    async fn test_get_contract_state() {
+        let ctx = test_context();
+        let mpn_contract_id = ctx
+            .read()
+            .await
+            .blockchain
+            .config()
+            .mpn_config
+            .mpn_contract_id;
+        let resp = get_contract_state(
+            ctx.clone(),
+            GetContractStateRequest {
+                contract_id: mpn_contract_id.to_string(),
+                after: None,
+                page_size: 10,
+                prove: false,
+            },
+        )
+        .await
+        .unwrap();
+        let account = ctx
+            .read()
+            .await
+            .blockchain
+            .get_contract_account(mpn_contract_id)
+            .unwrap();
+        assert_eq!(resp.height, account.height);
+        assert_eq!(resp.compressed_state, account.compressed_state);
+        assert_eq!(
+            ZkDataPairs::try_from(&resp.state)
+                .unwrap()
+                .compress::<ZkHasher>(resp.state_model)
+                .unwrap(),
+            account.compressed_state
+        );
+
+        let resp_invalid = get_contract_state(
+            ctx.clone(),
+            GetContractStateRequest {
+                contract_id: "invalid".into(),
+                after: None,
+                page_size: 10,
+                prove: false,
+            },
+        )
+        .await;
+        assert!(matches!(
+            resp_invalid,
+            Err(NodeError::ContractIdParseError(_))
+        ));
+    }
+}
@@ -0,0 +1,72 @@
+use super::messages::{GetDeltasRequest, GetDeltasResponse};
+use super::{NodeContext, NodeError};
+use crate::blockchain::Blockchain;
+use crate::db::KvStore;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub async fn get_deltas<K: KvStore, B: Blockchain<K>>(
+    context: Arc<RwLock<NodeContext<K, B>>>,
+    req: GetDeltasRequest,
+) -> Result<GetDeltasResponse, NodeError> {
+    let context = context.read().await;
+    // Only deltas that are applied locally are served
+    let (_, _, deltas) = super::contract_deltas(
+        &context.blockchain,
+        req.contract_id,
+        req.since_height,
+        std::cmp::min(context.opts.max_blocks_fetch, req.count),
+    )?;
+    Ok(GetDeltasResponse { deltas })
+}
+
+#[cfg(test)]
+use super::tests::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_deltas() {
+        let ctx = test_context();
+        let mpn_contract_id = ctx
+            .read()
+            .await
+            .blockchain
+            .config()
+            .mpn_config
+            .mpn_contract_id;
+        let height = ctx
+            .read()
+            .await
+            .blockchain
+            .get_contract_account(mpn_contract_id)
+            .unwrap()
+            .height;
+
+        let resp = get_deltas(
+            ctx.clone(),
+            GetDeltasRequest {
+                contract_id: mpn_contract_id,
+                since_height: 0,
+                count: 10000,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.deltas.len() as u64, height);
+
+        let resp_synced = get_deltas(
+            ctx.clone(),
+            GetDeltasRequest {
+                contract_id: mpn_contract_id,
+                since_height: height,
+                count: 10,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(resp_synced.deltas.is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+use super::messages::{DepositNonceProof, GetDepositNonceRequest, GetDepositNonceResponse};
+use super::{NodeContext, NodeError};
+use crate::blockchain::Blockchain;
+use crate::core::{Address, ContractId};
+use crate::db::{keys, KvStore};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub async fn get_deposit_nonce<K: KvStore, B: Blockchain<K>>(
+    context: Arc<RwLock<NodeContext<K, B>>>,
+    req: GetDepositNonceRequest,
+) -> Result<GetDepositNonceResponse, NodeError> {
+    let context = context.read().await;
+    let address: Address = req.address.parse()?;
+    let contract_id: ContractId = req.contract_id.parse()?;
+    let proof = if req.prove {
+        Some(DepositNonceProof {
+            height: context.blockchain.get_tip()?.number,
+            nonce: context
+                .blockchain
+                .get_state_proof(keys::deposit_nonce(&address, &contract_id))?,
+        })
+    } else {
+        None
+    };
+    Ok(GetDepositNonceResponse {
+        nonce: context.blockchain.get_deposit_nonce(address, contract_id)?,
+        proof,
+    })
+}
+
+#[cfg(test)]
+use super::tests::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::TxBuilder;
+
+    #[tokio::test]
+    async fn test_get_deposit_nonce() {
+        let ctx = test_context();
+        let abc_addr = TxBuilder::new(&Vec::from("ABC")).get_address();
+        let mpn_contract_id = ctx
+            .read()
+            .await
+            .blockchain
+            .config()
+            .mpn_config
+            .mpn_contract_id;
+        let resp = get_deposit_nonce(
+            ctx.clone(),
+            GetDepositNonceRequest {
+                address: abc_addr.to_string(),
+                contract_id: mpn_contract_id.to_string(),
+                prove: false,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            resp,
+            GetDepositNonceResponse {
+                nonce: 0,
+                proof: None,
+            }
+        );
+
+        let resp_invalid = get_deposit_nonce(
+            ctx.clone(),
+            GetDepositNonceRequest {
+                address: abc_addr.to_string(),
+                contract_id: "invalid".into(),
+                prove: false,
+            },
+        )
+        .await;
+        assert!(matches!(
+            resp_invalid,
+            Err(NodeError::ContractIdParseError(_))
+        ));
+    }
+}
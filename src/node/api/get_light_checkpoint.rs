@@ -0,0 +1,17 @@
+use super::messages::{GetLightCheckpointRequest, GetLightCheckpointResponse};
+use super::{NodeContext, NodeError};
+use crate::blockchain::Blockchain;
+use crate::client::light::LightCheckpoint;
+use crate::db::KvStore;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub async fn get_light_checkpoint<K: KvStore, B: Blockchain<K>>(
+    context: Arc<RwLock<NodeContext<K, B>>>,
+    _req: GetLightCheckpointRequest,
+) -> Result<GetLightCheckpointResponse, NodeError> {
+    let context = context.read().await;
+    Ok(GetLightCheckpointResponse {
+        checkpoint: LightCheckpoint::new(&context.blockchain)?,
+    })
+}
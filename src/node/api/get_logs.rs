@@ -2,14 +2,20 @@ use super::messages::{GetLogsRequest, GetLogsResponse};
 use super::{NodeContext, NodeError};
 use crate::blockchain::Blockchain;
 use crate::db::KvStore;
+use crate::logs::{self, LogFilter};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 pub async fn get_logs<K: KvStore, B: Blockchain<K>>(
     _context: Arc<RwLock<NodeContext<K, B>>>,
-    _req: GetLogsRequest,
+    req: GetLogsRequest,
 ) -> Result<GetLogsResponse, NodeError> {
+    let filter = LogFilter {
+        level: req.level.map(|l| l.parse()).transpose()?,
+        module: req.module,
+        since: req.since,
+    };
     Ok(GetLogsResponse {
-        logs: crate::GLOBAL_LOGS.lock().unwrap().clone(),
+        logs: logs::query(&filter),
     })
 }
@@ -0,0 +1,142 @@
+use super::messages::GetMetricsRequest;
+use super::{NodeContext, NodeError};
+use crate::blockchain::Blockchain;
+use crate::db::KvStore;
+use crate::node::metrics::{MetricKind, MetricsPage};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub async fn get_metrics<K: KvStore, B: Blockchain<K>>(
+    context: Arc<RwLock<NodeContext<K, B>>>,
+    _req: GetMetricsRequest,
+) -> Result<String, NodeError> {
+    let context = context.read().await;
+    let now = context.local_timestamp();
+    let mut page = MetricsPage::default();
+
+    page.add(
+        "chain_height",
+        MetricKind::Gauge,
+        "Number of blocks in the local chain",
+        context.blockchain.get_height()? as f64,
+    );
+    page.add(
+        "chain_power",
+        MetricKind::Gauge,
+        "Power of the local chain",
+        context.blockchain.get_power()?,
+    );
+    page.add_labeled(
+        "peers",
+        MetricKind::Gauge,
+        "Number of known peers by state",
+        "state",
+        &[
+            ("peer", context.peer_manager.get_peers().len() as f64),
+            ("node", context.peer_manager.node_count() as f64),
+            ("candidate", context.peer_manager.candidate_count() as f64),
+            ("punished", context.peer_manager.punished_count(now) as f64),
+        ],
+    );
+    page.add(
+        "orphan_blocks",
+        MetricKind::Gauge,
+        "Number of blocks kept in the orphan pool",
+        context.orphan_pool.len() as f64,
+    );
+
+    let mut mempool_sizes = context
+        .mempool
+        .len_by_kind()
+        .into_iter()
+        .map(|(k, v)| (format!("{:?}", k), v as f64))
+        .collect::<Vec<_>>();
+    mempool_sizes.sort_by(|a, b| a.0.cmp(&b.0));
+    page.add_labeled(
+        "mempool_size",
+        MetricKind::Gauge,
+        "Number of transactions in the mempool by kind",
+        "kind",
+        &mempool_sizes,
+    );
+
+    let (solved, total) = context
+        .mpn_work_pool
+        .as_ref()
+        .map(|p| p.progress())
+        .unwrap_or_default();
+    page.add_labeled(
+        "mpn_works",
+        MetricKind::Gauge,
+        "Number of works in the current MPN work-pool",
+        "state",
+        &[("solved", solved as f64), ("total", total as f64)],
+    );
+
+    let metrics = &context.metrics;
+    page.add(
+        "mpn_late_solutions_total",
+        MetricKind::Counter,
+        "MPN solutions received when there was no work-pool",
+        metrics.mpn_late_solutions as f64,
+    );
+    page.add(
+        "punishments_total",
+        MetricKind::Counter,
+        "Number of times a peer has been punished",
+        metrics.punishments as f64,
+    );
+    if let Some(firewall) = &context.firewall {
+        let stats = firewall.stats();
+        page.add(
+            "firewall_permitted_total",
+            MetricKind::Counter,
+            "Number of requests permitted by the firewall",
+            stats.permitted as f64,
+        );
+        page.add_labeled(
+            "firewall_drops_total",
+            MetricKind::Counter,
+            "Number of requests dropped by the firewall by reason",
+            "reason",
+            &[
+                ("denylist", stats.dropped_denylist as f64),
+                ("request_limit", stats.dropped_request_limit as f64),
+                ("traffic_limit", stats.dropped_traffic_limit as f64),
+            ],
+        );
+        let mut route_drops = stats
+            .dropped_route_limit
+            .iter()
+            .map(|(route, cnt)| (route.clone(), *cnt as f64))
+            .collect::<Vec<_>>();
+        route_drops.sort_by(|a, b| a.0.cmp(&b.0));
+        page.add_labeled(
+            "firewall_route_drops_total",
+            MetricKind::Counter,
+            "Number of requests dropped by the route budgets of the firewall",
+            "route",
+            &route_drops,
+        );
+    }
+    page.add(
+        "sync_failures_total",
+        MetricKind::Counter,
+        "Number of failed block syncs with peers",
+        metrics.sync_failures as f64,
+    );
+    page.add(
+        "block_apply_seconds_sum",
+        MetricKind::Counter,
+        "Total time spent on applying blocks",
+        metrics.block_apply_seconds(),
+    );
+    page.add(
+        "block_apply_seconds_count",
+        MetricKind::Counter,
+        "Number of applied blocks",
+        metrics.block_apply_count() as f64,
+    );
+
+    Ok(page.render())
+}
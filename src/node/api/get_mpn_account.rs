@@ -1,7 +1,9 @@
-use super::messages::{GetMpnAccountRequest, GetMpnAccountResponse};
+use super::messages::{GetMpnAccountRequest, GetMpnAccountResponse, MpnAccountProof};
 use super::{NodeContext, NodeError};
-use crate::blockchain::Blockchain;
-use crate::db::KvStore;
+use crate::blockchain::{Blockchain, BlockchainError};
+use crate::core::{MpnAddress, ZkHasher};
+use crate::db::{keys, KvStore};
+use crate::zk;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -10,7 +12,76 @@ pub async fn get_mpn_account<K: KvStore, B: Blockchain<K>>(
     req: GetMpnAccountRequest,
 ) -> Result<GetMpnAccountResponse, NodeError> {
     let context = context.read().await;
-    Ok(GetMpnAccountResponse {
-        account: context.blockchain.get_mpn_account(req.address.parse()?)?,
-    })
+    let address: MpnAddress = req.address.parse()?;
+    let account = context.blockchain.get_mpn_account(address.clone())?;
+    let index = context
+        .blockchain
+        .get_mpn_account_indices(address)?
+        .first()
+        .cloned();
+    let proof = if let (true, Some(index)) = (req.prove, index) {
+        let mpn_contract_id = context.blockchain.config().mpn_config.mpn_contract_id;
+        let db = context.blockchain.database();
+        let mut token_proofs = std::collections::HashMap::new();
+        for token_index in account.tokens.keys() {
+            token_proofs.insert(
+                *token_index,
+                zk::KvStoreStateManager::<ZkHasher>::prove(
+                    db,
+                    mpn_contract_id,
+                    zk::ZkDataLocator(vec![index, 4]),
+                    *token_index,
+                )
+                .map_err(BlockchainError::from)?,
+            );
+        }
+        let account_proof = zk::MpnAccountProof {
+            index,
+            proof: zk::KvStoreStateManager::<ZkHasher>::prove(
+                db,
+                mpn_contract_id,
+                zk::ZkDataLocator(vec![]),
+                index,
+            )
+            .map_err(BlockchainError::from)?,
+            token_proofs,
+        };
+        Some(MpnAccountProof {
+            height: context.blockchain.get_tip()?.number,
+            contract_account: context.blockchain.get_contract_account(mpn_contract_id)?,
+            contract_account_proof: context
+                .blockchain
+                .get_state_proof(keys::contract_account(&mpn_contract_id))?,
+            account: account_proof,
+        })
+    } else {
+        None
+    };
+    Ok(GetMpnAccountResponse { account, proof })
+}
+
+#[cfg(test)]
+use super::tests::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::TxBuilder;
+
+    #[tokio::test]
+    async fn test_absent_mpn_account_is_not_proven() {
+        let ctx = test_context();
+        let abc = TxBuilder::new(&Vec::from("ABC"));
+        let resp = get_mpn_account(
+            ctx.clone(),
+            GetMpnAccountRequest {
+                address: abc.get_mpn_address().to_string(),
+                prove: true,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.account, Default::default());
+        assert!(resp.proof.is_none());
+    }
 }
@@ -1,22 +1,94 @@
 use super::messages::{GetMpnWorkRequest, GetMpnWorkResponse};
 use super::{NodeContext, NodeError};
 use crate::blockchain::Blockchain;
+use crate::core::Address;
 use crate::db::KvStore;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 pub async fn get_mpn_work<K: KvStore, B: Blockchain<K>>(
+    signer: Option<Address>,
     context: Arc<RwLock<NodeContext<K, B>>>,
     req: GetMpnWorkRequest,
 ) -> Result<GetMpnWorkResponse, NodeError> {
+    if signer.as_ref() != Some(&req.address) {
+        return Err(NodeError::SignatureRequired);
+    }
     let mut ctx = context.write().await;
+    if !ctx.mpn_workers.use_nonce(&req.address, req.nonce) {
+        return Err(NodeError::ReplayedRequest);
+    }
+    let now = ctx.local_timestamp();
+    let registered = ctx.mpn_workers.is_registered(&req.address);
+    let ctx = &mut *ctx;
     let works = ctx
         .mpn_work_pool
         .as_mut()
-        .map(|p| p.get_works(req.address.clone()))
+        .map(|p| {
+            p.get_works(
+                req.address.clone(),
+                registered,
+                now,
+                ctx.mpn_workers.stats_mut(),
+            )
+        })
         .unwrap_or_default();
     if !works.is_empty() {
         println!("Sending {} works to {}", works.len(), req.address);
     }
     Ok(GetMpnWorkResponse { works })
 }
+
+#[cfg(test)]
+use super::tests::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::TxBuilder;
+
+    #[tokio::test]
+    async fn test_get_mpn_work_needs_worker_signature() {
+        let ctx = test_context();
+        let worker = TxBuilder::new(&Vec::from("WORKER")).get_address();
+        let other = TxBuilder::new(&Vec::from("OTHER")).get_address();
+        for signer in [None, Some(other)] {
+            assert!(matches!(
+                get_mpn_work(
+                    signer,
+                    ctx.clone(),
+                    GetMpnWorkRequest {
+                        address: worker.clone(),
+                        nonce: 1,
+                    }
+                )
+                .await,
+                Err(NodeError::SignatureRequired)
+            ));
+        }
+        let resp = get_mpn_work(
+            Some(worker.clone()),
+            ctx.clone(),
+            GetMpnWorkRequest {
+                address: worker.clone(),
+                nonce: 1,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(resp.works.is_empty());
+        // The same request cannot be replayed
+        assert!(matches!(
+            get_mpn_work(
+                Some(worker.clone()),
+                ctx.clone(),
+                GetMpnWorkRequest {
+                    address: worker,
+                    nonce: 1,
+                }
+            )
+            .await,
+            Err(NodeError::ReplayedRequest)
+        ));
+    }
+}
@@ -0,0 +1,31 @@
+use super::messages::{GetMpnWorkersRequest, GetMpnWorkersResponse, MpnWorkerInfo};
+use super::{NodeContext, NodeError};
+use crate::blockchain::Blockchain;
+use crate::db::KvStore;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub async fn get_mpn_workers<K: KvStore, B: Blockchain<K>>(
+    context: Arc<RwLock<NodeContext<K, B>>>,
+    _req: GetMpnWorkersRequest,
+) -> Result<GetMpnWorkersResponse, NodeError> {
+    let context = context.read().await;
+    let registry = &context.mpn_workers;
+    Ok(GetMpnWorkersResponse {
+        workers: registry
+            .workers()
+            .keys()
+            .map(|address| {
+                let stats = registry.stats().get(address).cloned().unwrap_or_default();
+                MpnWorkerInfo {
+                    address: address.to_string(),
+                    registered: registry.is_registered(address),
+                    assigned: stats.assigned,
+                    accepted: stats.solved,
+                    rewards: stats.rewards,
+                    late: stats.late,
+                }
+            })
+            .collect(),
+    })
+}
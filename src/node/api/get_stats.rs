@@ -1,6 +1,7 @@
 use super::messages::{GetStatsRequest, GetStatsResponse};
 use super::{NodeContext, NodeError};
 use crate::blockchain::Blockchain;
+use crate::client::PROTOCOL_VERSION;
 use crate::db::KvStore;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -25,8 +26,10 @@ pub async fn get_stats<K: KvStore, B: Blockchain<K>>(
         epoch,
         slot,
         version: env!("CARGO_PKG_VERSION").into(),
+        protocol_version: PROTOCOL_VERSION,
         network: context.network.clone(),
         validator_claim: context.validator_claim.clone(),
         median_fees: context.mempool.median_fees(),
+        mpn_rewards: context.opts.mpn_rewards,
     })
 }
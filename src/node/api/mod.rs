@@ -16,6 +16,8 @@ mod get_explorer_blocks;
 pub use get_explorer_blocks::*;
 mod get_headers;
 pub use get_headers::*;
+mod get_light_checkpoint;
+pub use get_light_checkpoint::*;
 mod transact;
 pub use transact::*;
 mod shutdown;
@@ -34,12 +36,22 @@ mod get_balance;
 pub use get_balance::*;
 mod get_token;
 pub use get_token::*;
+mod get_contract_state;
+pub use get_contract_state::*;
+mod get_contract_deltas;
+pub use get_contract_deltas::*;
+mod get_deltas;
+pub use get_deltas::*;
+mod get_deposit_nonce;
+pub use get_deposit_nonce::*;
 mod post_validator_claim;
 pub use post_validator_claim::*;
 mod get_explorer_stakers;
 pub use get_explorer_stakers::*;
 mod get_mpn_work;
 pub use get_mpn_work::*;
+mod get_mpn_workers;
+pub use get_mpn_workers::*;
 mod post_mpn_solution;
 pub use post_mpn_solution::*;
 mod get_delegations;
@@ -52,6 +64,18 @@ mod get_check_tx;
 pub use get_check_tx::*;
 mod get_logs;
 pub use get_logs::*;
+mod get_metrics;
+pub use get_metrics::*;
+mod post_log_level;
+pub use post_log_level::*;
+mod remove_mpn_worker;
+pub use remove_mpn_worker::*;
+mod ban;
+pub use ban::*;
+mod unban;
+pub use unban::*;
+mod flush_mempool;
+pub use flush_mempool::*;
 #[cfg(test)]
 mod generate_block;
 #[cfg(test)]
@@ -65,12 +89,13 @@ mod tests {
     use crate::client::messages::SocialProfiles;
     use crate::client::NodeRequest;
     use crate::client::OutgoingSender;
-    use crate::core::Amount;
     use crate::db::RamKvStore;
-    use crate::node::local_timestamp;
     use crate::node::Mempool;
+    use crate::node::Metrics;
+    use crate::node::OrphanPool;
     use crate::node::PeerManager;
     use crate::node::TxBuilder;
+    use crate::utils::{Clock, MockClock};
     use std::sync::Arc;
     use tokio::sync::mpsc;
     use tokio::sync::RwLock;
@@ -94,8 +119,10 @@ mod tests {
                 .unwrap();
             blockchain.extend((i + 1) as u64, &[block]).unwrap();
         }
+        let clock = MockClock::new(crate::utils::local_timestamp());
         Arc::new(RwLock::new(NodeContext {
             _phantom: std::marker::PhantomData,
+            clock: Arc::new(clock.clone()),
             firewall: None,
             opts: opts.clone(),
             network: network.clone(),
@@ -104,19 +131,30 @@ mod tests {
             shutdown: false,
             outgoing: Arc::new(OutgoingSender {
                 network: network.clone(),
+                admin_token: None,
                 chan: out_send,
                 priv_key: user_wallet.get_priv_key(),
             }),
             mpn_workers: Default::default(),
             mpn_work_pool: None,
-            mempool: Mempool::new(Amount(1_000_000_000)),
+            mempool: Mempool::new(
+                Arc::new(clock.clone()),
+                opts.mempool_min_balance_per_tx,
+                opts.mempool_max_txs_per_sender,
+            ),
+            orphan_pool: OrphanPool::new(
+                opts.max_orphan_blocks,
+                opts.max_orphan_blocks_per_peer,
+                opts.max_orphan_block_age,
+            ),
+            metrics: Metrics::default(),
             blockchain,
             validator_wallet: validator_wallet.clone(),
             user_wallet: user_wallet.clone(),
             peer_manager: PeerManager::new(
                 None,
                 Default::default(),
-                local_timestamp(),
+                clock.now(),
                 opts.candidate_remove_threshold,
             ),
             timestamp_offset: 0,
@@ -2,15 +2,20 @@ use super::messages::{PostBlockRequest, PostBlockResponse};
 use super::{promote_block, NodeContext, NodeError};
 use crate::blockchain::Blockchain;
 use crate::db::KvStore;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::RwLock;
 
 pub async fn post_block<K: KvStore, B: Blockchain<K>>(
+    client: Option<SocketAddr>,
     context: Arc<RwLock<NodeContext<K, B>>>,
     req: PostBlockRequest,
 ) -> Result<PostBlockResponse, NodeError> {
     let mut ctx = context.write().await;
-    if req.block.header.number == ctx.blockchain.get_height()? {
+    let height = ctx.blockchain.get_height()?;
+    let number = req.block.header.number;
+    if number >= height && number < height + ctx.opts.max_blocks_fetch {
         // Only accept new blocks if validator doesn't have ongoing work!
         if ctx.mpn_work_pool.is_none() {
             if req
@@ -23,14 +28,74 @@ pub async fn post_block<K: KvStore, B: Blockchain<K>>(
             {
                 return Err(NodeError::BlockTimestampInFuture);
             }
-            ctx.blockchain
-                .extend(req.block.header.number, &[req.block.clone()])?;
-            ctx.on_update()?;
-            drop(ctx);
-            promote_block(context, req.block).await;
+            if number == height {
+                let timer = Instant::now();
+                ctx.blockchain
+                    .extend(number, std::slice::from_ref(&req.block))?;
+                ctx.metrics.observe_block_apply(timer.elapsed(), 1);
+                ctx.account_mpn_rewards(std::slice::from_ref(&req.block), false);
+                ctx.on_update()?;
+                let mut applied = vec![req.block];
+                applied.extend(ctx.process_orphans()?);
+                drop(ctx);
+                for block in applied {
+                    promote_block(Arc::clone(&context), block).await;
+                }
+            } else {
+                ctx.validate_orphan(&req.block)?;
+                let now = ctx.local_timestamp();
+                if ctx
+                    .orphan_pool
+                    .insert(now, client.map(|c| c.ip()), req.block)
+                {
+                    log::info!("Block {} kept as an orphan!", number);
+                }
+            }
         } else {
             log::info!("Syncing ignored! Validator is already producing a block!");
         }
     }
     Ok(PostBlockResponse {})
 }
+
+#[cfg(test)]
+use super::tests::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::TxBuilder;
+
+    #[tokio::test]
+    async fn test_invalid_orphans_are_not_kept() {
+        let validator = TxBuilder::new(&Vec::from("VALIDATOR"));
+        let ctx = test_context();
+        let orphan = {
+            let ctx = ctx.read().await;
+            let mut fork = ctx.blockchain.fork_on_ram();
+            let height = fork.get_height().unwrap();
+            let parent = fork
+                .draft_block(height as u32 * 60 + 30, &[], &validator, true)
+                .unwrap()
+                .unwrap();
+            fork.extend(height, &[parent]).unwrap();
+            fork.draft_block(height as u32 * 60 + 90, &[], &validator, true)
+                .unwrap()
+                .unwrap()
+        };
+
+        let mut junk = orphan.clone();
+        junk.header.block_root = Default::default();
+        assert!(
+            post_block(None, ctx.clone(), PostBlockRequest { block: junk })
+                .await
+                .is_err()
+        );
+        assert_eq!(ctx.read().await.orphan_pool.len(), 0);
+
+        post_block(None, ctx.clone(), PostBlockRequest { block: orphan })
+            .await
+            .unwrap();
+        assert_eq!(ctx.read().await.orphan_pool.len(), 1);
+    }
+}
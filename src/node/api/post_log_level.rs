@@ -0,0 +1,18 @@
+use super::messages::{PostLogLevelRequest, PostLogLevelResponse};
+use super::{NodeContext, NodeError};
+use crate::blockchain::Blockchain;
+use crate::db::KvStore;
+use crate::logs;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub async fn post_log_level<K: KvStore, B: Blockchain<K>>(
+    _context: Arc<RwLock<NodeContext<K, B>>>,
+    req: PostLogLevelRequest,
+) -> Result<PostLogLevelResponse, NodeError> {
+    logs::set_level(req.level.parse()?);
+    log::info!("Log level changed to {}!", logs::level());
+    Ok(PostLogLevelResponse {
+        level: logs::level().to_string(),
+    })
+}
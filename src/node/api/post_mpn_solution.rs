@@ -1,25 +1,38 @@
 use super::messages::{PostMpnSolutionRequest, PostMpnSolutionResponse};
 use super::{NodeContext, NodeError};
 use crate::blockchain::Blockchain;
+use crate::core::Address;
 use crate::db::KvStore;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 pub async fn post_mpn_solution<K: KvStore, B: Blockchain<K>>(
+    signer: Option<Address>,
     context: Arc<RwLock<NodeContext<K, B>>>,
     req: PostMpnSolutionRequest,
 ) -> Result<PostMpnSolutionResponse, NodeError> {
+    if signer.as_ref() != Some(&req.prover) {
+        return Err(NodeError::SignatureRequired);
+    }
     let mut ctx = context.write().await;
+    if !ctx.mpn_workers.use_nonce(&req.prover, req.nonce) {
+        return Err(NodeError::ReplayedRequest);
+    }
+    let ctx = &mut *ctx;
     if let Some(mpn_work_pool) = &mut ctx.mpn_work_pool {
         let mut accepted = 0;
         for (id, proof) in req.proofs.iter() {
-            if mpn_work_pool.prove(*id, &req.prover, proof) {
+            if mpn_work_pool.prove(*id, &req.prover, proof, ctx.mpn_workers.stats_mut()) {
                 accepted += 1;
             }
         }
+        if accepted > 0 {
+            ctx.mpn_workers.save()?;
+        }
         println!("Got {} accepted SNARK proofs!", accepted);
         Ok(PostMpnSolutionResponse { accepted })
     } else {
+        ctx.metrics.mpn_late_solutions += req.proofs.len() as u64;
         Ok(PostMpnSolutionResponse { accepted: 0 })
     }
 }
@@ -1,8 +1,7 @@
-use super::messages::{HandshakeRequest, HandshakeResponse};
+use super::messages::{HandshakeRequest, HandshakeResponse, ProtocolInfo};
 use super::{NodeContext, NodeError};
 use crate::blockchain::Blockchain;
 use crate::db::KvStore;
-use crate::utils;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -21,9 +20,8 @@ pub async fn post_peer<K: KvStore, B: Blockchain<K>>(
                 return Err(NodeError::HandshakeClientMismatch);
             }
         }
-        context
-            .peer_manager
-            .add_candidate(utils::local_timestamp(), peer);
+        let now = context.local_timestamp();
+        context.peer_manager.add_candidate(now, peer);
     }
 
     Ok(HandshakeResponse {
@@ -31,5 +29,6 @@ pub async fn post_peer<K: KvStore, B: Blockchain<K>>(
         timestamp: context.network_timestamp(),
         timestamp_offset: context.timestamp_offset,
         validator_claim: context.validator_claim.clone(),
+        protocol: ProtocolInfo::current(),
     })
 }
@@ -0,0 +1,16 @@
+use super::messages::{RemoveMpnWorkerRequest, RemoveMpnWorkerResponse};
+use super::{NodeContext, NodeError};
+use crate::blockchain::Blockchain;
+use crate::db::KvStore;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub async fn remove_mpn_worker<K: KvStore, B: Blockchain<K>>(
+    context: Arc<RwLock<NodeContext<K, B>>>,
+    req: RemoveMpnWorkerRequest,
+) -> Result<RemoveMpnWorkerResponse, NodeError> {
+    let mut context = context.write().await;
+    Ok(RemoveMpnWorkerResponse {
+        removed: context.mpn_workers.remove(&req.address)?,
+    })
+}
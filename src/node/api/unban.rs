@@ -0,0 +1,16 @@
+use super::messages::{UnbanRequest, UnbanResponse};
+use super::{NodeContext, NodeError};
+use crate::blockchain::Blockchain;
+use crate::db::KvStore;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub async fn unban<K: KvStore, B: Blockchain<K>>(
+    context: Arc<RwLock<NodeContext<K, B>>>,
+    req: UnbanRequest,
+) -> Result<UnbanResponse, NodeError> {
+    let mut context = context.write().await;
+    Ok(UnbanResponse {
+        was_banned: context.peer_manager.unpunish_ip(req.ip),
+    })
+}
@@ -1,15 +1,22 @@
-use super::{Firewall, NodeError, NodeOptions, OutgoingSender, Peer, PeerAddress, PeerManager};
+use super::{
+    Firewall, Metrics, MpnWorkerRegistry, NodeError, NodeOptions, OrphanPool, OutgoingSender, Peer,
+    PeerAddress, PeerManager,
+};
 use crate::blockchain::{Blockchain, BlockchainError, Mempool, TransactionMetadata};
 use crate::client::messages::{SocialProfiles, ValidatorClaim};
-use crate::core::{Address, Block, GeneralTransaction, TransactionAndDelta};
-use crate::mpn::{MpnWorkPool, MpnWorker};
+use crate::core::{
+    Block, ContractUpdate, ContractUpdateData, GeneralTransaction, TransactionAndDelta,
+    TransactionData,
+};
+use crate::mpn::{MpnAssignmentPolicy, MpnWorkPool};
 use crate::node::KvStore;
-use crate::utils;
+use crate::utils::Clock;
 use crate::wallet::TxBuilder;
-use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 
 pub struct NodeContext<K: KvStore, B: Blockchain<K>> {
+    pub clock: Arc<dyn Clock>,
     pub firewall: Option<Firewall>,
     pub social_profiles: SocialProfiles,
     pub opts: NodeOptions,
@@ -24,16 +31,25 @@ pub struct NodeContext<K: KvStore, B: Blockchain<K>> {
     pub timestamp_offset: i32,
     pub validator_claim: Option<ValidatorClaim>,
 
-    pub mpn_workers: HashMap<Address, MpnWorker>,
+    pub mpn_workers: MpnWorkerRegistry,
     pub mpn_work_pool: Option<MpnWorkPool>,
 
     pub mempool: Mempool,
+    pub orphan_pool: OrphanPool,
+    pub metrics: Metrics,
     pub _phantom: std::marker::PhantomData<K>,
 }
 
 impl<K: KvStore, B: Blockchain<K>> NodeContext<K, B> {
     pub fn local_timestamp(&self) -> u32 {
-        utils::local_timestamp()
+        self.clock.now()
+    }
+    pub fn mpn_assignment_policy(&self) -> MpnAssignmentPolicy {
+        MpnAssignmentPolicy {
+            deadline: self.opts.mpn_work_deadline,
+            redundancy: self.opts.mpn_work_redundancy,
+            works_per_worker: self.opts.mpn_works_per_worker,
+        }
     }
     pub fn network_timestamp(&self) -> u32 {
         (self.local_timestamp() as i32 + self.timestamp_offset) as u32
@@ -43,6 +59,7 @@ impl<K: KvStore, B: Blockchain<K>> NodeContext<K, B> {
         log::warn!("Punishing {} for {} seconds...", bad_peer, secs);
         self.peer_manager
             .punish_ip_for(self.local_timestamp(), bad_peer.ip(), secs);
+        self.metrics.punishments += 1;
     }
     pub fn punish_unresponsive(&mut self, bad_peer: PeerAddress) {
         log::warn!("Peer {} is unresponsive!", bad_peer);
@@ -69,6 +86,9 @@ impl<K: KvStore, B: Blockchain<K>> NodeContext<K, B> {
             firewall.refresh(local_ts);
         }
 
+        let height = self.blockchain.get_height()?;
+        self.orphan_pool.prune(local_ts, height);
+
         Ok(())
     }
 
@@ -78,18 +98,161 @@ impl<K: KvStore, B: Blockchain<K>> NodeContext<K, B> {
         tx: GeneralTransaction,
         meta: Option<TransactionMetadata>,
     ) -> Result<(), BlockchainError> {
-        let local_ts = self.local_timestamp();
-        self.mempool
-            .add_tx(&self.blockchain, tx, is_local, local_ts, meta)?;
+        self.mempool.add_tx(&self.blockchain, tx, is_local, meta)?;
+        Ok(())
+    }
+
+    /// Keeps the rewards of the MPN workers in line with the MPN updates of
+    /// this node which are in the chain
+    pub fn account_mpn_rewards(&mut self, blocks: &[Block], rolled_back: bool) {
+        let mpn_contract_id = self.blockchain.config().mpn_config.mpn_contract_id;
+        self.mpn_workers.account_rewards(
+            &mpn_contract_id,
+            &self.validator_wallet.get_address(),
+            blocks,
+            rolled_back,
+        );
+    }
+
+    /// Offers the user transactions of rolled back blocks to the mempool again.
+    /// The chain has already changed at this point, so transactions which
+    /// can't be added are skipped instead of failing the whole sync.
+    pub fn reinject_rolled_back(&mut self, blocks: &[Block]) {
+        self.account_mpn_rewards(blocks, true);
+        let mpn_contract_id = self.blockchain.config().mpn_config.mpn_contract_id;
+        for tx in blocks.iter().flat_map(|b| b.body.iter()) {
+            let state_delta = match &tx.data {
+                TransactionData::UpdateContract {
+                    contract_id,
+                    delta,
+                    updates,
+                } => {
+                    // MPN updates are rebuilt by the validators, but the user
+                    // deposits/withdraws within them have to be re-injected
+                    if *contract_id == mpn_contract_id {
+                        self.reinject_mpn_payments(updates);
+                        continue;
+                    }
+                    delta.clone()
+                }
+                TransactionData::CreateContract { state, .. } => {
+                    state.as_ref().map(|s| s.as_delta())
+                }
+                _ => None,
+            };
+            if tx.src.is_none() {
+                continue;
+            }
+            self.reinject(GeneralTransaction::TransactionAndDelta(
+                TransactionAndDelta {
+                    tx: tx.clone(),
+                    state_delta,
+                },
+            ));
+        }
+    }
+
+    fn reinject(&mut self, tx: GeneralTransaction) {
+        if let Err(e) = self.mempool_add_tx(false, tx, None) {
+            log::warn!("Cannot re-inject a rolled back transaction. Error: {}", e);
+        }
+    }
+
+    fn reinject_mpn_payments(&mut self, updates: &[ContractUpdate]) {
+        for update in updates.iter() {
+            let txs: Vec<GeneralTransaction> = match &update.data {
+                ContractUpdateData::Deposit { deposits } => deposits
+                    .iter()
+                    .filter_map(|d| self.mempool.find_mpn_deposit(d))
+                    .map(GeneralTransaction::MpnDeposit)
+                    .collect(),
+                ContractUpdateData::Withdraw { withdraws } => withdraws
+                    .iter()
+                    .filter_map(|w| self.mempool.find_mpn_withdraw(w))
+                    .map(GeneralTransaction::MpnWithdraw)
+                    .collect(),
+                _ => Vec::new(),
+            };
+            for tx in txs {
+                self.reinject(tx);
+            }
+        }
+    }
+
+    /// Orphans can't be fully validated before their parents arrive, but their
+    /// bodies should match their headers, and their validators should be
+    /// known stakers (Elected ones, if they are in the epoch of our tip)
+    pub fn validate_orphan(&self, block: &Block) -> Result<(), BlockchainError> {
+        if block.merkle_tree().root() != block.header.block_root {
+            return Err(BlockchainError::InvalidMerkleRoot);
+        }
+        if self.blockchain.config().check_validator {
+            let pos = &block.header.proof_of_stake;
+            let proof = pos
+                .proof
+                .clone()
+                .ok_or(BlockchainError::ValidatorProofNotGiven)?;
+            let tip_epoch = self
+                .blockchain
+                .epoch_slot(self.blockchain.get_tip()?.proof_of_stake.timestamp)
+                .0;
+            if self.blockchain.epoch_slot(pos.timestamp).0 == tip_epoch {
+                if !self
+                    .blockchain
+                    .is_validator(pos.timestamp, pos.validator.clone(), proof)?
+                {
+                    return Err(BlockchainError::UnelectedValidator);
+                }
+            } else if self.blockchain.get_staker(pos.validator.clone())?.is_none() {
+                return Err(BlockchainError::ValidatorNotRegistered);
+            }
+        }
         Ok(())
     }
 
+    /// Applies the orphan blocks which their parents are now in the chain
+    pub fn process_orphans(&mut self) -> Result<Vec<Block>, BlockchainError> {
+        let mut applied = Vec::new();
+        loop {
+            let height = self.blockchain.get_height()?;
+            let tip_hash = self.blockchain.get_tip()?.hash();
+            let mut extended = false;
+            for orphan in self.orphan_pool.take_children(&tip_hash) {
+                if orphan.header.number != height {
+                    continue;
+                }
+                let timer = Instant::now();
+                match self
+                    .blockchain
+                    .extend(height, std::slice::from_ref(&orphan))
+                {
+                    Ok(_) => {
+                        self.metrics.observe_block_apply(timer.elapsed(), 1);
+                        log::info!("Orphan block {} applied!", height);
+                        self.account_mpn_rewards(std::slice::from_ref(&orphan), false);
+                        applied.push(orphan);
+                        extended = true;
+                        break;
+                    }
+                    Err(e) => {
+                        log::warn!("Cannot apply orphan block. Error: {}", e);
+                    }
+                }
+            }
+            if !extended {
+                break;
+            }
+        }
+        if !applied.is_empty() {
+            self.on_update()?;
+        }
+        Ok(applied)
+    }
+
     /// Is called whenever chain is extended or rolled back
     pub fn on_update(&mut self) -> Result<(), BlockchainError> {
-        let local_ts = self.local_timestamp();
         self.mempool.refresh(
             &self.blockchain,
-            local_ts,
             self.opts.tx_max_time_alive,
             self.opts.tx_max_time_alive,
         )?;
@@ -139,7 +302,8 @@ impl<K: KvStore, B: Blockchain<K>> NodeContext<K, B> {
             Ok(draft) => {
                 if let Some(draft) = draft {
                     self.blockchain
-                        .extend(draft.header.number, &[draft.clone()])?;
+                        .extend(draft.header.number, std::slice::from_ref(&draft))?;
+                    self.account_mpn_rewards(std::slice::from_ref(&draft), false);
                     self.on_update()?;
                     Ok(Some(draft))
                 } else {
@@ -1,56 +1,272 @@
 use super::*;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ParseCidrError {
+    #[error("cidr invalid")]
+    Invalid,
+}
+
+/// A range of ip addresses, e.g. `10.0.0.0/8`. A bare ip is a range of
+/// length one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    /// IPv4-mapped IPv6 addresses (`::ffff:a.b.c.d`) of dual-stack listeners
+    /// are matched as the IPv4 addresses they are
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = ParseCidrError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (
+                IpAddr::from_str(addr).map_err(|_| ParseCidrError::Invalid)?,
+                Some(u8::from_str(prefix).map_err(|_| ParseCidrError::Invalid)?),
+            ),
+            None => (
+                IpAddr::from_str(s).map_err(|_| ParseCidrError::Invalid)?,
+                None,
+            ),
+        };
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max_prefix);
+        if prefix > max_prefix {
+            return Err(ParseCidrError::Invalid);
+        }
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl std::fmt::Display for Cidr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+impl TryFrom<String> for Cidr {
+    type Error = ParseCidrError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Cidr> for String {
+    fn from(cidr: Cidr) -> String {
+        cidr.to_string()
+    }
+}
+
+/// Per-ip budgets of a single route
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteLimit {
+    pub request_count_limit_per_minute: Option<usize>,
+    pub traffic_limit_per_minute: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FirewallOptions {
+    pub request_count_limit_per_minute: usize,
+    pub traffic_limit_per_minute: u64,
+    /// Trusted ranges, exempt from all of the budgets
+    pub allowlist: Vec<Cidr>,
+    /// Ranges which all of their requests are dropped
+    pub denylist: Vec<Cidr>,
+    /// Budgets of specific routes, keyed by path
+    pub route_limits: HashMap<String, RouteLimit>,
+}
+
+impl Default for FirewallOptions {
+    fn default() -> Self {
+        // (70 request, 100MB) per minute
+        Self {
+            request_count_limit_per_minute: 70,
+            traffic_limit_per_minute: 100 * MB,
+            allowlist: vec![],
+            denylist: vec![],
+            route_limits: [
+                (
+                    "/bincode/mempool".into(),
+                    RouteLimit {
+                        request_count_limit_per_minute: Some(4),
+                        traffic_limit_per_minute: Some(40 * MB),
+                    },
+                ),
+                (
+                    "/bincode/blocks".into(),
+                    RouteLimit {
+                        request_count_limit_per_minute: Some(20),
+                        traffic_limit_per_minute: None,
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+}
+
+impl FirewallOptions {
+    pub fn validate(&self) -> Result<(), NodeError> {
+        if self.request_count_limit_per_minute == 0 || self.traffic_limit_per_minute == 0 {
+            return Err(NodeError::InvalidNodeOptions(
+                "firewall limits should be positive".into(),
+            ));
+        }
+        for (path, limit) in self.route_limits.iter() {
+            if !path.starts_with('/') {
+                return Err(NodeError::InvalidNodeOptions(format!(
+                    "firewall route {} should start with /",
+                    path
+                )));
+            }
+            if limit.request_count_limit_per_minute == Some(0)
+                || limit.traffic_limit_per_minute == Some(0)
+            {
+                return Err(NodeError::InvalidNodeOptions(format!(
+                    "firewall limits of route {} should be positive",
+                    path
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Counters of the firewall decisions, since the node has started
+#[derive(Debug, Clone, Default)]
+pub struct FirewallStats {
+    pub permitted: u64,
+    pub dropped_denylist: u64,
+    pub dropped_request_limit: u64,
+    pub dropped_traffic_limit: u64,
+    pub dropped_route_limit: HashMap<String, u64>,
+}
 
 pub struct Firewall {
-    request_count_limit_per_minute: usize,
+    opts: FirewallOptions,
+    stats: FirewallStats,
+
     request_count_last_reset: Timestamp,
     request_count: HashMap<IpAddr, usize>,
+    route_request_count: HashMap<(IpAddr, String), usize>,
 
-    traffic_limit_per_minute: u64,
     traffic_last_reset: Timestamp,
     traffic: HashMap<IpAddr, u64>,
+    route_traffic: HashMap<(IpAddr, String), u64>,
 }
 
 impl Firewall {
-    pub fn new(request_count_limit_per_minute: usize, traffic_limit_per_minute: u64) -> Self {
+    pub fn new(opts: FirewallOptions) -> Self {
         Self {
-            request_count_limit_per_minute,
-            traffic_limit_per_minute,
+            opts,
+            stats: Default::default(),
             request_count_last_reset: 0,
             request_count: HashMap::new(),
+            route_request_count: HashMap::new(),
             traffic_last_reset: 0,
             traffic: HashMap::new(),
+            route_traffic: HashMap::new(),
         }
     }
+    pub fn stats(&self) -> &FirewallStats {
+        &self.stats
+    }
     pub fn refresh(&mut self, now: u32) {
         if now.saturating_sub(self.request_count_last_reset) > 60 {
             self.request_count.clear();
+            self.route_request_count.clear();
             self.request_count_last_reset = now;
         }
 
         if now.saturating_sub(self.traffic_last_reset) > 60 {
             self.traffic.clear();
+            self.route_traffic.clear();
             self.traffic_last_reset = now;
         }
     }
-    pub fn add_traffic(&mut self, ip: IpAddr, amount: u64) {
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        // Incoming from loopback is always permitted
+        ip.is_loopback() || self.opts.allowlist.iter().any(|c| c.contains(ip))
+    }
+    pub fn add_traffic(&mut self, ip: IpAddr, path: &str, amount: u64) {
+        if self.is_trusted(ip) {
+            return;
+        }
         *self.traffic.entry(ip).or_insert(0) += amount;
+        if self.opts.route_limits.contains_key(path) {
+            *self.route_traffic.entry((ip, path.into())).or_insert(0) += amount;
+        }
     }
-    pub fn incoming_permitted(&mut self, client: SocketAddr) -> bool {
-        // Incoming from loopback is always permitted
-        if client.ip().is_loopback() {
+    pub fn incoming_permitted(&mut self, client: SocketAddr, path: &str) -> bool {
+        let ip = client.ip();
+        if self.opts.denylist.iter().any(|c| c.contains(ip)) {
+            self.stats.dropped_denylist += 1;
+            return false;
+        }
+
+        if self.is_trusted(ip) {
+            self.stats.permitted += 1;
             return true;
         }
 
-        if self.traffic.get(&client.ip()).cloned().unwrap_or(0) > self.traffic_limit_per_minute {
+        if self.traffic.get(&ip).cloned().unwrap_or(0) > self.opts.traffic_limit_per_minute {
+            self.stats.dropped_traffic_limit += 1;
             return false;
         }
 
-        let cnt = self.request_count.entry(client.ip()).or_insert(0);
-        if *cnt >= self.request_count_limit_per_minute {
+        if self.request_count.get(&ip).cloned().unwrap_or(0)
+            >= self.opts.request_count_limit_per_minute
+        {
+            self.stats.dropped_request_limit += 1;
             return false;
         }
 
-        *cnt += 1;
+        if let Some(limit) = self.opts.route_limits.get(path) {
+            let key = (ip, path.to_string());
+            let over_traffic = limit
+                .traffic_limit_per_minute
+                .map(|l| self.route_traffic.get(&key).cloned().unwrap_or(0) > l)
+                .unwrap_or(false);
+            let cnt = self.route_request_count.entry(key).or_insert(0);
+            let over_count = limit
+                .request_count_limit_per_minute
+                .map(|l| *cnt >= l)
+                .unwrap_or(false);
+            if over_traffic || over_count {
+                *self
+                    .stats
+                    .dropped_route_limit
+                    .entry(path.into())
+                    .or_insert(0) += 1;
+                return false;
+            }
+            *cnt += 1;
+        }
+
+        *self.request_count.entry(ip).or_insert(0) += 1;
+        self.stats.permitted += 1;
         true
     }
 }
@@ -61,32 +277,95 @@ mod tests {
 
     #[test]
     fn test_request_limit() {
-        let mut firewall = Firewall::new(10, 1000);
+        let mut firewall = Firewall::new(FirewallOptions {
+            request_count_limit_per_minute: 10,
+            traffic_limit_per_minute: 1000,
+            ..Default::default()
+        });
         firewall.refresh(1234);
         let client: SocketAddr = "123.234.56.78:12345".parse().unwrap();
         for _ in 0..10 {
-            assert!(firewall.incoming_permitted(client));
+            assert!(firewall.incoming_permitted(client, "/stats"));
         }
         // Do not allow after 10 reqs
-        assert!(!firewall.incoming_permitted(client));
+        assert!(!firewall.incoming_permitted(client, "/stats"));
 
         // Not allowed before timer reset
         firewall.refresh(1235);
-        assert!(!firewall.incoming_permitted(client));
+        assert!(!firewall.incoming_permitted(client, "/stats"));
         firewall.refresh(1240);
-        assert!(!firewall.incoming_permitted(client));
+        assert!(!firewall.incoming_permitted(client, "/stats"));
 
         // Go back in time
         firewall.refresh(1230);
-        assert!(!firewall.incoming_permitted(client));
+        assert!(!firewall.incoming_permitted(client, "/stats"));
 
         firewall.refresh(1293);
-        assert!(!firewall.incoming_permitted(client));
+        assert!(!firewall.incoming_permitted(client, "/stats"));
         firewall.refresh(1294);
-        assert!(!firewall.incoming_permitted(client));
+        assert!(!firewall.incoming_permitted(client, "/stats"));
 
         // Reset! Allowed again :)
         firewall.refresh(1295);
-        assert!(firewall.incoming_permitted(client));
+        assert!(firewall.incoming_permitted(client, "/stats"));
+    }
+
+    #[test]
+    fn test_cidr() {
+        let net: Cidr = "10.1.0.0/16".parse().unwrap();
+        assert!(net.contains("10.1.2.3".parse().unwrap()));
+        assert!(!net.contains("10.2.2.3".parse().unwrap()));
+        assert!(!net.contains("::1".parse().unwrap()));
+        let single: Cidr = "1.2.3.4".parse().unwrap();
+        assert_eq!(single.to_string(), "1.2.3.4/32");
+        assert!(single.contains("1.2.3.4".parse().unwrap()));
+        assert!(!single.contains("1.2.3.5".parse().unwrap()));
+        let all: Cidr = "::/0".parse().unwrap();
+        assert!(all.contains("2001:db8::1".parse().unwrap()));
+        assert!("1.2.3.4/33".parse::<Cidr>().is_err());
+
+        let mapped: IpAddr = "::ffff:10.1.2.3".parse().unwrap();
+        assert!(net.contains(mapped));
+        assert!(!single.contains(mapped));
+    }
+
+    #[test]
+    fn test_lists_and_route_limits() {
+        let mut firewall = Firewall::new(FirewallOptions {
+            request_count_limit_per_minute: 10,
+            traffic_limit_per_minute: 1000,
+            allowlist: vec!["123.234.0.0/16".parse().unwrap()],
+            denylist: vec!["66.66.66.0/24".parse().unwrap()],
+            route_limits: [(
+                "/bincode/mempool".into(),
+                RouteLimit {
+                    request_count_limit_per_minute: Some(2),
+                    traffic_limit_per_minute: None,
+                },
+            )]
+            .into_iter()
+            .collect(),
+        });
+        firewall.refresh(1234);
+        let trusted: SocketAddr = "123.234.56.78:12345".parse().unwrap();
+        let denied: SocketAddr = "66.66.66.66:12345".parse().unwrap();
+        let mapped_denied: SocketAddr = "[::ffff:66.66.66.67]:12345".parse().unwrap();
+        let client: SocketAddr = "12.34.56.78:12345".parse().unwrap();
+
+        assert!(!firewall.incoming_permitted(denied, "/stats"));
+        assert!(!firewall.incoming_permitted(mapped_denied, "/stats"));
+        for _ in 0..20 {
+            assert!(firewall.incoming_permitted(trusted, "/bincode/mempool"));
+        }
+
+        assert!(firewall.incoming_permitted(client, "/bincode/mempool"));
+        assert!(firewall.incoming_permitted(client, "/bincode/mempool"));
+        assert!(!firewall.incoming_permitted(client, "/bincode/mempool"));
+        // Other routes are still permitted
+        assert!(firewall.incoming_permitted(client, "/stats"));
+
+        assert_eq!(firewall.stats().dropped_denylist, 2);
+        assert_eq!(firewall.stats().dropped_route_limit["/bincode/mempool"], 1);
+        assert_eq!(firewall.stats().permitted, 23);
     }
 }
@@ -29,7 +29,7 @@ pub async fn discover_peers<K: KvStore, B: Blockchain<K>>(
         async move {
             let timer = Instant::now();
             let result = net
-                .bincode_post::<HandshakeRequest, HandshakeResponse>(
+                .handshake(
                     format!("http://{}/bincode/peers", peer),
                     handshake_req,
                     Limit::default().size(5 * KB).time(SECOND),
@@ -45,7 +45,16 @@ pub async fn discover_peers<K: KvStore, B: Blockchain<K>>(
         for (p, resp) in peer_responses {
             if let Ok((resp, ping_time)) = resp {
                 if p == resp.peer.address {
-                    ctx.peer_manager.add_node(resp.peer, ping_time);
+                    if !ctx
+                        .peer_manager
+                        .add_node(resp.peer, resp.protocol.clone(), ping_time)
+                    {
+                        log::warn!(
+                            "Peer {} has an incompatible protocol version ({})!",
+                            p,
+                            resp.protocol.version
+                        );
+                    }
                 } else {
                     // ?!
                 }
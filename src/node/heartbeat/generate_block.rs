@@ -1,5 +1,4 @@
 use super::*;
-use crate::core::Amount;
 use crate::mpn;
 
 pub async fn generate_block<K: KvStore, B: Blockchain<K>>(
@@ -43,32 +42,34 @@ pub async fn generate_block<K: KvStore, B: Blockchain<K>>(
                     .blockchain
                     .min_validator_reward(ctx.validator_wallet.get_address())?;
 
-                // TODO: Remove Hardcoded rewards
-                let deposit_reward = Amount(u64::from(validator_reward) / 100 * 5);
-                let withdraw_reward = Amount(u64::from(validator_reward) / 100 * 5);
-                let update_reward = Amount(u64::from(validator_reward) / 100 * 15);
-
                 let deposit_nonce = ctx.blockchain.get_deposit_nonce(
                     ctx.validator_wallet.get_address(),
                     ctx.blockchain.config().mpn_config.mpn_contract_id,
                 )?;
-                ctx.mpn_work_pool = Some(mpn::prepare_works(
-                    &ctx.blockchain.config().mpn_config,
-                    &ctx.blockchain,
-                    &ctx.mpn_workers,
-                    deposits,
-                    withdraws,
-                    updates,
-                    validator_reward,
-                    deposit_reward,
-                    withdraw_reward,
-                    update_reward,
-                    deposit_nonce,
-                    ctx.validator_wallet.clone(),
-                    ctx.user_wallet.clone(),
-                )?);
+                let policy = ctx.mpn_assignment_policy();
+                ctx.mpn_work_pool = Some(
+                    mpn::prepare_works(
+                        &ctx.blockchain.config().mpn_config,
+                        &ctx.blockchain,
+                        ctx.mpn_workers.workers(),
+                        deposits,
+                        withdraws,
+                        updates,
+                        validator_reward,
+                        &ctx.opts.mpn_rewards,
+                        deposit_nonce,
+                        ctx.validator_wallet.clone(),
+                        ctx.user_wallet.clone(),
+                    )?
+                    .with_policy(policy),
+                );
             }
         }
+        let now = ctx.local_timestamp();
+        let ctx_ref = &mut *ctx;
+        if let Some(work_pool) = &mut ctx_ref.mpn_work_pool {
+            work_pool.expire(now, ctx_ref.mpn_workers.stats_mut());
+        }
         if let Some(work_pool) = &ctx.mpn_work_pool {
             let wallet = ctx.validator_wallet.clone();
             let nonce = ctx.blockchain.get_nonce(wallet.get_address())?;
@@ -90,10 +91,14 @@ pub async fn generate_block<K: KvStore, B: Blockchain<K>>(
     } else {
         if let Some(claim) = ctx.validator_claim.clone() {
             if claim.address == ctx.validator_wallet.get_address() {
-                if let Some(work_pool) = &ctx.mpn_work_pool {
+                let ctx_ref = &mut *ctx;
+                if let Some(work_pool) = &mut ctx_ref.mpn_work_pool {
                     for work in work_pool.remaining_works().keys() {
                         log::error!("Solution for work {} is late!", work);
                     }
+                    // The slot is over, so every pending assignment is late
+                    work_pool.expire(u32::MAX, ctx_ref.mpn_workers.stats_mut());
+                    ctx_ref.mpn_workers.save()?;
                 }
             }
         }
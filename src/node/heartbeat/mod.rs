@@ -5,6 +5,7 @@ mod generate_block;
 mod refresh;
 mod sync_blocks;
 mod sync_clock;
+mod sync_deltas;
 mod sync_mempool;
 mod sync_peers;
 
@@ -72,6 +73,11 @@ pub async fn heartbeater<K: KvStore, B: Blockchain<K>>(
             |ctx| sync_mempool::sync_mempool(ctx.clone()),
             ints.sync_mempool
         ),
+        make_loop(
+            &ctx,
+            |ctx| sync_deltas::sync_deltas(ctx.clone()),
+            ints.sync_deltas
+        ),
         make_loop(
             &ctx,
             |ctx| generate_block::generate_block(ctx.clone()),
@@ -1,5 +1,6 @@
 use super::*;
 use crate::common::*;
+use std::time::Instant;
 
 pub async fn sync_blocks<K: KvStore, B: Blockchain<K>>(
     context: Arc<RwLock<NodeContext<K, B>>>,
@@ -8,7 +9,7 @@ pub async fn sync_blocks<K: KvStore, B: Blockchain<K>>(
     let net = ctx.outgoing.clone();
     let opts = ctx.opts.clone();
     let max_block_size = ctx.blockchain.config().max_block_size;
-    let mut sorted_peers = ctx.peer_manager.get_peers();
+    let mut sorted_peers = ctx.peer_manager.get_peers_supporting("/bincode/blocks");
     drop(ctx);
 
     sorted_peers.retain(|p| !p.power.is_nan());
@@ -39,6 +40,16 @@ pub async fn sync_blocks<K: KvStore, B: Blockchain<K>>(
             let start_height = std::cmp::min(local_height, peer.height);
             drop(ctx);
 
+            if local_height - start_height > opts.max_reorg_depth {
+                log::error!(
+                    "Peer {} is trying to force a reorg deeper than {} blocks!",
+                    peer.address,
+                    opts.max_reorg_depth
+                );
+                chain_fail = true;
+                break;
+            }
+
             // WARN: Chain might change when getting responses from users, maybe get all data needed before dropping ctx
 
             // Get all headers starting from the indices that we don't have.
@@ -68,7 +79,6 @@ pub async fn sync_blocks<K: KvStore, B: Blockchain<K>>(
                 chain_fail = true;
                 break;
             }
-            // TODO: Check parent hashes
             let ctx = context.read().await;
             let net_ts = ctx.network_timestamp();
             let max_ts_diff = ctx.opts.max_block_time_difference;
@@ -88,6 +98,11 @@ pub async fn sync_blocks<K: KvStore, B: Blockchain<K>>(
                     chain_fail = true;
                     break;
                 }
+                if i > 0 && head.parent_hash != headers[i - 1].hash() {
+                    log::warn!("Bad parent hash returned!");
+                    chain_fail = true;
+                    break;
+                }
             }
             drop(ctx);
 
@@ -153,6 +168,15 @@ pub async fn sync_blocks<K: KvStore, B: Blockchain<K>>(
 
                 if let Some(local_header) = local_header {
                     if local_header.hash() != peer_header.hash() {
+                        if local_height - index > opts.max_reorg_depth {
+                            log::error!(
+                                "Peer {} is trying to force a reorg deeper than {} blocks!",
+                                peer.address,
+                                opts.max_reorg_depth
+                            );
+                            chain_fail = true;
+                            break;
+                        }
                         headers.insert(0, peer_header);
                     } else {
                         break;
@@ -169,6 +193,8 @@ pub async fn sync_blocks<K: KvStore, B: Blockchain<K>>(
 
             let ctx = context.read().await;
 
+            // Validate the whole header chain (Parent hashes, epoch/slots and
+            // validator proofs) before downloading any block body.
             let will_extend = match ctx.blockchain.will_extend(headers[0].number, &headers) {
                 Ok(result) => {
                     if !result {
@@ -194,20 +220,44 @@ pub async fn sync_blocks<K: KvStore, B: Blockchain<K>>(
                     format!("http://{}/bincode/blocks", peer.address).to_string(),
                     GetBlocksRequest {
                         since: headers[0].number,
-                        count: opts.max_blocks_fetch,
+                        count: headers.len() as u64,
                     },
                     Limit::default()
-                        .size(opts.max_blocks_fetch as u64 * max_block_size as u64 * 2)
-                        .time(opts.max_blocks_fetch as u32 * 30 * SECOND),
+                        .size(headers.len() as u64 * max_block_size as u64 * 2)
+                        .time(headers.len() as u32 * 30 * SECOND),
                 )
                 .await
             {
+                if resp.blocks.len() != headers.len()
+                    || resp
+                        .blocks
+                        .iter()
+                        .zip(headers.iter())
+                        .any(|(b, h)| &b.header != h)
+                {
+                    log::warn!("Blocks do not match the validated headers!");
+                    chain_fail = true;
+                    break;
+                }
+
                 let mut ctx = context.write().await;
 
+                let timer = Instant::now();
                 match ctx.blockchain.extend(headers[0].number, &resp.blocks) {
-                    Ok(_) => {
+                    Ok(rolled_back) => {
+                        ctx.metrics
+                            .observe_block_apply(timer.elapsed(), resp.blocks.len());
                         println!("Height advanced to {}!", ctx.blockchain.get_height()?);
+                        ctx.account_mpn_rewards(&resp.blocks, false);
                         ctx.on_update()?;
+                        if !rolled_back.is_empty() {
+                            log::info!(
+                                "Re-injecting transactions of {} rolled back blocks...",
+                                rolled_back.len()
+                            );
+                            ctx.reinject_rolled_back(&rolled_back);
+                        }
+                        ctx.process_orphans()?;
                     }
                     Err(e) => {
                         chain_fail = true;
@@ -221,6 +271,9 @@ pub async fn sync_blocks<K: KvStore, B: Blockchain<K>>(
                 break;
             }
         }
+        if chain_fail || net_fail {
+            context.write().await.metrics.sync_failures += 1;
+        }
         if chain_fail {
             context.write().await.punish_bad_behavior(
                 peer.address,
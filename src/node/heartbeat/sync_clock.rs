@@ -26,7 +26,7 @@ pub async fn sync_clock<K: KvStore, B: Blockchain<K>>(
             async move {
                 let timer = Instant::now();
                 let result = net
-                    .bincode_post::<HandshakeRequest, HandshakeResponse>(
+                    .handshake(
                         format!("http://{}/bincode/peers", peer.address),
                         handshake_req,
                         Limit::default().size(5 * KB).time(SECOND),
@@ -39,14 +39,24 @@ pub async fn sync_clock<K: KvStore, B: Blockchain<K>>(
 
     {
         let mut ctx = context.write().await;
-        let resps = punish_non_responding(&mut ctx, &peer_responses)
+        let mut resps = punish_non_responding(&mut ctx, &peer_responses)
             .into_iter()
             .collect::<Vec<_>>();
         for (p, (resp, ping_time)) in resps.iter() {
-            if *p == resp.peer.address {
-                ctx.peer_manager.add_node(resp.peer.clone(), *ping_time);
+            if *p == resp.peer.address
+                && !ctx
+                    .peer_manager
+                    .add_node(resp.peer.clone(), resp.protocol.clone(), *ping_time)
+            {
+                log::warn!(
+                    "Peer {} has an incompatible protocol version ({})!",
+                    p,
+                    resp.protocol.version
+                );
             }
         }
+        // Ignore the clocks and claims of the incompatible peers
+        resps.retain(|(_, (resp, _))| ProtocolInfo::current().negotiate(&resp.protocol).is_some());
         let (timestamps, timestamp_offsets): (Vec<u32>, Vec<i32>) = resps
             .iter()
             .map(|(_, (resp, _))| (resp.timestamp, resp.timestamp_offset))
@@ -56,7 +66,7 @@ pub async fn sync_clock<K: KvStore, B: Blockchain<K>>(
             let median_timestamp = utils::median(&timestamps).expect("Timestamp list not empty!");
             let median_timestamp_offset =
                 utils::median(&timestamp_offsets).expect("Timestamp list not empty!");
-            ctx.timestamp_offset = median_timestamp as i32 - utils::local_timestamp() as i32;
+            ctx.timestamp_offset = median_timestamp as i32 - ctx.local_timestamp() as i32;
             ctx.timestamp_offset -= median_timestamp_offset;
         }
 
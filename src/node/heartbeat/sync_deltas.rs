@@ -0,0 +1,77 @@
+use super::*;
+use crate::blockchain::BlockchainError;
+use crate::common::*;
+
+pub async fn sync_deltas<K: KvStore, B: Blockchain<K>>(
+    context: Arc<RwLock<NodeContext<K, B>>>,
+) -> Result<(), NodeError> {
+    let ctx = context.read().await;
+    let net = ctx.outgoing.clone();
+    let opts = ctx.opts.clone();
+    let outdated = ctx.blockchain.get_outdated_heights()?;
+    let peers = ctx.peer_manager.get_peers_supporting("/bincode/deltas");
+    drop(ctx);
+
+    for (contract_id, height) in outdated {
+        log::info!(
+            "Contract {} is outdated! Fetching deltas since {}...",
+            contract_id,
+            height
+        );
+        for peer in peers.iter() {
+            let resp = net
+                .bincode_get::<GetDeltasRequest, GetDeltasResponse>(
+                    format!("http://{}/bincode/deltas", peer.address),
+                    GetDeltasRequest {
+                        contract_id,
+                        since_height: height,
+                        count: opts.max_blocks_fetch,
+                    },
+                    Limit::default().size(10 * MB).time(10 * SECOND),
+                )
+                .await;
+            let mut ctx = context.write().await;
+            match resp {
+                Ok(resp) => {
+                    if resp.deltas.is_empty() {
+                        continue;
+                    }
+                    // The contract might have been updated while fetching
+                    if ctx.blockchain.get_outdated_heights()?.get(&contract_id) != Some(&height) {
+                        break;
+                    }
+                    match ctx
+                        .blockchain
+                        .update_contract_state(contract_id, &resp.deltas)
+                    {
+                        Ok(_) => {
+                            log::info!(
+                                "Applied {} deltas of contract {}!",
+                                resp.deltas.len(),
+                                contract_id
+                            );
+                            break;
+                        }
+                        // Not the peer's fault, the local state has changed
+                        Err(BlockchainError::DeltaNotMissing(_)) => {
+                            break;
+                        }
+                        Err(e) => {
+                            log::warn!("Peer {} returned invalid deltas: {}", peer.address, e);
+                            ctx.punish_bad_behavior(
+                                peer.address,
+                                opts.invalid_data_punish,
+                                "invalid contract deltas",
+                            );
+                        }
+                    }
+                }
+                Err(_) => {
+                    ctx.punish_unresponsive(peer.address);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,6 +1,5 @@
 use super::*;
 use crate::common::*;
-use crate::utils::local_timestamp;
 use rand::prelude::IteratorRandom;
 
 pub async fn sync_peers<K: KvStore, B: Blockchain<K>>(
@@ -33,7 +32,7 @@ pub async fn sync_peers<K: KvStore, B: Blockchain<K>>(
             .map(|(_, r)| r.peers)
             .collect::<Vec<_>>();
 
-        let now = local_timestamp();
+        let now = ctx.local_timestamp();
 
         for peers in resps {
             for p in peers
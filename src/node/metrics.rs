@@ -0,0 +1,105 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Counters which are collected by the heartbeats and the API handlers
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    pub punishments: u64,
+    pub sync_failures: u64,
+    pub mpn_late_solutions: u64,
+    block_apply_count: u64,
+    block_apply_seconds: f64,
+}
+
+impl Metrics {
+    /// Records the time it took to apply `count` blocks
+    pub fn observe_block_apply(&mut self, elapsed: Duration, count: usize) {
+        self.block_apply_count += count as u64;
+        self.block_apply_seconds += elapsed.as_secs_f64();
+    }
+
+    pub fn block_apply_count(&self) -> u64 {
+        self.block_apply_count
+    }
+
+    pub fn block_apply_seconds(&self) -> f64 {
+        self.block_apply_seconds
+    }
+}
+
+pub enum MetricKind {
+    Counter,
+    Gauge,
+}
+
+/// Builds a page in the Prometheus text exposition format
+#[derive(Default)]
+pub struct MetricsPage {
+    text: String,
+}
+
+impl MetricsPage {
+    pub fn add(&mut self, name: &str, kind: MetricKind, help: &str, value: f64) {
+        self.add_labeled(name, kind, help, "", &[("", value)]);
+    }
+
+    /// Adds a metric family with one sample per value of the given label
+    pub fn add_labeled<V: std::fmt::Display>(
+        &mut self,
+        name: &str,
+        kind: MetricKind,
+        help: &str,
+        label: &str,
+        samples: &[(V, f64)],
+    ) {
+        let kind = match kind {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+        };
+        let _ = writeln!(self.text, "# HELP bazuka_{} {}", name, help);
+        let _ = writeln!(self.text, "# TYPE bazuka_{} {}", name, kind);
+        for (label_value, value) in samples {
+            if label.is_empty() {
+                let _ = writeln!(self.text, "bazuka_{} {}", name, value);
+            } else {
+                let _ = writeln!(
+                    self.text,
+                    "bazuka_{}{{{}=\"{}\"}} {}",
+                    name, label, label_value, value
+                );
+            }
+        }
+    }
+
+    pub fn render(self) -> String {
+        self.text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_page_format() {
+        let mut page = MetricsPage::default();
+        page.add("height", MetricKind::Gauge, "Chain height", 12.0);
+        page.add_labeled(
+            "peers",
+            MetricKind::Gauge,
+            "Peers by state",
+            "state",
+            &[("node", 3.0), ("punished", 1.0)],
+        );
+        assert_eq!(
+            page.render(),
+            "# HELP bazuka_height Chain height\n\
+             # TYPE bazuka_height gauge\n\
+             bazuka_height 12\n\
+             # HELP bazuka_peers Peers by state\n\
+             # TYPE bazuka_peers gauge\n\
+             bazuka_peers{state=\"node\"} 3\n\
+             bazuka_peers{state=\"punished\"} 1\n"
+        );
+    }
+}
@@ -6,25 +6,33 @@ mod context;
 mod firewall;
 mod heartbeat;
 mod http;
+mod metrics;
+mod mpn_workers;
+mod orphan_pool;
 mod peer_manager;
+pub mod simulation;
 use crate::blockchain::{Blockchain, Mempool};
 use crate::client::{
     messages::*, Limit, NodeError, NodeRequest, OutgoingSender, Peer, PeerAddress, Timestamp,
-    NETWORK_HEADER, SIGNATURE_HEADER,
+    MIN_PROTOCOL_VERSION, NETWORK_HEADER, PROTOCOL_HEADER, SIGNATURE_HEADER,
 };
 use crate::common::*;
-use crate::core::{Amount, Block};
+use crate::core::{Address, Amount, Block};
 use crate::crypto::ed25519;
 use crate::crypto::SignatureScheme;
 use crate::db::KvStore;
-use crate::mpn::MpnWorker;
-use crate::utils::local_timestamp;
+use crate::mpn::{MpnConfig, MpnRewardPolicy};
+use crate::utils::Clock;
 use crate::wallet::TxBuilder;
 use context::NodeContext;
-pub use firewall::Firewall;
+pub use firewall::{Cidr, Firewall, FirewallOptions, FirewallStats, RouteLimit};
 use hyper::body::HttpBody;
 use hyper::{Body, Method, Request, Response, StatusCode};
+use metrics::Metrics;
+pub use mpn_workers::MpnWorkerRegistry;
+use orphan_pool::OrphanPool;
 use peer_manager::PeerManager;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
@@ -33,19 +41,51 @@ use std::time::Duration;
 use tokio::sync::RwLock;
 use tokio::try_join;
 
-#[derive(Debug, Clone)]
+/// Durations are represented as milliseconds in config files
+mod duration_millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(d: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(d.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        Ok(Duration::from_millis(u64::deserialize(deserializer)?))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct HeartbeatIntervals {
+    #[serde(with = "duration_millis")]
     pub log_info: Duration,
+    #[serde(with = "duration_millis")]
     pub refresh: Duration,
+    #[serde(with = "duration_millis")]
     pub sync_peers: Duration,
+    #[serde(with = "duration_millis")]
     pub discover_peers: Duration,
+    #[serde(with = "duration_millis")]
     pub sync_clock: Duration,
+    #[serde(with = "duration_millis")]
     pub sync_blocks: Duration,
+    #[serde(with = "duration_millis")]
     pub sync_mempool: Duration,
+    #[serde(with = "duration_millis")]
+    pub sync_deltas: Duration,
+    #[serde(with = "duration_millis")]
     pub generate_block: Duration,
 }
 
-#[derive(Debug, Clone)]
+impl Default for HeartbeatIntervals {
+    fn default() -> Self {
+        crate::config::node::get_node_options().heartbeat_intervals
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct NodeOptions {
     pub tx_max_time_alive: Option<u32>,
     pub heartbeat_intervals: HeartbeatIntervals,
@@ -60,6 +100,126 @@ pub struct NodeOptions {
     pub mempool_max_fetch: usize,
     pub max_block_time_difference: u32,
     pub automatic_block_generation: bool,
+    pub max_reorg_depth: u64,
+    pub max_orphan_blocks: usize,
+    pub max_orphan_blocks_per_peer: usize,
+    /// Seconds an orphan block is kept waiting for its parent
+    pub max_orphan_block_age: u32,
+    pub mempool_min_balance_per_tx: Amount,
+    pub mempool_max_txs_per_sender: usize,
+    /// Seconds an MPN worker has for solving a work, before it is reassigned
+    pub mpn_work_deadline: u32,
+    /// Number of MPN workers a work is assigned to at the same time
+    pub mpn_work_redundancy: usize,
+    /// Number of MPN works a fully reliable worker gets at the same time
+    pub mpn_works_per_worker: usize,
+    /// Rewards paid to the provers of the MPN works of this validator
+    pub mpn_rewards: MpnRewardPolicy,
+}
+
+impl Default for NodeOptions {
+    fn default() -> Self {
+        crate::config::node::get_node_options()
+    }
+}
+
+impl NodeOptions {
+    pub fn validate(&self) -> Result<(), NodeError> {
+        let ints = &self.heartbeat_intervals;
+        for (name, interval) in [
+            ("log_info", ints.log_info),
+            ("refresh", ints.refresh),
+            ("sync_peers", ints.sync_peers),
+            ("discover_peers", ints.discover_peers),
+            ("sync_clock", ints.sync_clock),
+            ("sync_blocks", ints.sync_blocks),
+            ("sync_mempool", ints.sync_mempool),
+            ("sync_deltas", ints.sync_deltas),
+            ("generate_block", ints.generate_block),
+        ] {
+            if interval.is_zero() {
+                return Err(NodeError::InvalidNodeOptions(format!(
+                    "heartbeat interval {} should be positive",
+                    name
+                )));
+            }
+        }
+        for (name, value) in [
+            ("num_peers", self.num_peers as u64),
+            ("max_blocks_fetch", self.max_blocks_fetch),
+            ("mempool_max_fetch", self.mempool_max_fetch as u64),
+            ("max_reorg_depth", self.max_reorg_depth),
+            (
+                "mempool_min_balance_per_tx",
+                self.mempool_min_balance_per_tx.0,
+            ),
+            (
+                "mempool_max_txs_per_sender",
+                self.mempool_max_txs_per_sender as u64,
+            ),
+            ("mpn_work_deadline", self.mpn_work_deadline as u64),
+            ("mpn_work_redundancy", self.mpn_work_redundancy as u64),
+            ("mpn_works_per_worker", self.mpn_works_per_worker as u64),
+        ] {
+            if value == 0 {
+                return Err(NodeError::InvalidNodeOptions(format!(
+                    "{} should be positive",
+                    name
+                )));
+            }
+        }
+        for (name, punish) in [
+            ("default_punish", self.default_punish),
+            ("no_response_punish", self.no_response_punish),
+            ("invalid_data_punish", self.invalid_data_punish),
+            ("incorrect_chain_punish", self.incorrect_chain_punish),
+        ] {
+            if punish > self.max_punish {
+                return Err(NodeError::InvalidNodeOptions(format!(
+                    "{} should not be more than max_punish",
+                    name
+                )));
+            }
+        }
+        for (name, reward) in [
+            ("deposit", self.mpn_rewards.deposit),
+            ("withdraw", self.mpn_rewards.withdraw),
+            ("update", self.mpn_rewards.update),
+        ] {
+            if !reward.is_valid() {
+                return Err(NodeError::InvalidNodeOptions(format!(
+                    "mpn {} reward percentage should not be more than 100",
+                    name
+                )));
+            }
+        }
+        if self.max_orphan_blocks_per_peer > self.max_orphan_blocks {
+            return Err(NodeError::InvalidNodeOptions(
+                "max_orphan_blocks_per_peer should not be more than max_orphan_blocks".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The MPN rewards which are paid from the block reward should fit in
+    /// the reward of the validator, otherwise no MPN update is ever produced
+    pub fn validate_mpn_rewards(
+        &self,
+        config: &MpnConfig,
+        block_reward: Amount,
+    ) -> Result<(), NodeError> {
+        if self
+            .mpn_rewards
+            .remaining_block_reward(config, block_reward)
+            .is_none()
+        {
+            return Err(NodeError::InvalidNodeOptions(format!(
+                "mpn rewards of a block are more than the block reward ({})",
+                u64::from(block_reward)
+            )));
+        }
+        Ok(())
+    }
 }
 
 fn fetch_signature(
@@ -91,7 +251,7 @@ async fn promote_block<K: KvStore, B: Blockchain<K>>(
 ) {
     let context = context.read().await;
     let net = context.outgoing.clone();
-    let peer_addresses = context.peer_manager.get_peers();
+    let peer_addresses = context.peer_manager.get_peers_supporting("/bincode/blocks");
     tokio::task::spawn(async move {
         http::group_request(&peer_addresses, |peer| {
             net.bincode_post::<PostBlockRequest, PostBlockResponse>(
@@ -112,7 +272,7 @@ async fn promote_validator_claim<K: KvStore, B: Blockchain<K>>(
 ) {
     let context = context.read().await;
     let net = context.outgoing.clone();
-    let peer_addresses = context.peer_manager.get_peers();
+    let peer_addresses = context.peer_manager.get_peers_supporting("/claim");
     tokio::task::spawn(async move {
         http::group_request(&peer_addresses, |peer| {
             net.bincode_post::<PostValidatorClaimRequest, PostValidatorClaimResponse>(
@@ -127,8 +287,21 @@ async fn promote_validator_claim<K: KvStore, B: Blockchain<K>>(
     });
 }
 
+/// Routes that are only served through the admin listener
+const ADMIN_ROUTES: &[&str] = &[
+    "/shutdown",
+    "/generate_block",
+    "/log_level",
+    "/ban",
+    "/unban",
+    "/mempool/flush",
+    "/bincode/mpn/worker",
+    "/bincode/mpn/worker/remove",
+];
+
 async fn node_service<K: KvStore, B: Blockchain<K>>(
     client: Option<SocketAddr>,
+    is_admin: bool,
     context: Arc<RwLock<NodeContext<K, B>>>,
     req: Request<Body>,
 ) -> Result<Response<Body>, NodeError> {
@@ -138,7 +311,12 @@ async fn node_service<K: KvStore, B: Blockchain<K>>(
             .header("Access-Control-Allow-Origin", "*")
             .body(Body::default())?;
 
-        if let Some(client) = client {
+        if ADMIN_ROUTES.contains(&req.uri().path()) && !is_admin {
+            *response.status_mut() = StatusCode::FORBIDDEN;
+            return Ok(response);
+        }
+
+        if let (Some(client), false) = (client, is_admin) {
             let mut ctx = context.write().await;
             let now = ctx.local_timestamp();
             if ctx.peer_manager.is_ip_punished(now, client.ip()) {
@@ -147,7 +325,7 @@ async fn node_service<K: KvStore, B: Blockchain<K>>(
                 return Ok(response);
             }
             if let Some(firewall) = &mut ctx.firewall {
-                if !firewall.incoming_permitted(client) {
+                if !firewall.incoming_permitted(client, req.uri().path()) {
                     log::warn!("{} -> Firewall dropped request!", client);
                     *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
                     return Ok(response);
@@ -186,17 +364,38 @@ async fn node_service<K: KvStore, B: Blockchain<K>>(
         }
         .unwrap_or_else(|| "mainnet".into());
 
+        // Requests without a protocol version are coming from plain clients
+        let protocol_version: Option<u32> = req
+            .headers()
+            .get(PROTOCOL_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok());
+
         let body = req.into_body();
 
         if !is_local && network != context.read().await.network {
             return Err(NodeError::WrongNetwork);
         }
 
+        if let Some(protocol_version) = protocol_version {
+            if protocol_version < MIN_PROTOCOL_VERSION {
+                log::warn!(
+                    "{} -> Incompatible protocol version ({})!",
+                    client
+                        .map(|c| c.to_string())
+                        .unwrap_or_else(|| "UNKNOWN".into()),
+                    protocol_version
+                );
+                *response.status_mut() = StatusCode::UPGRADE_REQUIRED;
+                return Ok(response);
+            }
+        }
+
         if let Some(req_sz) = body.size_hint().upper() {
             if let Some(client) = client {
                 let mut ctx = context.write().await;
                 if let Some(firewall) = &mut ctx.firewall {
-                    firewall.add_traffic(client.ip(), req_sz);
+                    firewall.add_traffic(client.ip(), &path, req_sz);
                 }
             }
         } else {
@@ -208,13 +407,13 @@ async fn node_service<K: KvStore, B: Blockchain<K>>(
 
         let needs_signature = false;
 
-        // TODO: This doesn't prevent replay attacks
-        let is_signed = creds
-            .map(|(pub_key, sig)| {
-                ed25519::Ed25519::<crate::core::Hasher>::verify(&pub_key, &body_bytes, &sig)
-            })
-            .unwrap_or(false);
-        if needs_signature && !is_signed {
+        // Signatures alone don't prevent replay attacks, so the signed
+        // requests which need it carry their own nonces
+        let signer: Option<Address> = creds.and_then(|(pub_key, sig)| {
+            ed25519::Ed25519::<crate::core::Hasher>::verify(&pub_key, &body_bytes, &sig)
+                .then_some(pub_key)
+        });
+        if needs_signature && signer.is_none() {
             return Err(NodeError::SignatureRequired);
         }
 
@@ -231,6 +430,15 @@ async fn node_service<K: KvStore, B: Blockchain<K>>(
                     &api::get_logs(Arc::clone(&context), serde_qs::from_str(&qs)?).await?,
                 )?);
             }
+            (Method::GET, "/metrics") => {
+                response.headers_mut().insert(
+                    hyper::header::CONTENT_TYPE,
+                    hyper::header::HeaderValue::from_static("text/plain; version=0.0.4"),
+                );
+                *response.body_mut() = Body::from(
+                    api::get_metrics(Arc::clone(&context), serde_qs::from_str(&qs)?).await?,
+                );
+            }
             (Method::GET, "/stats") => {
                 *response.body_mut() = Body::from(serde_json::to_vec(
                     &api::get_stats(Arc::clone(&context), serde_qs::from_str(&qs)?).await?,
@@ -256,11 +464,33 @@ async fn node_service<K: KvStore, B: Blockchain<K>>(
                     &api::get_balance(Arc::clone(&context), serde_qs::from_str(&qs)?).await?,
                 )?);
             }
+            (Method::GET, "/contract/state") => {
+                *response.body_mut() = Body::from(serde_json::to_vec(
+                    &api::get_contract_state(Arc::clone(&context), serde_qs::from_str(&qs)?)
+                        .await?,
+                )?);
+            }
+            (Method::GET, "/contract/deltas") => {
+                *response.body_mut() = Body::from(serde_json::to_vec(
+                    &api::get_contract_deltas(Arc::clone(&context), serde_qs::from_str(&qs)?)
+                        .await?,
+                )?);
+            }
+            (Method::GET, "/contract/deposit_nonce") => {
+                *response.body_mut() = Body::from(serde_json::to_vec(
+                    &api::get_deposit_nonce(Arc::clone(&context), serde_qs::from_str(&qs)?).await?,
+                )?);
+            }
             (Method::GET, "/mpn/account") => {
                 *response.body_mut() = Body::from(serde_json::to_vec(
                     &api::get_mpn_account(Arc::clone(&context), serde_qs::from_str(&qs)?).await?,
                 )?);
             }
+            (Method::GET, "/mpn/workers") => {
+                *response.body_mut() = Body::from(serde_json::to_vec(
+                    &api::get_mpn_workers(Arc::clone(&context), serde_qs::from_str(&qs)?).await?,
+                )?);
+            }
             (Method::GET, "/peers") => {
                 *response.body_mut() = Body::from(serde_json::to_vec(
                     &api::get_peers(client, Arc::clone(&context), serde_qs::from_str(&qs)?).await?,
@@ -287,15 +517,36 @@ async fn node_service<K: KvStore, B: Blockchain<K>>(
                     .await?,
                 )?);
             }
+            (Method::POST, "/log_level") => {
+                *response.body_mut() = Body::from(serde_json::to_vec(
+                    &api::post_log_level(
+                        Arc::clone(&context),
+                        serde_json::from_slice(&body_bytes)?,
+                    )
+                    .await?,
+                )?);
+            }
             (Method::POST, "/shutdown") => {
-                if is_local {
-                    *response.body_mut() = Body::from(serde_json::to_vec(
-                        &api::shutdown(Arc::clone(&context), serde_json::from_slice(&body_bytes)?)
-                            .await?,
-                    )?);
-                } else {
-                    *response.status_mut() = StatusCode::FORBIDDEN;
-                }
+                *response.body_mut() = Body::from(serde_json::to_vec(
+                    &api::shutdown(Arc::clone(&context), serde_json::from_slice(&body_bytes)?)
+                        .await?,
+                )?);
+            }
+            (Method::POST, "/ban") => {
+                *response.body_mut() = Body::from(serde_json::to_vec(
+                    &api::ban(Arc::clone(&context), serde_json::from_slice(&body_bytes)?).await?,
+                )?);
+            }
+            (Method::POST, "/unban") => {
+                *response.body_mut() = Body::from(serde_json::to_vec(
+                    &api::unban(Arc::clone(&context), serde_json::from_slice(&body_bytes)?).await?,
+                )?);
+            }
+            (Method::POST, "/mempool/flush") => {
+                *response.body_mut() = Body::from(serde_json::to_vec(
+                    &api::flush_mempool(Arc::clone(&context), serde_json::from_slice(&body_bytes)?)
+                        .await?,
+                )?);
             }
             (Method::POST, "/bincode/transact") => {
                 *response.body_mut() = Body::from(bincode::serialize(
@@ -350,6 +601,21 @@ async fn node_service<K: KvStore, B: Blockchain<K>>(
                         .await?,
                 )?);
             }
+            (Method::GET, "/bincode/light/checkpoint") => {
+                *response.body_mut() = Body::from(bincode::serialize(
+                    &api::get_light_checkpoint(
+                        Arc::clone(&context),
+                        bincode::deserialize(&body_bytes)?,
+                    )
+                    .await?,
+                )?);
+            }
+            (Method::GET, "/bincode/deltas") => {
+                *response.body_mut() = Body::from(bincode::serialize(
+                    &api::get_deltas(Arc::clone(&context), bincode::deserialize(&body_bytes)?)
+                        .await?,
+                )?);
+            }
             (Method::GET, "/bincode/blocks") => {
                 *response.body_mut() = Body::from(bincode::serialize(
                     &api::get_blocks(Arc::clone(&context), bincode::deserialize(&body_bytes)?)
@@ -358,8 +624,12 @@ async fn node_service<K: KvStore, B: Blockchain<K>>(
             }
             (Method::POST, "/bincode/blocks") => {
                 *response.body_mut() = Body::from(bincode::serialize(
-                    &api::post_block(Arc::clone(&context), bincode::deserialize(&body_bytes)?)
-                        .await?,
+                    &api::post_block(
+                        client,
+                        Arc::clone(&context),
+                        bincode::deserialize(&body_bytes)?,
+                    )
+                    .await?,
                 )?);
             }
             (Method::GET, "/mempool") => {
@@ -392,13 +662,18 @@ async fn node_service<K: KvStore, B: Blockchain<K>>(
             }
             (Method::GET, "/bincode/mpn/work") => {
                 *response.body_mut() = Body::from(bincode::serialize(
-                    &api::get_mpn_work(Arc::clone(&context), bincode::deserialize(&body_bytes)?)
-                        .await?,
+                    &api::get_mpn_work(
+                        signer,
+                        Arc::clone(&context),
+                        bincode::deserialize(&body_bytes)?,
+                    )
+                    .await?,
                 )?);
             }
             (Method::POST, "/bincode/mpn/solution") => {
                 *response.body_mut() = Body::from(bincode::serialize(
                     &api::post_mpn_solution(
+                        signer,
                         Arc::clone(&context),
                         bincode::deserialize(&body_bytes)?,
                     )
@@ -411,6 +686,15 @@ async fn node_service<K: KvStore, B: Blockchain<K>>(
                         .await?,
                 )?);
             }
+            (Method::POST, "/bincode/mpn/worker/remove") => {
+                *response.body_mut() = Body::from(bincode::serialize(
+                    &api::remove_mpn_worker(
+                        Arc::clone(&context),
+                        bincode::deserialize(&body_bytes)?,
+                    )
+                    .await?,
+                )?);
+            }
             _ => {
                 *response.status_mut() = StatusCode::NOT_FOUND;
             }
@@ -420,7 +704,7 @@ async fn node_service<K: KvStore, B: Blockchain<K>>(
             if let Some(client) = client {
                 let mut ctx = context.write().await;
                 if let Some(firewall) = &mut ctx.firewall {
-                    firewall.add_traffic(client.ip(), resp_sz);
+                    firewall.add_traffic(client.ip(), &path, resp_sz);
                 }
             }
         }
@@ -432,12 +716,13 @@ async fn node_service<K: KvStore, B: Blockchain<K>>(
         Ok(resp) => Ok(resp),
         Err(e) => {
             if let Some(client) = client {
-                if !is_local {
+                if !is_local && !is_admin {
                     let mut ctx = context.write().await;
                     let default_punish = ctx.opts.default_punish;
                     let now = ctx.local_timestamp();
                     ctx.peer_manager
                         .punish_ip_for(now, client.ip(), default_punish);
+                    ctx.metrics.punishments += 1;
                 }
             }
             log::warn!(
@@ -454,12 +739,14 @@ async fn node_service<K: KvStore, B: Blockchain<K>>(
 
 use tokio::sync::mpsc;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn node_create<K: KvStore, B: Blockchain<K>>(
     opts: NodeOptions,
     network: &str,
     address: Option<PeerAddress>,
     bootstrap: Vec<PeerAddress>,
     blockchain: B,
+    clock: Arc<dyn Clock>,
     timestamp_offset: i32,
     validator_wallet: TxBuilder,
     user_wallet: TxBuilder,
@@ -467,10 +754,11 @@ pub async fn node_create<K: KvStore, B: Blockchain<K>>(
     mut incoming: mpsc::UnboundedReceiver<NodeRequest>,
     outgoing: mpsc::UnboundedSender<NodeRequest>,
     firewall: Option<Firewall>,
-    mpn_workers: Vec<MpnWorker>,
+    mpn_workers: MpnWorkerRegistry,
 ) -> Result<(), NodeError> {
     let context = Arc::new(RwLock::new(NodeContext {
         _phantom: std::marker::PhantomData,
+        clock: Arc::clone(&clock),
         firewall,
         opts: opts.clone(),
         network: network.into(),
@@ -479,22 +767,30 @@ pub async fn node_create<K: KvStore, B: Blockchain<K>>(
         shutdown: false,
         outgoing: Arc::new(OutgoingSender {
             network: network.into(),
+            admin_token: None,
             chan: outgoing,
             priv_key: validator_wallet.get_priv_key(),
         }),
-        mpn_workers: mpn_workers
-            .into_iter()
-            .map(|w| (w.address.clone(), w))
-            .collect(),
+        mpn_workers,
         mpn_work_pool: None,
-        mempool: Mempool::new(Amount(1_000_000_000)),
+        mempool: Mempool::new(
+            Arc::clone(&clock),
+            opts.mempool_min_balance_per_tx,
+            opts.mempool_max_txs_per_sender,
+        ),
+        orphan_pool: OrphanPool::new(
+            opts.max_orphan_blocks,
+            opts.max_orphan_blocks_per_peer,
+            opts.max_orphan_block_age,
+        ),
+        metrics: Metrics::default(),
         blockchain,
         validator_wallet,
         user_wallet,
         peer_manager: PeerManager::new(
             address,
             bootstrap,
-            local_timestamp(),
+            clock.now(),
             opts.candidate_remove_threshold,
         ),
         timestamp_offset,
@@ -507,10 +803,9 @@ pub async fn node_create<K: KvStore, B: Blockchain<K>>(
                 break;
             }
             if let Some(msg) = incoming.recv().await {
-                if let Err(e) = msg
-                    .resp
-                    .send(node_service(msg.socket_addr, Arc::clone(&context), msg.body).await)
-                {
+                if let Err(e) = msg.resp.send(
+                    node_service(msg.socket_addr, msg.admin, Arc::clone(&context), msg.body).await,
+                ) {
                     log::error!("Request sender not receiving its answer: {}", e);
                 }
             } else {
@@ -0,0 +1,273 @@
+use crate::client::NodeError;
+use crate::core::{Address, Amount, Block, ContractId, ContractUpdate, TransactionData};
+use crate::mpn::{MpnWorker, MpnWorkerStats};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+#[derive(Default, Serialize, Deserialize)]
+struct MpnWorkerRegistryData {
+    workers: HashMap<Address, MpnWorker>,
+    stats: HashMap<Address, MpnWorkerStats>,
+    /// Nonce of the last signed request of each worker
+    nonces: HashMap<Address, u64>,
+}
+
+/// Workers registered for proving the MPN works of the node, together with
+/// their accounting. Only registered workers have statistics, so that the work
+/// pool doesn't account anyone else. When a path is given, the registry is
+/// kept in that file across restarts.
+#[derive(Default)]
+pub struct MpnWorkerRegistry {
+    path: Option<PathBuf>,
+    data: MpnWorkerRegistryData,
+}
+
+impl MpnWorkerRegistry {
+    /// In-memory registry
+    pub fn new(workers: Vec<MpnWorker>) -> Self {
+        let mut registry = Self::default();
+        for worker in workers {
+            registry.insert(worker);
+        }
+        registry
+    }
+
+    fn insert(&mut self, worker: MpnWorker) {
+        self.data.stats.entry(worker.address.clone()).or_default();
+        self.data.workers.insert(worker.address.clone(), worker);
+    }
+
+    /// Loads the registry from its file, if it exists, and registers the
+    /// given workers too. An unreadable file is replaced by an empty registry.
+    pub fn open(path: PathBuf, workers: Vec<MpnWorker>) -> Result<Self, NodeError> {
+        let data = if path.exists() {
+            match std::fs::read(&path)
+                .map_err(NodeError::from)
+                .and_then(|bytes| Ok(bincode::deserialize(&bytes)?))
+            {
+                Ok(data) => data,
+                Err(e) => {
+                    log::warn!(
+                        "Cannot read the MPN worker registry at {:?}, starting over! Error: {}",
+                        path,
+                        e
+                    );
+                    MpnWorkerRegistryData::default()
+                }
+            }
+        } else {
+            MpnWorkerRegistryData::default()
+        };
+        let mut registry = Self {
+            path: Some(path),
+            data: MpnWorkerRegistryData::default(),
+        };
+        for worker in data.workers.into_values().chain(workers) {
+            registry.insert(worker);
+        }
+        registry.data.stats.extend(data.stats);
+        registry.data.nonces = data.nonces;
+        registry.save()?;
+        Ok(registry)
+    }
+
+    /// Drops the accounting of unregistered workers and writes the registry
+    /// to a temporary file, which then replaces the registry file at once
+    pub fn save(&mut self) -> Result<(), NodeError> {
+        let workers = &self.data.workers;
+        self.data.stats.retain(|addr, _| workers.contains_key(addr));
+        self.data
+            .nonces
+            .retain(|addr, _| workers.contains_key(addr));
+        if let Some(path) = &self.path {
+            let tmp_path = path.with_extension("tmp");
+            let mut file = std::fs::File::create(&tmp_path)?;
+            file.write_all(&bincode::serialize(&self.data)?)?;
+            file.sync_all()?;
+            std::fs::rename(tmp_path, path)?;
+        }
+        Ok(())
+    }
+
+    pub fn workers(&self) -> &HashMap<Address, MpnWorker> {
+        &self.data.workers
+    }
+
+    pub fn is_registered(&self, address: &Address) -> bool {
+        self.data.workers.contains_key(address)
+    }
+
+    /// Returns false if the worker is already registered
+    pub fn register(&mut self, worker: MpnWorker) -> Result<bool, NodeError> {
+        if self.is_registered(&worker.address) {
+            return Ok(false);
+        }
+        self.insert(worker);
+        self.save()?;
+        Ok(true)
+    }
+
+    pub fn remove(&mut self, address: &Address) -> Result<bool, NodeError> {
+        let removed = self.data.workers.remove(address).is_some();
+        self.data.stats.remove(address);
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    pub fn stats(&self) -> &HashMap<Address, MpnWorkerStats> {
+        &self.data.stats
+    }
+
+    /// Statistics are only written to the file on the next `save`
+    pub fn stats_mut(&mut self) -> &mut HashMap<Address, MpnWorkerStats> {
+        &mut self.data.stats
+    }
+
+    /// Accepts each nonce of a worker only once, and only in increasing
+    /// order, so that signed requests cannot be replayed
+    pub fn use_nonce(&mut self, address: &Address, nonce: u64) -> bool {
+        let last = self.data.nonces.entry(address.clone()).or_default();
+        if nonce <= *last {
+            return false;
+        }
+        *last = nonce;
+        true
+    }
+
+    /// Credits (Or takes back, when the blocks are rolled back) the rewards
+    /// of the registered workers in the MPN updates of `producer`
+    pub fn account_rewards(
+        &mut self,
+        mpn_contract_id: &ContractId,
+        producer: &Address,
+        blocks: &[Block],
+        rolled_back: bool,
+    ) {
+        let updates = blocks
+            .iter()
+            .flat_map(|b| b.body.iter())
+            .filter(|tx| tx.src.as_ref() == Some(producer))
+            .filter_map(|tx| match &tx.data {
+                TransactionData::UpdateContract {
+                    contract_id,
+                    updates,
+                    ..
+                } if contract_id == mpn_contract_id => Some(updates),
+                _ => None,
+            })
+            .flatten()
+            .collect::<Vec<&ContractUpdate>>();
+        for update in updates {
+            if !self.is_registered(&update.prover) {
+                continue;
+            }
+            let stats = self.data.stats.entry(update.prover.clone()).or_default();
+            stats.rewards = Amount(if rolled_back {
+                stats.rewards.0.saturating_sub(update.reward.0)
+            } else {
+                stats.rewards.0.saturating_add(update.reward.0)
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Amount;
+    use crate::wallet::TxBuilder;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_registry_is_persisted() {
+        let dir = TempDir::new("bazuka_mpn_workers").unwrap();
+        let path = dir.path().join("mpn_workers");
+        let alice = TxBuilder::new(&Vec::from("ALICE")).get_address();
+        let bob = TxBuilder::new(&Vec::from("BOB")).get_address();
+
+        let mut registry = MpnWorkerRegistry::open(
+            path.clone(),
+            vec![MpnWorker {
+                address: alice.clone(),
+            }],
+        )
+        .unwrap();
+        assert!(registry
+            .register(MpnWorker {
+                address: bob.clone()
+            })
+            .unwrap());
+        assert!(!registry
+            .register(MpnWorker {
+                address: bob.clone()
+            })
+            .unwrap());
+        let stats = registry.stats_mut().entry(bob.clone()).or_default();
+        stats.solved += 1;
+        stats.rewards = Amount(10);
+        registry.save().unwrap();
+        drop(registry);
+
+        let mut registry = MpnWorkerRegistry::open(path.clone(), vec![]).unwrap();
+        assert!(registry.is_registered(&alice));
+        assert!(registry.is_registered(&bob));
+        assert_eq!(registry.stats()[&bob].rewards, Amount(10));
+        assert!(registry.remove(&alice).unwrap());
+        assert!(!registry.remove(&alice).unwrap());
+
+        let registry = MpnWorkerRegistry::open(path, vec![]).unwrap();
+        assert!(!registry.is_registered(&alice));
+        assert_eq!(registry.workers().len(), 1);
+    }
+
+    #[test]
+    fn test_registry_recovers_from_corrupt_file() {
+        let dir = TempDir::new("bazuka_mpn_workers").unwrap();
+        let path = dir.path().join("mpn_workers");
+        std::fs::write(&path, b"garbage").unwrap();
+        let alice = TxBuilder::new(&Vec::from("ALICE")).get_address();
+        let registry = MpnWorkerRegistry::open(
+            path.clone(),
+            vec![MpnWorker {
+                address: alice.clone(),
+            }],
+        )
+        .unwrap();
+        assert!(registry.is_registered(&alice));
+        drop(registry);
+        assert!(MpnWorkerRegistry::open(path, vec![])
+            .unwrap()
+            .is_registered(&alice));
+    }
+
+    #[test]
+    fn test_registry_nonces_and_unregistered_stats() {
+        let alice = TxBuilder::new(&Vec::from("ALICE")).get_address();
+        let bob = TxBuilder::new(&Vec::from("BOB")).get_address();
+        let mut registry = MpnWorkerRegistry::new(vec![MpnWorker {
+            address: alice.clone(),
+        }]);
+        // Registered workers are accounted from the start
+        assert!(registry.stats().contains_key(&alice));
+        assert!(!registry.stats().contains_key(&bob));
+        assert!(registry.use_nonce(&alice, 10));
+        assert!(!registry.use_nonce(&alice, 10));
+        assert!(!registry.use_nonce(&alice, 9));
+        assert!(registry.use_nonce(&alice, 11));
+        assert!(registry.use_nonce(&bob, 10));
+
+        registry
+            .stats_mut()
+            .entry(alice.clone())
+            .or_default()
+            .solved += 1;
+        registry.stats_mut().entry(bob.clone()).or_default().solved += 1;
+        registry.save().unwrap();
+        assert!(registry.stats().contains_key(&alice));
+        assert!(!registry.stats().contains_key(&bob));
+    }
+}
@@ -0,0 +1,204 @@
+use crate::core::{hash::Hash, Block, Hasher};
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+struct Orphan {
+    block: Block,
+    sender: Option<IpAddr>,
+    received: u32,
+}
+
+/// Keeps blocks that have arrived before their parents, keyed by their
+/// parent hash, so that they can be applied once the parent is applied.
+pub struct OrphanPool {
+    max_size: usize,
+    max_per_peer: usize,
+    max_age: u32,
+    orphans: HashMap<<Hasher as Hash>::Output, Vec<Orphan>>,
+    per_peer: HashMap<Option<IpAddr>, usize>,
+}
+
+impl OrphanPool {
+    pub fn new(max_size: usize, max_per_peer: usize, max_age: u32) -> Self {
+        Self {
+            max_size,
+            max_per_peer,
+            max_age,
+            orphans: HashMap::new(),
+            per_peer: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.orphans.values().map(|o| o.len()).sum()
+    }
+
+    /// Returns false if the block is rejected. When the pool is full, the
+    /// oldest orphan of the peer with the most orphans is evicted.
+    pub fn insert(&mut self, now: u32, sender: Option<IpAddr>, block: Block) -> bool {
+        if self.per_peer.get(&sender).cloned().unwrap_or(0) >= self.max_per_peer {
+            return false;
+        }
+        if self
+            .orphans
+            .get(&block.header.parent_hash)
+            .map(|siblings| siblings.iter().any(|o| o.block.header == block.header))
+            .unwrap_or_default()
+        {
+            return false;
+        }
+        if self.len() >= self.max_size && !self.evict() {
+            return false;
+        }
+        self.orphans
+            .entry(block.header.parent_hash)
+            .or_default()
+            .push(Orphan {
+                block,
+                sender,
+                received: now,
+            });
+        *self.per_peer.entry(sender).or_insert(0) += 1;
+        true
+    }
+
+    fn evict(&mut self) -> bool {
+        let worst_sender = if let Some((sender, _)) = self.per_peer.iter().max_by_key(|(_, c)| **c)
+        {
+            *sender
+        } else {
+            return false;
+        };
+        let oldest = self
+            .orphans
+            .iter()
+            .flat_map(|(parent, siblings)| {
+                siblings
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, o)| o.sender == worst_sender)
+                    .map(move |(i, o)| (o.received, *parent, i))
+            })
+            .min();
+        if let Some((_, parent, i)) = oldest {
+            if let Some(siblings) = self.orphans.get_mut(&parent) {
+                siblings.remove(i);
+                if siblings.is_empty() {
+                    self.orphans.remove(&parent);
+                }
+            }
+            self.release(worst_sender);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Removes and returns all orphans that are built on the given block
+    pub fn take_children(&mut self, parent_hash: &<Hasher as Hash>::Output) -> Vec<Block> {
+        let children = self.orphans.remove(parent_hash).unwrap_or_default();
+        for child in children.iter() {
+            self.release(child.sender);
+        }
+        children.into_iter().map(|o| o.block).collect()
+    }
+
+    /// Removes the orphans that can't be applied on a chain with given height
+    /// and the ones that have been waiting for their parents for too long
+    pub fn prune(&mut self, now: u32, height: u64) {
+        let mut released = Vec::new();
+        for siblings in self.orphans.values_mut() {
+            siblings.retain(|o| {
+                let keep = o.block.header.number >= height
+                    && now.saturating_sub(o.received) <= self.max_age;
+                if !keep {
+                    released.push(o.sender);
+                }
+                keep
+            });
+        }
+        self.orphans.retain(|_, siblings| !siblings.is_empty());
+        for sender in released {
+            self.release(sender);
+        }
+    }
+
+    fn release(&mut self, sender: Option<IpAddr>) {
+        if let Some(cnt) = self.per_peer.get_mut(&sender) {
+            *cnt -= 1;
+            if *cnt == 0 {
+                self.per_peer.remove(&sender);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Header, ProofOfStake};
+
+    fn dummy_block(parent: u8, number: u64) -> Block {
+        Block {
+            header: Header {
+                parent_hash: [parent; 32],
+                number,
+                block_root: Default::default(),
+                state_root: Default::default(),
+                proof_of_stake: ProofOfStake {
+                    validator: Default::default(),
+                    timestamp: 0,
+                    proof: None,
+                },
+            },
+            body: vec![],
+        }
+    }
+
+    #[test]
+    fn test_orphan_pool_limits() {
+        let mut pool = OrphanPool::new(3, 2, 600);
+        let peer1: Option<IpAddr> = Some("123.234.56.78".parse().unwrap());
+        let peer2: Option<IpAddr> = Some("123.234.56.79".parse().unwrap());
+
+        assert!(pool.insert(0, peer1, dummy_block(1, 10)));
+        // Duplicates are ignored
+        assert!(!pool.insert(1, peer1, dummy_block(1, 10)));
+        assert!(pool.insert(2, peer1, dummy_block(2, 11)));
+        // Peer limit reached
+        assert!(!pool.insert(3, peer1, dummy_block(3, 12)));
+        assert!(pool.insert(4, peer2, dummy_block(3, 12)));
+        // Pool is full, the oldest orphan of the worst peer is evicted
+        assert!(pool.insert(5, peer2, dummy_block(4, 13)));
+        assert_eq!(pool.len(), 3);
+        assert!(pool.take_children(&[1; 32]).is_empty());
+
+        assert_eq!(pool.take_children(&[2; 32]), vec![dummy_block(2, 11)]);
+        assert!(pool.take_children(&[2; 32]).is_empty());
+        assert!(pool.insert(6, peer1, dummy_block(5, 14)));
+
+        pool.prune(7, 13);
+        assert_eq!(pool.len(), 2);
+        assert!(pool.take_children(&[3; 32]).is_empty());
+        assert!(pool.insert(8, peer1, dummy_block(6, 15)));
+        assert!(!pool.insert(9, peer1, dummy_block(7, 16)));
+    }
+
+    #[test]
+    fn test_orphan_pool_expiry() {
+        let mut pool = OrphanPool::new(3, 2, 600);
+        let peer: Option<IpAddr> = Some("123.234.56.78".parse().unwrap());
+
+        assert!(pool.insert(1000, peer, dummy_block(1, 1000000)));
+        assert!(pool.insert(1300, peer, dummy_block(2, 1000000)));
+        pool.prune(1600, 10);
+        assert_eq!(pool.len(), 2);
+        // Far-future orphans are dropped once they are too old
+        pool.prune(1601, 10);
+        assert_eq!(pool.len(), 1);
+        assert!(pool.insert(1602, peer, dummy_block(3, 1000000)));
+        pool.prune(1901, 10);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.take_children(&[3; 32]), vec![dummy_block(3, 1000000)]);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::client::{Peer, PeerAddress};
+use crate::client::{messages::ProtocolInfo, Peer, PeerAddress};
 use rand::prelude::IteratorRandom;
 use std::collections::HashMap;
 use std::net::IpAddr;
@@ -12,6 +12,7 @@ struct CandidateDetails {
 #[derive(Clone)]
 struct NodeDetails {
     peer: Peer,
+    protocol: ProtocolInfo,
     ping_time: Duration,
 }
 
@@ -89,6 +90,11 @@ impl PeerManager {
         );
     }
 
+    /// Returns false if the peer was not punished
+    pub fn unpunish_ip(&mut self, ip: IpAddr) -> bool {
+        self.punishments.remove(&ip).is_some()
+    }
+
     pub fn mark_as_candidate(&mut self, now: u32, addr: &PeerAddress) {
         if self.nodes.contains_key(&addr.ip()) {
             self.nodes.remove(&addr.ip());
@@ -106,6 +112,17 @@ impl PeerManager {
         self.nodes.len()
     }
 
+    pub fn candidate_count(&self) -> usize {
+        self.candidates.len()
+    }
+
+    pub fn punished_count(&self, now: u32) -> usize {
+        self.punishments
+            .values()
+            .filter(|det| now < det.punished_till)
+            .count()
+    }
+
     pub fn get_nodes(&self) -> impl Iterator<Item = &Peer> {
         self.nodes.values().map(|n| &n.peer)
     }
@@ -137,6 +154,16 @@ impl PeerManager {
             .collect()
     }
 
+    /// Peers that are able to handle the given message
+    pub fn get_peers_supporting(&self, message: &str) -> Vec<Peer> {
+        self.peers
+            .iter()
+            .filter_map(|addr| self.nodes.get(addr))
+            .filter(|p| p.protocol.supports(message))
+            .map(|p| p.peer.clone())
+            .collect()
+    }
+
     pub fn add_candidate(&mut self, now: u32, addr: PeerAddress) {
         if self.self_addr == Some(addr) {
             return;
@@ -152,12 +179,25 @@ impl PeerManager {
         }
     }
 
-    pub fn add_node(&mut self, peer: Peer, ping_time: Duration) {
+    /// Returns false if the node's protocol is incompatible with ours
+    pub fn add_node(&mut self, peer: Peer, protocol: ProtocolInfo, ping_time: Duration) -> bool {
+        if ProtocolInfo::current().negotiate(&protocol).is_none() {
+            self.candidates.remove(&peer.address.ip());
+            self.nodes.remove(&peer.address.ip());
+            return false;
+        }
         if self.self_addr == Some(peer.address) {
-            return;
+            return true;
         }
         self.candidates.remove(&peer.address.ip());
-        self.nodes
-            .insert(peer.address.ip(), NodeDetails { peer, ping_time });
+        self.nodes.insert(
+            peer.address.ip(),
+            NodeDetails {
+                peer,
+                protocol,
+                ping_time,
+            },
+        );
+        true
     }
 }
@@ -0,0 +1,750 @@
+//! Harness for running scripted scenarios on a network of in-memory nodes.
+//!
+//! Requests between the nodes pass through a router which applies [`Rule`]s
+//! (partitions, latencies, drop rates and redirections). Nodes may crash and
+//! restart while keeping their chain, and their clocks can be skewed through
+//! their timestamp offsets. All nodes share a manual clock which only moves
+//! when the scenario advances it. The router handles one request at a time,
+//! in the order the requests reach it, and delayed requests wait on the
+//! manual clock instead of real time. Every routing decision and scenario step
+//! is recorded in a trace, and random decisions are drawn from a seeded
+//! generator in routing order, so that a failing scenario can be replayed with
+//! the same seed. Scenarios are meant to run on a paused tokio runtime
+//! (`start_paused`), where advancing the clock takes no real time.
+
+use super::*;
+
+use crate::blockchain::{BlockchainConfig, KvStoreChain};
+use crate::client::{messages::SocialProfiles, BazukaClient};
+use crate::config;
+use crate::db::{Blob, KvStoreError, QueryResult, RamKvStore, StringKey, WriteOp};
+use crate::mpn::MpnWorker;
+use crate::utils::MockClock;
+use crate::wallet::TxBuilder;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::sync::{watch, RwLock};
+use tokio::time::{sleep, Duration};
+
+/// Timestamp of the shared clock when a simulation starts
+const START_TIMESTAMP: u32 = 1000;
+
+#[derive(Clone)]
+pub struct NodeOpts {
+    pub config: BlockchainConfig,
+    pub wallet: TxBuilder,
+    /// Id of the node, which is also the last octet of its ip
+    pub addr: u16,
+    pub bootstrap: Vec<u16>,
+    pub timestamp_offset: i32,
+    pub auto_gen_block: bool,
+    pub mpn_workers: Vec<MpnWorker>,
+}
+
+pub fn peer_address(id: u16) -> PeerAddress {
+    PeerAddress(SocketAddr::from(([123, 234, 123, id as u8], 8765)))
+}
+
+fn node_id(addr: SocketAddr) -> Option<u16> {
+    match addr.ip() {
+        IpAddr::V4(ip) if ip.octets()[..3] == [123, 234, 123] => Some(ip.octets()[3] as u16),
+        _ => None,
+    }
+}
+
+/// Storage of a simulated node, which survives its crashes
+#[derive(Clone, Default)]
+struct Disk(Arc<std::sync::Mutex<RamKvStore>>);
+
+impl KvStore for Disk {
+    fn get(&self, k: StringKey) -> Result<Option<Blob>, KvStoreError> {
+        self.0.lock().unwrap().get(k)
+    }
+    fn update(&mut self, ops: &[WriteOp]) -> Result<(), KvStoreError> {
+        self.0.lock().unwrap().update(ops)
+    }
+    fn pairs_from(
+        &self,
+        prefix: StringKey,
+        from: StringKey,
+    ) -> Result<QueryResult<'_>, KvStoreError> {
+        let db = self.0.lock().unwrap();
+        let pairs = db.pairs_from(prefix, from)?.into_iter().collect();
+        Ok(QueryResult::Precalculated(pairs))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Action {
+    Drop,
+    /// Drops the requests with the given probability
+    DropRate(f64),
+    Delay(Duration),
+    Redirect(u16),
+}
+
+#[derive(Clone, Debug)]
+pub enum Endpoint {
+    Any,
+    Peer(u16),
+    Group(Vec<u16>),
+}
+
+impl Endpoint {
+    fn matches(&self, id: u16) -> bool {
+        match self {
+            Endpoint::Any => true,
+            Endpoint::Peer(peer) => *peer == id,
+            Endpoint::Group(peers) => peers.contains(&id),
+        }
+    }
+}
+
+/// The first rule which applies to a request decides what happens to it
+#[derive(Clone, Debug)]
+pub struct Rule {
+    pub from: Endpoint,
+    pub to: Endpoint,
+    pub url: String,
+    pub action: Action,
+}
+
+impl Rule {
+    pub fn drop_all() -> Self {
+        Rule {
+            from: Endpoint::Any,
+            to: Endpoint::Any,
+            url: "".into(),
+            action: Action::Drop,
+        }
+    }
+    pub fn drop_url(url: &str) -> Self {
+        Rule {
+            from: Endpoint::Any,
+            to: Endpoint::Any,
+            url: url.into(),
+            action: Action::Drop,
+        }
+    }
+    pub fn latency(from: Endpoint, to: Endpoint, delay: Duration) -> Self {
+        Rule {
+            from,
+            to,
+            url: "".into(),
+            action: Action::Delay(delay),
+        }
+    }
+    pub fn drop_rate(from: Endpoint, to: Endpoint, rate: f64) -> Self {
+        Rule {
+            from,
+            to,
+            url: "".into(),
+            action: Action::DropRate(rate),
+        }
+    }
+    /// Rules which drop all of the requests between two groups of nodes
+    pub fn partition(a: Vec<u16>, b: Vec<u16>) -> Vec<Self> {
+        vec![
+            Rule {
+                from: Endpoint::Group(a.clone()),
+                to: Endpoint::Group(b.clone()),
+                url: "".into(),
+                action: Action::Drop,
+            },
+            Rule {
+                from: Endpoint::Group(b),
+                to: Endpoint::Group(a),
+                url: "".into(),
+                action: Action::Drop,
+            },
+        ]
+    }
+    fn applies(&self, url: &str, from: u16, to: u16) -> bool {
+        url.contains(&self.url) && self.from.matches(from) && self.to.matches(to)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TraceEvent {
+    Delivered {
+        from: u16,
+        to: u16,
+        url: String,
+    },
+    Dropped {
+        from: u16,
+        to: u16,
+        url: String,
+    },
+    Delayed {
+        from: u16,
+        to: u16,
+        url: String,
+        millis: u64,
+    },
+    Redirected {
+        from: u16,
+        to: u16,
+        url: String,
+        redirected_to: u16,
+    },
+    /// The destination was down when the request arrived
+    Unreachable {
+        from: u16,
+        to: u16,
+        url: String,
+    },
+    Crashed {
+        node: u16,
+    },
+    Restarted {
+        node: u16,
+        timestamp_offset: i32,
+    },
+    RulesChanged {
+        rules: usize,
+    },
+    ClockAdvanced {
+        secs: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceEntry {
+    pub seq: u64,
+    pub event: TraceEvent,
+}
+
+struct SimNode {
+    opts: NodeOpts,
+    disk: Disk,
+    up: bool,
+}
+
+/// Request which has passed the rules and is on its way to its destination
+struct Delivery {
+    from: u16,
+    to: u16,
+    url: String,
+    req: NodeRequest,
+}
+
+struct SimState {
+    rules: Vec<Rule>,
+    rng: ChaChaRng,
+    trace: Vec<TraceEntry>,
+    nodes: BTreeMap<u16, SimNode>,
+    /// Delayed requests, keyed by the millisecond of the manual clock they are
+    /// due at and the order they were delayed in
+    delayed: BTreeMap<(u64, u64), Delivery>,
+    finished: bool,
+}
+
+impl SimState {
+    fn record(&mut self, event: TraceEvent) {
+        log::debug!("Simulation: {:?}", event);
+        let seq = self.trace.len() as u64;
+        self.trace.push(TraceEntry { seq, event });
+    }
+    fn is_up(&self, id: u16) -> bool {
+        self.nodes.get(&id).map(|n| n.up).unwrap_or(false)
+    }
+
+    /// Applies the rules to a request sent by `src`. Returns the request if it
+    /// should be delivered right away, delayed requests are kept until the
+    /// clock reaches them.
+    fn route(&mut self, now: u32, src: u16, req: NodeRequest) -> Option<Delivery> {
+        let url = req.body.uri().path().to_string();
+        let dst = req
+            .body
+            .uri()
+            .authority()
+            .and_then(|a| a.as_str().parse().ok())
+            .and_then(node_id)?;
+
+        let action = self
+            .rules
+            .iter()
+            .find(|r| r.applies(&url, src, dst))
+            .map(|r| r.action.clone());
+        let mut delivery = Delivery {
+            from: src,
+            to: dst,
+            url,
+            req,
+        };
+        match action {
+            Some(Action::Drop) => {
+                self.record(TraceEvent::Dropped {
+                    from: src,
+                    to: dst,
+                    url: delivery.url,
+                });
+                None
+            }
+            Some(Action::DropRate(rate)) => {
+                if self.rng.gen_bool(rate.clamp(0.0, 1.0)) {
+                    self.record(TraceEvent::Dropped {
+                        from: src,
+                        to: dst,
+                        url: delivery.url,
+                    });
+                    None
+                } else {
+                    Some(delivery)
+                }
+            }
+            Some(Action::Delay(delay)) => {
+                let millis = delay.as_millis() as u64;
+                self.record(TraceEvent::Delayed {
+                    from: src,
+                    to: dst,
+                    url: delivery.url.clone(),
+                    millis,
+                });
+                let order = self.trace.len() as u64;
+                self.delayed
+                    .insert((now as u64 * 1000 + millis, order), delivery);
+                None
+            }
+            Some(Action::Redirect(to)) => {
+                self.record(TraceEvent::Redirected {
+                    from: src,
+                    to: dst,
+                    url: delivery.url.clone(),
+                    redirected_to: to,
+                });
+                delivery.to = to;
+                Some(delivery)
+            }
+            None => Some(delivery),
+        }
+    }
+
+    /// Delayed requests which are due at the given time, in the order they
+    /// are due
+    fn take_due(&mut self, now: u32) -> Vec<Delivery> {
+        let later = self.delayed.split_off(&(now as u64 * 1000 + 1, 0));
+        std::mem::replace(&mut self.delayed, later)
+            .into_values()
+            .collect()
+    }
+
+    fn deliver(&mut self, fronts: &Fronts, delivery: Delivery) {
+        let Delivery { from, to, url, req } = delivery;
+        match fronts.get(&to) {
+            Some(front) if self.is_up(to) => {
+                self.record(TraceEvent::Delivered { from, to, url });
+                // The answer goes straight back to the sender
+                let _ = front.sender.chan.send(NodeRequest {
+                    limit: Limit::default(),
+                    socket_addr: None,
+                    admin: false,
+                    body: req.body,
+                    resp: req.resp,
+                });
+            }
+            _ => {
+                self.record(TraceEvent::Unreachable { from, to, url });
+            }
+        }
+    }
+}
+
+type Fronts = Arc<HashMap<u16, BazukaClient>>;
+
+/// Requests on their way to the router, along with the node which sent them
+type Network = mpsc::UnboundedSender<(u16, NodeRequest)>;
+
+pub struct Simulator {
+    clock: MockClock,
+    state: Arc<RwLock<SimState>>,
+    changes: watch::Sender<()>,
+    network: Network,
+    clients: Vec<BazukaClient>,
+}
+
+impl Simulator {
+    /// Creates a network of the given nodes. The returned future runs the
+    /// nodes until [`Simulator::finish`] is called.
+    pub fn new(
+        seed: u64,
+        node_opts: Vec<NodeOpts>,
+    ) -> (Self, impl futures::Future<Output = Result<(), NodeError>>) {
+        let (changes, changes_recv) = watch::channel(());
+        let (network, network_recv) = mpsc::unbounded_channel::<(u16, NodeRequest)>();
+        let clock = MockClock::new(START_TIMESTAMP);
+        let mut clients = Vec::new();
+        let mut fronts = HashMap::new();
+        let mut front_recvs = Vec::new();
+        let mut nodes = BTreeMap::new();
+        for opts in node_opts {
+            // Requests are sent to the front channel of a node, which is
+            // forwarded to the node as long as it is up. Requests of the
+            // scenario pass through the operator channel, and are served as
+            // admin requests.
+            let (front_send, front_recv) = mpsc::unbounded_channel::<NodeRequest>();
+            let (operator_send, operator_recv) = mpsc::unbounded_channel::<NodeRequest>();
+            let client = BazukaClient {
+                peer: peer_address(opts.addr),
+                sender: Arc::new(OutgoingSender {
+                    chan: front_send.clone(),
+                    network: "simulator".into(),
+                    admin_token: None,
+                    priv_key: opts.wallet.get_priv_key(),
+                }),
+                limit: None,
+                light: None,
+            };
+            clients.push(BazukaClient {
+                sender: Arc::new(OutgoingSender {
+                    chan: operator_send,
+                    network: "simulator".into(),
+                    admin_token: None,
+                    priv_key: opts.wallet.get_priv_key(),
+                }),
+                ..client.clone()
+            });
+            fronts.insert(opts.addr, client);
+            front_recvs.push((opts.addr, front_recv, operator_recv, front_send));
+            nodes.insert(
+                opts.addr,
+                SimNode {
+                    opts,
+                    disk: Disk::default(),
+                    up: true,
+                },
+            );
+        }
+        let state = Arc::new(RwLock::new(SimState {
+            rules: Vec::new(),
+            rng: ChaChaRng::seed_from_u64(seed),
+            trace: Vec::new(),
+            nodes,
+            delayed: BTreeMap::new(),
+            finished: false,
+        }));
+        let node_futs = front_recvs
+            .into_iter()
+            .map(|(id, front, operator, front_send)| {
+                let node = run_node(
+                    Arc::clone(&state),
+                    network.clone(),
+                    clock.clone(),
+                    changes_recv.clone(),
+                    id,
+                    front,
+                );
+                async move {
+                    tokio::select! {
+                        res = node => res,
+                        _ = operate(operator, front_send) => Ok(()),
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        let routing = route(
+            Arc::clone(&state),
+            Arc::new(fronts),
+            clock.clone(),
+            changes_recv,
+            network_recv,
+        );
+        (
+            Self {
+                clock,
+                state,
+                changes,
+                network,
+                clients,
+            },
+            async move {
+                tokio::join!(futures::future::try_join_all(node_futs), routing).0?;
+                Ok(())
+            },
+        )
+    }
+
+    /// Clients of the nodes, in the order they were given
+    pub fn clients(&self) -> Vec<BazukaClient> {
+        self.clients.clone()
+    }
+
+    /// Clients of the nodes, signing their requests with the given wallet
+    pub fn clients_as(&self, wallet: &TxBuilder) -> Vec<BazukaClient> {
+        self.clients
+            .iter()
+            .map(|c| BazukaClient {
+                sender: Arc::new(OutgoingSender {
+                    chan: c.sender.chan.clone(),
+                    network: c.sender.network.clone(),
+                    admin_token: None,
+                    priv_key: wallet.get_priv_key(),
+                }),
+                ..c.clone()
+            })
+            .collect()
+    }
+
+    /// Client of node `dst` whose requests pass through the router as if they
+    /// were sent by node `src`, so that scenarios can script the traffic
+    /// between two nodes
+    pub fn client_from(&self, src: u16, dst: u16) -> BazukaClient {
+        let sender = self
+            .clients
+            .iter()
+            .find(|c| c.peer == peer_address(src))
+            .expect("Unknown source node!");
+        let (chan, mut recv) = mpsc::unbounded_channel::<NodeRequest>();
+        let network = self.network.clone();
+        tokio::spawn(async move {
+            while let Some(req) = recv.recv().await {
+                if network.send((src, req)).is_err() {
+                    break;
+                }
+            }
+        });
+        BazukaClient {
+            peer: peer_address(dst),
+            sender: Arc::new(OutgoingSender {
+                chan,
+                network: sender.sender.network.clone(),
+                admin_token: None,
+                priv_key: sender.sender.priv_key.clone(),
+            }),
+            limit: None,
+            light: None,
+        }
+    }
+
+    pub async fn set_rules(&self, rules: Vec<Rule>) {
+        let mut state = self.state.write().await;
+        state.record(TraceEvent::RulesChanged { rules: rules.len() });
+        state.rules = rules;
+    }
+
+    /// Stops the node abruptly, its chain is kept for its restart
+    pub async fn crash(&self, node: u16) {
+        let mut state = self.state.write().await;
+        if let Some(n) = state.nodes.get_mut(&node) {
+            if n.up {
+                n.up = false;
+                state.record(TraceEvent::Crashed { node });
+                let _ = self.changes.send(());
+            }
+        }
+    }
+
+    pub async fn restart(&self, node: u16) {
+        let mut state = self.state.write().await;
+        if let Some(n) = state.nodes.get_mut(&node) {
+            if !n.up {
+                n.up = true;
+                let timestamp_offset = n.opts.timestamp_offset;
+                state.record(TraceEvent::Restarted {
+                    node,
+                    timestamp_offset,
+                });
+                let _ = self.changes.send(());
+            }
+        }
+    }
+
+    /// Changes the clock skew of a node, applied when the node restarts
+    pub async fn set_clock_skew(&self, node: u16, timestamp_offset: i32) {
+        if let Some(n) = self.state.write().await.nodes.get_mut(&node) {
+            n.opts.timestamp_offset = timestamp_offset;
+        }
+    }
+
+    /// Current timestamp of the shared clock, without the skews of the nodes
+    pub fn now(&self) -> u32 {
+        self.clock.now()
+    }
+
+    /// Moves the shared clock of the nodes forward, delivers the delayed
+    /// requests which have become due and lets the nodes run for that long
+    pub async fn advance_clock(&self, secs: u32) {
+        {
+            let mut state = self.state.write().await;
+            self.clock.advance(secs);
+            state.record(TraceEvent::ClockAdvanced { secs });
+        }
+        let _ = self.changes.send(());
+        sleep(Duration::from_secs(secs.into())).await;
+    }
+
+    pub async fn trace(&self) -> Vec<TraceEntry> {
+        self.state.read().await.trace.clone()
+    }
+
+    /// Stops all of the nodes and ends the simulation
+    pub async fn finish(&self) {
+        self.state.write().await.finished = true;
+        let _ = self.changes.send(());
+    }
+}
+
+async fn run_node(
+    state: Arc<RwLock<SimState>>,
+    network: Network,
+    clock: MockClock,
+    mut changes: watch::Receiver<()>,
+    id: u16,
+    mut front: mpsc::UnboundedReceiver<NodeRequest>,
+) -> Result<(), NodeError> {
+    // Requests which have arrived while the node was restarting
+    let mut pending = Vec::new();
+    loop {
+        changes.borrow_and_update();
+        let incarnation = {
+            let state = state.read().await;
+            if state.finished {
+                return Ok(());
+            }
+            let node = &state.nodes[&id];
+            node.up.then(|| (node.opts.clone(), node.disk.clone()))
+        };
+
+        let (opts, disk) = match incarnation {
+            Some(incarnation) => incarnation,
+            None => {
+                // Requests to a crashed node are never answered
+                tokio::select! {
+                    req = front.recv() => match req {
+                        Some(req) => if state.read().await.is_up(id) {
+                            pending.push(req);
+                        },
+                        None => {
+                            return Ok(());
+                        }
+                    },
+                    res = changes.changed() => if res.is_err() {
+                        return Ok(());
+                    },
+                }
+                continue;
+            }
+        };
+
+        let chain = KvStoreChain::new(disk, opts.config.clone())?;
+        let (inc_send, inc_recv) = mpsc::unbounded_channel::<NodeRequest>();
+        let (out_send, out_recv) = mpsc::unbounded_channel::<NodeRequest>();
+        let mut simulator_options = config::node::get_simulator_options();
+        simulator_options.automatic_block_generation = opts.auto_gen_block;
+        let node = node_create(
+            simulator_options,
+            "simulator",
+            Some(peer_address(id)),
+            opts.bootstrap.iter().map(|p| peer_address(*p)).collect(),
+            chain,
+            Arc::new(clock.clone()),
+            opts.timestamp_offset,
+            opts.wallet.clone(),
+            opts.wallet.clone(),
+            SocialProfiles::default(),
+            inc_recv,
+            out_send,
+            None,
+            MpnWorkerRegistry::new(opts.mpn_workers.clone()),
+        );
+        let routing = forward(network.clone(), id, out_recv);
+        for req in pending.drain(..) {
+            let _ = inc_send.send(req);
+        }
+        let forwarding = async {
+            while let Some(req) = front.recv().await {
+                if !state.read().await.is_up(id) {
+                    continue;
+                }
+                if inc_send.send(req).is_err() {
+                    // Node is stopping, which is handled below
+                    futures::future::pending::<()>().await;
+                }
+            }
+        };
+        let crashing = async {
+            while changes.changed().await.is_ok() {
+                let state = state.read().await;
+                if state.finished || !state.is_up(id) {
+                    break;
+                }
+            }
+        };
+
+        tokio::select! {
+            res = async { tokio::join!(node, routing).0 } => {
+                res?;
+                // Node is shut down through its API
+                if let Some(n) = state.write().await.nodes.get_mut(&id) {
+                    n.up = false;
+                }
+            }
+            _ = forwarding => {
+                return Ok(());
+            }
+            _ = crashing => {}
+        }
+    }
+}
+
+/// Marks the requests of the scenario as admin requests, never returns
+async fn operate(
+    mut operator: mpsc::UnboundedReceiver<NodeRequest>,
+    front: mpsc::UnboundedSender<NodeRequest>,
+) {
+    while let Some(mut req) = operator.recv().await {
+        req.admin = true;
+        if front.send(req).is_err() {
+            break;
+        }
+    }
+    futures::future::pending::<()>().await;
+}
+
+/// Hands the requests of a node to the router
+async fn forward(network: Network, src: u16, mut outgoing: mpsc::UnboundedReceiver<NodeRequest>) {
+    while let Some(req) = outgoing.recv().await {
+        if network.send((src, req)).is_err() {
+            break;
+        }
+    }
+}
+
+/// Routes the requests of all nodes one at a time, so that the rules, and the
+/// random numbers they draw, see them in a single order
+async fn route(
+    state: Arc<RwLock<SimState>>,
+    fronts: Fronts,
+    clock: MockClock,
+    mut changes: watch::Receiver<()>,
+    mut network: mpsc::UnboundedReceiver<(u16, NodeRequest)>,
+) {
+    loop {
+        tokio::select! {
+            biased;
+            req = network.recv() => match req {
+                Some((src, req)) => {
+                    let mut state = state.write().await;
+                    if let Some(delivery) = state.route(clock.now(), src, req) {
+                        state.deliver(&fronts, delivery);
+                    }
+                }
+                None => break,
+            },
+            res = changes.changed() => {
+                let mut state = state.write().await;
+                if res.is_err() || state.finished {
+                    break;
+                }
+                for delivery in state.take_due(clock.now()) {
+                    state.deliver(&fronts, delivery);
+                }
+            }
+        }
+    }
+}
@@ -1,14 +1,11 @@
 use super::*;
 
-mod simulation;
-use simulation::*;
+use super::simulation::*;
 
 use crate::config::blockchain;
+use crate::mpn::MpnWorker;
 use crate::zk;
-use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
-use tokio::time::sleep;
 
 fn init() {
     let _ = env_logger::builder().is_test(true).try_init();
@@ -16,14 +13,18 @@ fn init() {
 
 const MAX_WAIT_FOR_CHANGE: usize = 20;
 
-async fn catch_change<F: Fn() -> Fut, T, Fut>(f: F, timeout: usize) -> Result<T, NodeError>
+async fn catch_change<F: Fn() -> Fut, T, Fut>(
+    sim: &Simulator,
+    f: F,
+    timeout: usize,
+) -> Result<T, NodeError>
 where
     Fut: futures::Future<Output = Result<T, NodeError>>,
     T: std::fmt::Display + PartialEq,
 {
     let prev_val = f().await?;
     for _ in 0..timeout {
-        sleep(Duration::from_secs(1)).await;
+        sim.advance_clock(1).await;
         let new_val = f().await?;
         if new_val != prev_val {
             return Ok(new_val);
@@ -32,15 +33,14 @@ where
     Ok(prev_val)
 }
 
-#[tokio::test]
+#[tokio::test(start_paused = true)]
 async fn test_peers_find_each_other() -> Result<(), NodeError> {
     init();
 
-    let rules = Arc::new(RwLock::new(Vec::new()));
     let conf = blockchain::get_test_blockchain_config();
 
-    let (node_futs, route_futs, chans) = simulation::test_network(
-        Arc::clone(&rules),
+    let (sim, sim_fut) = Simulator::new(
+        0,
         vec![
             NodeOpts {
                 config: conf.clone(),
@@ -71,9 +71,11 @@ async fn test_peers_find_each_other() -> Result<(), NodeError> {
             },
         ],
     );
+    let chans = sim.clients();
     let test_logic = async {
         assert!(
             catch_change(
+                &sim,
                 || async {
                     let mut peer_counts = Vec::new();
                     for chan in chans.iter() {
@@ -86,24 +88,21 @@ async fn test_peers_find_each_other() -> Result<(), NodeError> {
             .await?
         );
 
-        for chan in chans.iter() {
-            chan.shutdown().await?;
-        }
+        sim.finish().await;
         Ok::<(), NodeError>(())
     };
-    tokio::try_join!(node_futs, route_futs, test_logic)?;
+    tokio::try_join!(sim_fut, test_logic)?;
     Ok(())
 }
 
-#[tokio::test]
+#[tokio::test(start_paused = true)]
 async fn test_timestamps_are_sync() -> Result<(), NodeError> {
     init();
 
-    let rules = Arc::new(RwLock::new(Vec::new()));
     let conf = blockchain::get_test_blockchain_config();
 
-    let (node_futs, route_futs, chans) = simulation::test_network(
-        Arc::clone(&rules),
+    let (sim, sim_fut) = Simulator::new(
+        0,
         vec![
             NodeOpts {
                 config: conf.clone(),
@@ -134,9 +133,11 @@ async fn test_timestamps_are_sync() -> Result<(), NodeError> {
             },
         ],
     );
+    let chans = sim.clients();
     let test_logic = async {
         assert!(
             catch_change(
+                &sim,
                 || async {
                     let mut timestamps = Vec::new();
                     for chan in chans.iter() {
@@ -150,27 +151,22 @@ async fn test_timestamps_are_sync() -> Result<(), NodeError> {
             .await?
         );
 
-        for chan in chans.iter() {
-            chan.shutdown().await?;
-        }
+        sim.finish().await;
         Ok::<(), NodeError>(())
     };
-    tokio::try_join!(node_futs, route_futs, test_logic)?;
+    tokio::try_join!(sim_fut, test_logic)?;
     Ok(())
 }
 
-#[tokio::test]
+#[tokio::test(start_paused = true)]
 async fn test_blocks_get_synced() -> Result<(), NodeError> {
     init();
 
-    // Allow sync of clocks but no block transfer
-    let rules = Arc::new(RwLock::new(vec![]));
-
     let mut conf = blockchain::get_test_blockchain_config();
     conf.slot_duration = 1;
 
-    let (node_futs, route_futs, chans) = simulation::test_network(
-        Arc::clone(&rules),
+    let (sim, sim_fut) = Simulator::new(
+        0,
         vec![
             NodeOpts {
                 config: conf.clone(),
@@ -192,40 +188,43 @@ async fn test_blocks_get_synced() -> Result<(), NodeError> {
             },
         ],
     );
+    let chans = sim.clients();
     let test_logic = async {
         // Wait till clocks sync
-        sleep(Duration::from_millis(1000)).await;
+        sim.advance_clock(1).await;
 
-        *rules.write().await = vec![Rule::drop_all()];
+        // No block transfer from now on
+        sim.set_rules(vec![Rule::drop_all()]).await;
 
         chans[0].mine().await?;
         assert_eq!(chans[0].stats().await?.height, 2);
 
-        sleep(Duration::from_millis(1000)).await;
+        sim.advance_clock(1).await;
 
         chans[0].mine().await?;
         assert_eq!(chans[0].stats().await?.height, 3);
 
-        sleep(Duration::from_millis(1000)).await;
+        sim.advance_clock(1).await;
 
         chans[0].mine().await?;
         assert_eq!(chans[0].stats().await?.height, 4);
 
         for i in 2..11 {
-            sleep(Duration::from_millis(1000)).await;
+            sim.advance_clock(1).await;
             chans[1].mine().await?;
             assert_eq!(chans[1].stats().await?.height, i);
         }
 
         // Still not synced...
-        sleep(Duration::from_millis(2000)).await;
+        sim.advance_clock(2).await;
         assert_eq!(chans[0].stats().await?.height, 4);
         assert_eq!(chans[1].stats().await?.height, 10);
 
         // Now we open the connections...
-        rules.write().await.clear();
+        sim.set_rules(vec![]).await;
         assert!(
             catch_change(
+                &sim,
                 || async { Ok(chans[0].stats().await?.height == 10) },
                 MAX_WAIT_FOR_CHANGE
             )
@@ -234,32 +233,30 @@ async fn test_blocks_get_synced() -> Result<(), NodeError> {
         assert_eq!(chans[1].stats().await?.height, 10);
 
         // Now nodes should immediately sync with post_block
+        sim.advance_clock(1).await;
         chans[1].mine().await?;
         assert_eq!(chans[1].stats().await?.height, 11);
         assert!(
             catch_change(
+                &sim,
                 || async { Ok(chans[0].stats().await?.height == 11) },
                 MAX_WAIT_FOR_CHANGE
             )
             .await?,
         );
 
-        for chan in chans.iter() {
-            chan.shutdown().await?;
-        }
+        sim.finish().await;
 
         Ok::<(), NodeError>(())
     };
-    tokio::try_join!(node_futs, route_futs, test_logic)?;
+    tokio::try_join!(sim_fut, test_logic)?;
     Ok(())
 }
 
-#[tokio::test]
+#[tokio::test(start_paused = true)]
 async fn test_auto_block_production() -> Result<(), NodeError> {
     init();
 
-    let rules = Arc::new(RwLock::new(vec![]));
-
     let mut conf = blockchain::get_test_blockchain_config();
     conf.slot_duration = 2;
     conf.mpn_config.mpn_num_deposit_batches = 1;
@@ -271,8 +268,8 @@ async fn test_auto_block_production() -> Result<(), NodeError> {
     let val2 = TxBuilder::new(&Vec::from("VALIDATOR2"));
     let val3 = TxBuilder::new(&Vec::from("VALIDATOR3"));
 
-    let (node_futs, route_futs, chans) = simulation::test_network(
-        Arc::clone(&rules),
+    let (sim, sim_fut) = Simulator::new(
+        0,
         vec![
             NodeOpts {
                 config: conf.clone(),
@@ -309,13 +306,18 @@ async fn test_auto_block_production() -> Result<(), NodeError> {
             },
         ],
     );
+    let chans = sim.clients();
+    let worker_chans = sim.clients_as(&abc);
     let test_logic = async {
         let mut height = 1;
         for _ in 0..5 {
             let next_height = catch_change(
+                &sim,
                 || async {
-                    // Continuously post dummy proofs to all validators to ensure block production
-                    for ch in chans.iter() {
+                    // Let the slots pass and continuously post dummy proofs to
+                    // all validators to ensure block production
+                    sim.advance_clock(1).await;
+                    for ch in worker_chans.iter() {
                         ch.post_mpn_proof(
                             abc.get_address(),
                             [
@@ -337,12 +339,195 @@ async fn test_auto_block_production() -> Result<(), NodeError> {
             height = next_height;
         }
 
-        for chan in chans.iter() {
-            chan.shutdown().await?;
-        }
+        sim.finish().await;
 
         Ok::<(), NodeError>(())
     };
-    tokio::try_join!(node_futs, route_futs, test_logic)?;
+    tokio::try_join!(sim_fut, test_logic)?;
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_partition_and_crash() -> Result<(), NodeError> {
+    init();
+
+    let mut conf = blockchain::get_test_blockchain_config();
+    conf.slot_duration = 1;
+
+    let (sim, sim_fut) = Simulator::new(
+        0,
+        vec![
+            NodeOpts {
+                config: conf.clone(),
+                wallet: TxBuilder::new(&Vec::from("VALIDATOR")),
+                addr: 120,
+                bootstrap: vec![],
+                timestamp_offset: 0,
+                auto_gen_block: false,
+                mpn_workers: vec![],
+            },
+            NodeOpts {
+                config: conf.clone(),
+                wallet: TxBuilder::new(&Vec::from("VALIDATOR2")),
+                addr: 121,
+                bootstrap: vec![120],
+                timestamp_offset: 0,
+                auto_gen_block: false,
+                mpn_workers: vec![],
+            },
+        ],
+    );
+    let chans = sim.clients();
+    let test_logic = async {
+        sim.advance_clock(1).await;
+
+        sim.set_rules(Rule::partition(vec![120], vec![121])).await;
+        sim.advance_clock(1).await;
+        chans[0].mine().await?;
+        assert_eq!(chans[0].stats().await?.height, 2);
+
+        // Crashed node keeps its chain
+        sim.crash(120).await;
+        assert!(chans[0].stats().await.is_err());
+        sim.set_clock_skew(120, 3).await;
+        sim.restart(120).await;
+        assert_eq!(chans[0].stats().await?.height, 2);
+        assert_eq!(chans[0].stats().await?.timestamp_offset, 3);
+
+        sim.advance_clock(2).await;
+        assert_eq!(chans[1].stats().await?.height, 1);
+
+        // Partition is healed
+        sim.set_rules(vec![]).await;
+        assert!(
+            catch_change(
+                &sim,
+                || async { Ok(chans[1].stats().await?.height == 2) },
+                MAX_WAIT_FOR_CHANGE
+            )
+            .await?,
+        );
+
+        let trace = sim
+            .trace()
+            .await
+            .into_iter()
+            .map(|e| e.event)
+            .collect::<Vec<_>>();
+        assert!(trace.contains(&TraceEvent::ClockAdvanced { secs: 1 }));
+        assert!(trace.contains(&TraceEvent::Crashed { node: 120 }));
+        assert!(trace.contains(&TraceEvent::Restarted {
+            node: 120,
+            timestamp_offset: 3
+        }));
+        assert!(trace.iter().any(|e| matches!(
+            e,
+            TraceEvent::Dropped {
+                from: 121,
+                to: 120,
+                ..
+            }
+        )));
+
+        sim.finish().await;
+        Ok::<(), NodeError>(())
+    };
+    tokio::try_join!(sim_fut, test_logic)?;
+    Ok(())
+}
+
+async fn scripted_trace(seed: u64) -> Result<Vec<TraceEntry>, NodeError> {
+    let conf = blockchain::get_test_blockchain_config();
+
+    // Nodes do not know each other, so all of the traffic is scripted
+    let (sim, sim_fut) = Simulator::new(
+        seed,
+        vec![
+            NodeOpts {
+                config: conf.clone(),
+                wallet: TxBuilder::new(&Vec::from("VALIDATOR")),
+                addr: 120,
+                bootstrap: vec![],
+                timestamp_offset: 0,
+                auto_gen_block: false,
+                mpn_workers: vec![],
+            },
+            NodeOpts {
+                config: conf.clone(),
+                wallet: TxBuilder::new(&Vec::from("VALIDATOR2")),
+                addr: 121,
+                bootstrap: vec![],
+                timestamp_offset: 0,
+                auto_gen_block: false,
+                mpn_workers: vec![],
+            },
+        ],
+    );
+    let addr = TxBuilder::new(&Vec::from("ABC")).get_address();
+    let test_logic = async {
+        sim.set_rules(vec![
+            Rule::drop_rate(Endpoint::Peer(121), Endpoint::Peer(120), 0.5),
+            Rule::latency(
+                Endpoint::Peer(120),
+                Endpoint::Peer(121),
+                Duration::from_millis(1500),
+            ),
+        ])
+        .await;
+
+        let from_121 = sim.client_from(121, 120);
+        for _ in 0..16 {
+            let _ = from_121.get_account(addr.clone()).await;
+        }
+
+        let from_120 = sim.client_from(120, 121);
+        for i in 0..4 {
+            let (resp, _) = tokio::join!(from_120.get_account(addr.clone()), async {
+                // Delayed requests are only delivered once the clock passes
+                // their latency
+                while sim
+                    .trace()
+                    .await
+                    .iter()
+                    .filter(|e| matches!(e.event, TraceEvent::Delayed { .. }))
+                    .count()
+                    <= i
+                {
+                    tokio::task::yield_now().await;
+                }
+                sim.advance_clock(2).await;
+            });
+            resp?;
+        }
+
+        let trace = sim.trace().await;
+        sim.finish().await;
+        Ok::<_, NodeError>(trace)
+    };
+    let (_, trace) = tokio::try_join!(sim_fut, test_logic)?;
+    Ok(trace)
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_same_seed_gives_same_trace() -> Result<(), NodeError> {
+    init();
+
+    let trace = scripted_trace(123).await?;
+    assert_eq!(trace, scripted_trace(123).await?);
+
+    let events = trace.into_iter().map(|e| e.event).collect::<Vec<_>>();
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, TraceEvent::Dropped { from: 121, .. })));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, TraceEvent::Delivered { from: 121, .. })));
+    assert_eq!(
+        events
+            .iter()
+            .filter(|e| matches!(e, TraceEvent::Delivered { from: 120, .. }))
+            .count(),
+        4
+    );
     Ok(())
 }
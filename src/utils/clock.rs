@@ -0,0 +1,54 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// Source of the local time, in seconds since the unix epoch
+pub trait Clock: std::fmt::Debug + Send + Sync {
+    fn now(&self) -> u32;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u32 {
+        super::local_timestamp()
+    }
+}
+
+/// A clock which only moves when it is told to. Clones share the same time.
+#[derive(Debug, Clone, Default)]
+pub struct MockClock(Arc<AtomicU32>);
+
+impl MockClock {
+    pub fn new(now: u32) -> Self {
+        Self(Arc::new(AtomicU32::new(now)))
+    }
+    pub fn set(&self, now: u32) {
+        self.0.store(now, Ordering::SeqCst);
+    }
+    pub fn advance(&self, secs: u32) {
+        self.0.fetch_add(secs, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> u32 {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock() {
+        let clock = MockClock::new(100);
+        let shared: Arc<dyn Clock> = Arc::new(clock.clone());
+        assert_eq!(shared.now(), 100);
+        clock.advance(20);
+        assert_eq!(shared.now(), 120);
+        clock.set(10);
+        assert_eq!(shared.now(), 10);
+    }
+}
@@ -1,5 +1,8 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
+mod clock;
+pub use clock::*;
+
 pub fn local_timestamp() -> u32 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -1,7 +1,7 @@
 mod tx_builder;
 pub use tx_builder::TxBuilder;
 
-use crate::core::{ContractId, GeneralTransaction, NonceGroup};
+use crate::core::{ContractDeposit, ContractId, GeneralTransaction, NonceGroup};
 
 use bip39::Mnemonic;
 use rand_core_mnemonic::{CryptoRng, RngCore};
@@ -42,12 +42,54 @@ pub enum WalletError {
     BlockchainError(#[from] io::Error),
 }
 
+/// Prefix of the wallet files written since the deposit nonces are kept.
+/// Files without it are read in the legacy format.
+const WALLET_FILE_MAGIC: &[u8; 8] = b"BZKWLT01";
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct WalletCollection {
     mnemonic: Mnemonic,
     wallets: HashMap<WalletType, Wallet>,
 }
 
+#[derive(Deserialize)]
+struct LegacyWalletCollection {
+    mnemonic: Mnemonic,
+    wallets: HashMap<WalletType, LegacyWallet>,
+}
+
+#[derive(Deserialize)]
+struct LegacyWallet {
+    mnemonic: Mnemonic,
+    wallet_type: WalletType,
+    tokens: Vec<ContractId>,
+    txs: HashMap<NonceGroup, Vec<GeneralTransaction>>,
+}
+
+impl From<LegacyWalletCollection> for WalletCollection {
+    fn from(legacy: LegacyWalletCollection) -> Self {
+        Self {
+            mnemonic: legacy.mnemonic,
+            wallets: legacy
+                .wallets
+                .into_iter()
+                .map(|(k, w)| {
+                    (
+                        k,
+                        Wallet {
+                            mnemonic: w.mnemonic,
+                            wallet_type: w.wallet_type,
+                            tokens: w.tokens,
+                            txs: w.txs,
+                            deposit_nonces: HashMap::new(),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
 impl WalletCollection {
     pub fn mnemonic(&self) -> &Mnemonic {
         &self.mnemonic
@@ -74,13 +116,18 @@ impl WalletCollection {
         if let Ok(mut f) = File::open(&path) {
             let mut bytes = Vec::new();
             f.read_to_end(&mut bytes)?;
-            Ok(Some(bincode::deserialize(&bytes)?))
+            Ok(Some(match bytes.strip_prefix(WALLET_FILE_MAGIC) {
+                Some(bytes) => bincode::deserialize(bytes)?,
+                None => bincode::deserialize::<LegacyWalletCollection>(&bytes)?.into(),
+            }))
         } else {
             Ok(None)
         }
     }
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), WalletError> {
-        File::create(path)?.write_all(&bincode::serialize(self)?)?;
+        let mut bytes = WALLET_FILE_MAGIC.to_vec();
+        bytes.extend(bincode::serialize(self)?);
+        File::create(path)?.write_all(&bytes)?;
         Ok(())
     }
 }
@@ -91,12 +138,16 @@ pub struct Wallet {
     pub wallet_type: WalletType,
     pub tokens: Vec<ContractId>,
     pub txs: HashMap<NonceGroup, Vec<GeneralTransaction>>,
+    /// Nonces of the last deposits signed for each contract, which are not
+    /// kept in the mempool
+    pub deposit_nonces: HashMap<ContractId, u32>,
 }
 
 impl Wallet {
     pub fn new(wallet_type: WalletType, mnemonic: Mnemonic) -> Self {
         Self {
             txs: HashMap::new(),
+            deposit_nonces: HashMap::new(),
             tokens: vec![ContractId::Ziesha],
             wallet_type,
             mnemonic,
@@ -120,6 +171,7 @@ impl Wallet {
         self.txs.iter_mut().for_each(|(_, v)| {
             *v = Vec::new();
         });
+        self.deposit_nonces.clear();
     }
     pub fn add_tx(&mut self, tx: GeneralTransaction) {
         self.txs.entry(tx.nonce_group()).or_default().push(tx);
@@ -135,4 +187,68 @@ impl Wallet {
             None
         }
     }
+    pub fn add_deposit(&mut self, deposit: &ContractDeposit) {
+        let nonce = self.deposit_nonces.entry(deposit.contract_id).or_default();
+        *nonce = std::cmp::max(*nonce, deposit.nonce);
+    }
+    pub fn new_deposit_nonce(&self, contract_id: ContractId) -> Option<u32> {
+        self.deposit_nonces.get(&contract_id).map(|n| n + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Money;
+
+    #[test]
+    fn test_wallet_deposit_nonces_are_persisted() {
+        let path = std::env::temp_dir().join(format!("bazuka-wallet-{}", rand::random::<u64>()));
+        let mut wallets = WalletCollection::create(&mut rand_mnemonic::thread_rng(), None);
+
+        // Wallets written before the deposit nonces were kept
+        let legacy = bincode::serialize(&(
+            wallets.mnemonic.clone(),
+            [(
+                WalletType::User(0),
+                (
+                    wallets.mnemonic.clone(),
+                    WalletType::User(0),
+                    vec![ContractId::Ziesha],
+                    HashMap::<NonceGroup, Vec<GeneralTransaction>>::new(),
+                ),
+            )]
+            .into_iter()
+            .collect::<HashMap<_, _>>(),
+        ))
+        .unwrap();
+        std::fs::write(&path, legacy).unwrap();
+        let mut opened = WalletCollection::open(&path).unwrap().unwrap();
+        assert_eq!(opened.mnemonic, wallets.mnemonic);
+        assert_eq!(opened.user(0).get_tokens(), &[ContractId::Ziesha]);
+
+        let contract_id = ContractId::Custom(123.into());
+        assert_eq!(wallets.user(0).new_deposit_nonce(contract_id), None);
+        let tx_builder = wallets.user(0).tx_builder();
+        for nonce in [3, 2] {
+            let deposit = tx_builder.deposit_contract(
+                "".into(),
+                contract_id,
+                0,
+                Default::default(),
+                nonce,
+                Money::ziesha(10),
+                Money::ziesha(0),
+            );
+            wallets.user(0).add_deposit(&deposit);
+        }
+        wallets.save(&path).unwrap();
+        let mut opened = WalletCollection::open(&path).unwrap().unwrap();
+        assert_eq!(opened.user(0).new_deposit_nonce(contract_id), Some(4));
+        assert_eq!(opened.user(0).new_deposit_nonce(ContractId::Ziesha), None);
+        opened.user(0).reset();
+        assert_eq!(opened.user(0).new_deposit_nonce(contract_id), None);
+
+        std::fs::remove_file(&path).unwrap();
+    }
 }
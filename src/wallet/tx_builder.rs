@@ -2,9 +2,9 @@
 use crate::client::{messages::ValidatorClaim, PeerAddress};
 
 use crate::core::{
-    hash::Hash, Address, Amount, ContractDeposit, ContractId, ContractWithdraw, Hasher, Money,
-    MpnAddress, MpnDeposit, MpnWithdraw, Ratio, RegularSendEntry, Signature, Signer, Token,
-    Transaction, TransactionAndDelta, TransactionData, ValidatorProof, Vrf, ZkSigner,
+    hash::Hash, Address, Amount, ContractDeposit, ContractId, ContractUpdate, ContractWithdraw,
+    Hasher, Money, MpnAddress, MpnDeposit, MpnWithdraw, Ratio, RegularSendEntry, Signature, Signer,
+    Token, Transaction, TransactionAndDelta, TransactionData, ValidatorProof, Vrf, ZkSigner,
 };
 use crate::crypto::SignatureScheme;
 use crate::crypto::VerifiableRandomFunction;
@@ -332,6 +332,82 @@ impl TxBuilder {
         }
     }
 
+    pub fn update_contract(
+        &self,
+        memo: String,
+        contract_id: ContractId,
+        updates: Vec<ContractUpdate>,
+        delta: zk::ZkDeltaPairs,
+        fee: Money,
+        nonce: u32,
+    ) -> TransactionAndDelta {
+        let mut tx = Transaction {
+            memo,
+            src: Some(self.get_address()),
+            data: TransactionData::UpdateContract {
+                contract_id,
+                updates,
+                delta: Some(delta.clone()),
+            },
+            nonce,
+            fee,
+            sig: Signature::Unsigned,
+        };
+        self.sign_tx(&mut tx);
+        TransactionAndDelta {
+            tx,
+            state_delta: Some(delta),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn deposit_contract(
+        &self,
+        memo: String,
+        contract_id: ContractId,
+        deposit_circuit_id: u32,
+        calldata: zk::ZkScalar,
+        nonce: u32,
+        amount: Money,
+        fee: Money,
+    ) -> ContractDeposit {
+        let mut tx = ContractDeposit {
+            memo,
+            src: self.get_address(),
+            contract_id,
+            deposit_circuit_id,
+            calldata,
+            nonce,
+            amount,
+            fee,
+            sig: None,
+        };
+        self.sign_deposit(&mut tx);
+        tx
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn withdraw_contract(
+        &self,
+        memo: String,
+        contract_id: ContractId,
+        withdraw_circuit_id: u32,
+        calldata: zk::ZkScalar,
+        amount: Money,
+        fee: Money,
+        to: <Signer as SignatureScheme>::Pub,
+    ) -> ContractWithdraw {
+        ContractWithdraw {
+            memo,
+            dst: to,
+            contract_id,
+            withdraw_circuit_id,
+            calldata,
+            amount,
+            fee,
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn deposit_mpn(
         &self,
@@ -354,21 +430,17 @@ impl TxBuilder {
                 .into(),
             ))
             .unwrap();
-        let mut tx = ContractDeposit {
-            memo,
-            src: self.get_address(),
-            contract_id,
-            deposit_circuit_id: 0,
-            calldata: calldata_builder.compress().unwrap().state_hash,
-            nonce,
-            amount,
-            fee,
-            sig: None,
-        };
-        self.sign_deposit(&mut tx);
         MpnDeposit {
             mpn_address: to.pub_key,
-            payment: tx,
+            payment: self.deposit_contract(
+                memo,
+                contract_id,
+                0,
+                calldata_builder.compress().unwrap().state_hash,
+                nonce,
+                amount,
+                fee,
+            ),
         }
     }
 
@@ -382,15 +454,15 @@ impl TxBuilder {
         fee: Money,
         to: <Signer as SignatureScheme>::Pub,
     ) -> MpnWithdraw {
-        let mut tx = ContractWithdraw {
+        let mut tx = self.withdraw_contract(
             memo,
-            dst: to,
             contract_id,
-            withdraw_circuit_id: 0,
-            calldata: zk::ZkScalar::default(),
+            0,
+            zk::ZkScalar::default(),
             amount,
             fee,
-        };
+            to,
+        );
         let sig = ZkSigner::sign(
             &self.zk_private_key,
             crate::core::ZkHasher::hash(&[tx.fingerprint(), zk::ZkScalar::from(nonce as u64)]),
@@ -64,6 +64,29 @@ impl From<bellman::groth16::VerifyingKey<Bls12>> for Groth16VerifyingKey {
     }
 }
 
+impl From<bellman::groth16::Proof<Bls12>> for Groth16Proof {
+    fn from(proof: bellman::groth16::Proof<Bls12>) -> Self {
+        unsafe {
+            Self {
+                a: std::mem::transmute::<BellmanG1, (Fp, Fp, bool)>(proof.a),
+                b: std::mem::transmute::<BellmanG2, ((Fp, Fp), (Fp, Fp), bool)>(proof.b),
+                c: std::mem::transmute::<BellmanG1, (Fp, Fp, bool)>(proof.c),
+            }
+        }
+    }
+}
+
+/// Public inputs of the contract circuits: commitment, height, previous
+/// state, aux data and next state
+const NUM_PUBLIC_INPUTS: usize = 5;
+
+impl Groth16VerifyingKey {
+    /// Whether the key is able to verify proofs of contract circuits
+    pub fn is_valid(&self) -> bool {
+        self.ic.len() == NUM_PUBLIC_INPUTS + 1
+    }
+}
+
 pub fn groth16_verify(
     vk: &Groth16VerifyingKey,
     commitment: ZkScalar,
@@ -119,3 +142,33 @@ pub fn groth16_verify(
     )
     .is_ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verifying_key(num_inputs: usize) -> Groth16VerifyingKey {
+        Groth16VerifyingKey {
+            alpha_g1: Default::default(),
+            beta_g1: Default::default(),
+            beta_g2: Default::default(),
+            gamma_g2: Default::default(),
+            delta_g1: Default::default(),
+            delta_g2: Default::default(),
+            ic: vec![Default::default(); num_inputs + 1],
+        }
+    }
+
+    #[test]
+    fn test_verifying_key_is_valid() {
+        assert!(verifying_key(NUM_PUBLIC_INPUTS).is_valid());
+        assert!(!verifying_key(0).is_valid());
+        assert!(!verifying_key(NUM_PUBLIC_INPUTS - 1).is_valid());
+        assert!(!verifying_key(NUM_PUBLIC_INPUTS + 1).is_valid());
+        assert!(!Groth16VerifyingKey {
+            ic: vec![],
+            ..verifying_key(NUM_PUBLIC_INPUTS)
+        }
+        .is_valid());
+    }
+}
@@ -112,6 +112,80 @@ impl MpnAccount {
         }
         None
     }
+    /// Leaf of the account in the accounts tree of the MPN contract
+    pub fn hash<H: ZkHasher>(&self, log4_token_capacity: u8) -> ZkScalar {
+        H::hash(&[
+            ZkScalar::from(self.tx_nonce as u64),
+            ZkScalar::from(self.withdraw_nonce as u64),
+            self.address.0,
+            self.address.1,
+            self.tokens_hash::<H>(log4_token_capacity),
+        ])
+    }
+}
+
+/// Root of a list, given one of its items and the siblings returned by
+/// `KvStoreStateManager::prove`. Returns `None` if the index does not fit in
+/// a list of that depth.
+pub fn list_root<H: ZkHasher>(
+    mut value: ZkScalar,
+    mut index: u64,
+    proof: &[[ZkScalar; 3]],
+) -> Option<ZkScalar> {
+    for siblings in proof {
+        let pos = (index % 4) as usize;
+        let mut vals = siblings[..pos].to_vec();
+        vals.push(value);
+        vals.extend_from_slice(&siblings[pos..]);
+        value = H::hash(&vals);
+        index /= 4;
+    }
+    (index == 0).then_some(value)
+}
+
+/// Merkle proof of an account of the MPN contract. The account is proven
+/// against the state hash of the contract, and each of its tokens against
+/// the token sub-tree of the account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MpnAccountProof {
+    pub index: u64,
+    pub proof: Vec<[ZkScalar; 3]>,
+    pub token_proofs: HashMap<u64, Vec<[ZkScalar; 3]>>,
+}
+
+impl MpnAccountProof {
+    pub fn verify<H: ZkHasher>(
+        &self,
+        account: &MpnAccount,
+        state: &ZkCompressedState,
+        log4_tree_size: u8,
+        log4_token_tree_size: u8,
+    ) -> bool {
+        if self.proof.len() != log4_tree_size as usize
+            || self.token_proofs.len() != account.tokens.len()
+        {
+            return false;
+        }
+        let tokens_hash = account.tokens_hash::<H>(log4_token_tree_size);
+        for (ind, money) in account.tokens.iter() {
+            let token_proof = if let Some(p) = self.token_proofs.get(ind) {
+                p
+            } else {
+                return false;
+            };
+            let leaf = H::hash(&[money.token_id.into(), money.amount.into()]);
+            if token_proof.len() != log4_token_tree_size as usize
+                || list_root::<H>(leaf, *ind, token_proof) != Some(tokens_hash)
+            {
+                return false;
+            }
+        }
+        list_root::<H>(
+            account.hash::<H>(log4_token_tree_size),
+            self.index,
+            &self.proof,
+        ) == Some(state.state_hash)
+    }
 }
 
 // Amount is passed by default
@@ -181,7 +255,7 @@ pub fn check_proof(
                 false
             }
         }
-        #[cfg(test)]
+        #[cfg(any(test, feature = "dev-proofs"))]
         ZkVerifierKey::Dummy => {
             if let ZkProof::Dummy(result) = proof {
                 *result
@@ -511,7 +585,7 @@ impl ZkHasher for PoseidonHasher {
 }
 
 // Full state of a contract
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub struct ZkState {
     pub data: ZkDataPairs,
     pub rollbacks: Vec<ZkDeltaPairs>,
@@ -566,10 +640,27 @@ impl ZkCompressedState {
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ZkVerifierKey {
     Groth16(Box<groth16::Groth16VerifyingKey>),
-    #[cfg(test)]
+    #[cfg(any(test, feature = "dev-proofs"))]
     Dummy,
 }
 
+impl ZkVerifierKey {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            ZkVerifierKey::Groth16(vk) => vk.is_valid(),
+            #[cfg(any(test, feature = "dev-proofs"))]
+            ZkVerifierKey::Dummy => true,
+        }
+    }
+    pub fn is_dummy(&self) -> bool {
+        match self {
+            ZkVerifierKey::Groth16(_) => false,
+            #[cfg(any(test, feature = "dev-proofs"))]
+            ZkVerifierKey::Dummy => true,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ZkMultiInputVerifierKey {
     pub verifier_key: ZkVerifierKey,
@@ -643,10 +734,20 @@ pub struct ZkContract {
     pub token: Option<ZkTokenContract>,
 }
 
+impl ZkContract {
+    pub fn has_dummy_verifier_keys(&self) -> bool {
+        self.deposit_functions
+            .iter()
+            .chain(self.withdraw_functions.iter())
+            .any(|f| f.verifier_key.is_dummy())
+            || self.functions.iter().any(|f| f.verifier_key.is_dummy())
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ZkProof {
     Groth16(Box<groth16::Groth16Proof>),
-    #[cfg(test)]
+    #[cfg(any(test, feature = "dev-proofs"))]
     Dummy(bool),
 }
 
@@ -3,7 +3,7 @@ use thiserror::Error;
 use super::*;
 use crate::core::ContractId;
 use crate::crypto::jubjub;
-use crate::db::{keys, KvStore, KvStoreError, RamKvStore, WriteOp};
+use crate::db::{keys, KvStore, KvStoreError, RamKvStore, StringKey, WriteOp};
 use ff::Field;
 use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
@@ -89,6 +89,18 @@ impl<H: ZkHasher> ZkStateBuilder<H> {
     }
 }
 
+impl ZkDataPairs {
+    /// Compressed state of the data, when laid out with the given model
+    pub fn compress<H: ZkHasher>(
+        &self,
+        state_model: ZkStateModel,
+    ) -> Result<ZkCompressedState, StateManagerError> {
+        let mut builder = ZkStateBuilder::<H>::new(state_model);
+        builder.batch_set(&self.as_delta())?;
+        builder.compress()
+    }
+}
+
 impl<H: ZkHasher> KvStoreStateManager<H> {
     pub fn get_mpn_account<K: KvStore>(
         db: &K,
@@ -263,6 +275,67 @@ impl<H: ZkHasher> KvStoreStateManager<H> {
         }
     }
 
+    /// Returns the delta which brought the state of the contract to
+    /// `height`, and the compressed state right after applying it
+    pub fn delta_of<K: KvStore>(
+        db: &K,
+        id: ContractId,
+        height: u64,
+    ) -> Result<(ZkDeltaPairs, ZkCompressedState), StateManagerError> {
+        match (
+            db.get(keys::local_delta(&id, height))?,
+            db.get(keys::local_delta_root(&id, height))?,
+        ) {
+            (Some(delta), Some(root)) => Ok((delta.try_into()?, root.try_into()?)),
+            _ => Err(ZkError::DeltaNotFound.into()),
+        }
+    }
+
+    /// Forgets the delta which brought the state of the contract to `height`,
+    /// along with the compressed state right after it
+    pub fn forget_delta<K: KvStore>(
+        db: &mut K,
+        id: ContractId,
+        height: u64,
+    ) -> Result<(), StateManagerError> {
+        db.update(&[
+            WriteOp::Remove(keys::local_delta(&id, height)),
+            WriteOp::Remove(keys::local_delta_root(&id, height)),
+        ])?;
+        Ok(())
+    }
+
+    /// At most `count` non-zero scalars of the state of the contract, which
+    /// come after the locator `after` in the order of their db keys. Also
+    /// returns the locator to continue from, if there may be more scalars.
+    pub fn get_data_pairs<K: KvStore>(
+        db: &K,
+        id: ContractId,
+        after: Option<&ZkDataLocator>,
+        count: usize,
+    ) -> Result<(ZkDataPairs, Option<ZkDataLocator>), StateManagerError> {
+        let prefix = keys::local_scalar_value_prefix(&id);
+        let from: StringKey = match after {
+            Some(loc) => format!("{}-{}", prefix, loc),
+            None => prefix.clone(),
+        }
+        .into();
+        let mut data = ZkDataPairs::default();
+        let mut last = None;
+        for (k, v) in db
+            .pairs_from(prefix.clone().into(), from.clone())?
+            .into_iter()
+            .filter(|(k, _)| *k != from)
+            .take(count)
+        {
+            let loc = ZkDataLocator::from_str(&k.0[prefix.len() + 1..])?;
+            data.0.insert(loc.clone(), v.try_into()?);
+            last = Some(loc);
+        }
+        let next = if data.0.len() == count { last } else { None };
+        Ok((data, next))
+    }
+
     pub fn type_of<K: KvStore>(db: &K, id: ContractId) -> Result<ZkStateModel, StateManagerError> {
         let cont: ZkContract = db
             .get(keys::contract(&id))?
@@ -302,6 +375,8 @@ impl<H: ZkHasher> KvStoreStateManager<H> {
         fork.update(&[
             WriteOp::Put(keys::local_root(&id), root.into()),
             WriteOp::Put(keys::local_height(&id), target_height.into()),
+            WriteOp::Put(keys::local_delta(&id, target_height), patch.into()),
+            WriteOp::Put(keys::local_delta_root(&id, target_height), root.into()),
         ])?;
         db.update(&fork.to_ops())?;
         Ok(())
@@ -285,3 +285,194 @@ fn test_state_manager_list() -> Result<(), StateManagerError> {
 
     Ok(())
 }
+
+#[test]
+fn test_mpn_account_proof() -> Result<(), StateManagerError> {
+    let mut db = RamKvStore::new();
+
+    let c0 =
+        ContractId::from_str("0x0000000000000000000000000000000000000000000000000000000000000000")
+            .unwrap();
+    db.update(&[WriteOp::Put(
+        format!("CON-{}", c0).into(),
+        empty_contract::<PoseidonHasher>(ZkStateModel::List {
+            log4_size: 2,
+            item_type: Box::new(ZkStateModel::Struct {
+                field_types: vec![
+                    ZkStateModel::Scalar,
+                    ZkStateModel::Scalar,
+                    ZkStateModel::Scalar,
+                    ZkStateModel::Scalar,
+                    ZkStateModel::List {
+                        log4_size: 1,
+                        item_type: Box::new(ZkStateModel::Struct {
+                            field_types: vec![ZkStateModel::Scalar, ZkStateModel::Scalar],
+                        }),
+                    },
+                ],
+            }),
+        })
+        .into(),
+    )])?;
+
+    let token_id =
+        ContractId::from_str("0x0000000000000000000000000000000000000000000000000000000000000001")
+            .unwrap();
+    let mut accounts = Vec::new();
+    let mut size = 0;
+    for i in 0..3u64 {
+        let acc = MpnAccount {
+            tx_nonce: i as u32,
+            withdraw_nonce: 1,
+            address: jubjub::PointAffine(ZkScalar::from(i + 10), ZkScalar::from(i + 20)),
+            tokens: [
+                (0, Money::new(ContractId::Ziesha, 100 * i)),
+                (3, Money::new(token_id, 7)),
+            ]
+            .into(),
+        };
+        KvStoreStateManager::<PoseidonHasher>::set_mpn_account(
+            &mut db,
+            c0,
+            i * 5,
+            acc.clone(),
+            &mut size,
+        )?;
+        accounts.push((i * 5, acc));
+    }
+    let state = ZkCompressedState::new(
+        KvStoreStateManager::<PoseidonHasher>::get_data(&db, c0, &ZkDataLocator(vec![]))?,
+        size,
+    );
+
+    for (index, acc) in accounts.iter() {
+        let proof = MpnAccountProof {
+            index: *index,
+            proof: KvStoreStateManager::<PoseidonHasher>::prove(
+                &db,
+                c0,
+                ZkDataLocator(vec![]),
+                *index,
+            )?,
+            token_proofs: acc
+                .tokens
+                .keys()
+                .map(|i| {
+                    KvStoreStateManager::<PoseidonHasher>::prove(
+                        &db,
+                        c0,
+                        ZkDataLocator(vec![*index, 4]),
+                        *i,
+                    )
+                    .map(|p| (*i, p))
+                })
+                .collect::<Result<_, _>>()?,
+        };
+        assert!(proof.verify::<PoseidonHasher>(acc, &state, 2, 1));
+        assert!(!proof.verify::<PoseidonHasher>(acc, &state, 3, 1));
+
+        let mut richer = acc.clone();
+        richer.tokens.get_mut(&3).unwrap().amount = Amount(8);
+        assert!(!proof.verify::<PoseidonHasher>(&richer, &state, 2, 1));
+
+        let mut moved = proof.clone();
+        moved.index += 1;
+        assert!(!moved.verify::<PoseidonHasher>(acc, &state, 2, 1));
+
+        let mut missing_token = proof.clone();
+        missing_token.token_proofs.remove(&0);
+        assert!(!missing_token.verify::<PoseidonHasher>(acc, &state, 2, 1));
+    }
+
+    // Absence is proven by an empty slot, but not by an occupied one
+    for (index, empty) in [(1, true), (5, false)] {
+        let proof = MpnAccountProof {
+            index,
+            proof: KvStoreStateManager::<PoseidonHasher>::prove(
+                &db,
+                c0,
+                ZkDataLocator(vec![]),
+                index,
+            )?,
+            token_proofs: HashMap::new(),
+        };
+        assert_eq!(
+            proof.verify::<PoseidonHasher>(&MpnAccount::default(), &state, 2, 1),
+            empty
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_state_manager_deltas() -> Result<(), StateManagerError> {
+    let mut db = RamKvStore::new();
+
+    let c0 =
+        ContractId::from_str("0x0000000000000000000000000000000000000000000000000000000000000000")
+            .unwrap();
+    let model = ZkStateModel::List {
+        log4_size: 3,
+        item_type: Box::new(ZkStateModel::Struct {
+            field_types: vec![ZkStateModel::Scalar, ZkStateModel::Scalar],
+        }),
+    };
+    db.update(&[WriteOp::Put(
+        format!("CON-{}", c0).into(),
+        empty_contract::<PoseidonHasher>(model.clone()).into(),
+    )])?;
+
+    let deltas = (0..5u64)
+        .map(|i| {
+            ZkDeltaPairs(
+                [
+                    (ZkDataLocator(vec![i * 7, 0]), Some(ZkScalar::from(i + 1))),
+                    (ZkDataLocator(vec![i * 3, 1]), Some(ZkScalar::from(i + 2))),
+                    (ZkDataLocator(vec![i, 1]), None),
+                ]
+                .into(),
+            )
+        })
+        .collect::<Vec<_>>();
+    for (i, delta) in deltas.iter().enumerate() {
+        KvStoreStateManager::<PoseidonHasher>::update_contract(&mut db, c0, delta, i as u64 + 1)?;
+    }
+    assert!(matches!(
+        KvStoreStateManager::<PoseidonHasher>::delta_of(&db, c0, 6),
+        Err(StateManagerError::ZkError(ZkError::DeltaNotFound))
+    ));
+
+    let mut state = ZkState::default();
+    for (i, delta) in deltas.iter().enumerate() {
+        let (stored, root) =
+            KvStoreStateManager::<PoseidonHasher>::delta_of(&db, c0, i as u64 + 1)?;
+        assert_eq!(&stored, delta);
+        state.apply_delta(&stored);
+        assert_eq!(state.data.compress::<PoseidonHasher>(model.clone())?, root);
+    }
+
+    let mut paged = ZkDataPairs::default();
+    let mut after = None;
+    let mut pages = 0;
+    loop {
+        let (data, next) =
+            KvStoreStateManager::<PoseidonHasher>::get_data_pairs(&db, c0, after.as_ref(), 3)?;
+        assert!(data.0.len() <= 3);
+        assert!(data.0.keys().all(|loc| !paged.0.contains_key(loc)));
+        paged.0.extend(data.0);
+        pages += 1;
+        after = next;
+        if after.is_none() {
+            break;
+        }
+    }
+    assert_eq!(pages, 3);
+    assert_eq!(paged.0.len(), 8);
+    assert_eq!(
+        paged.compress::<PoseidonHasher>(model)?,
+        KvStoreStateManager::<PoseidonHasher>::root(&db, c0)?
+    );
+
+    Ok(())
+}